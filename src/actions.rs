@@ -0,0 +1,524 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::{self, value, Deserializer, IntoDeserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{
+    ElementState, Event, GamepadButton, GamepadId, ModifiersState, MouseButton, VirtualKeyCode,
+};
+
+// -----------------------------------------------------------------------------
+//     - Error -
+// -----------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    /// A binding that is not a known key or mouse button.
+    Invalid(String),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingsError::Io(e) => write!(f, "failed to read bindings: {}", e),
+            BindingsError::Toml(e) => write!(f, "invalid bindings file: {}", e),
+            BindingsError::Invalid(binding) => write!(f, "invalid binding: {}", binding),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BindingsError::Io(e) => Some(e),
+            BindingsError::Toml(e) => Some(e),
+            BindingsError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BindingsError {
+    fn from(e: std::io::Error) -> Self {
+        BindingsError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for BindingsError {
+    fn from(e: toml::de::Error) -> Self {
+        BindingsError::Toml(e)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Binding -
+// -----------------------------------------------------------------------------
+/// Something that can be pressed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Button {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    /// A button on any gamepad.
+    Gamepad(GamepadButton),
+}
+
+/// A button, and the modifier keys that have to be held along with it.
+///
+/// Written as the modifiers and the button joined by `+`, like `Ctrl+Shift+S`.
+/// Keys are named like [`VirtualKeyCode`], mouse buttons are `MouseLeft`,
+/// `MouseRight`, `MouseMiddle` or `Mouse` followed by a number, and gamepad
+/// buttons are `Gamepad` followed by a [`GamepadButton`], like `GamepadSouth`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub button: Button,
+    pub modifiers: ModifiersState,
+}
+
+impl Binding {
+    pub fn key(key: VirtualKeyCode) -> Self {
+        Self {
+            button: Button::Key(key),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self {
+            button: Button::Mouse(button),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    pub fn gamepad(button: GamepadButton) -> Self {
+        Self {
+            button: Button::Gamepad(button),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    /// The same binding, but only while these modifiers are held.
+    pub fn with(mut self, modifiers: ModifiersState) -> Self {
+        self.modifiers |= modifiers;
+        self
+    }
+}
+
+const MODIFIERS: [(&str, ModifiersState); 4] = [
+    ("Ctrl", ModifiersState::CTRL),
+    ("Shift", ModifiersState::SHIFT),
+    ("Alt", ModifiersState::ALT),
+    ("Logo", ModifiersState::LOGO),
+];
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, modifier) in &MODIFIERS {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.button {
+            Button::Key(key) => write!(f, "{:?}", key),
+            Button::Mouse(MouseButton::Left) => write!(f, "MouseLeft"),
+            Button::Mouse(MouseButton::Right) => write!(f, "MouseRight"),
+            Button::Mouse(MouseButton::Middle) => write!(f, "MouseMiddle"),
+            Button::Mouse(MouseButton::Other(n)) => write!(f, "Mouse{}", n),
+            Button::Gamepad(button) => write!(f, "Gamepad{:?}", button),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = BindingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BindingsError::Invalid(s.to_string());
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+        let button = parts.pop().ok_or_else(invalid)?;
+
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            let (_, modifier) = MODIFIERS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))
+                .ok_or_else(invalid)?;
+            modifiers |= *modifier;
+        }
+
+        let button = match button {
+            "MouseLeft" => Button::Mouse(MouseButton::Left),
+            "MouseRight" => Button::Mouse(MouseButton::Right),
+            "MouseMiddle" => Button::Mouse(MouseButton::Middle),
+            _ => match button.strip_prefix("Mouse").map(str::parse) {
+                Some(Ok(n)) => Button::Mouse(MouseButton::Other(n)),
+                _ => match button.strip_prefix("Gamepad") {
+                    Some(name) => Button::Gamepad(variant(name).ok_or_else(invalid)?),
+                    None => Button::Key(variant(button).ok_or_else(invalid)?),
+                },
+            },
+        };
+
+        Ok(Self { button, modifiers })
+    }
+}
+
+// Parse an enum variant by its name
+fn variant<'de, T: Deserialize<'de>>(name: &'de str) -> Option<T> {
+    let name: value::StrDeserializer<'_, value::Error> = name.into_deserializer();
+    T::deserialize(name).ok()
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Actions -
+// -----------------------------------------------------------------------------
+/// Named actions, like "jump" or "fire", bound to buttons.
+///
+/// Pass every event to [`Actions::handle`], query the actions during the
+/// update and call [`Actions::end_frame`] after it.
+///
+/// When a button is pressed, only the most specific bindings fire:
+/// with `S` bound to "down" and `Ctrl+S` to "save", pressing ctrl + s
+/// saves without moving down, but shift + s still moves down.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Actions {
+    bindings: BTreeMap<String, Vec<Binding>>,
+    modifiers: ModifiersState,
+    // Buttons that are down, the gamepad they are on, and the action each of them started
+    held: Vec<(Button, Option<GamepadId>, String)>,
+    pressed: Vec<String>,
+    released: Vec<String>,
+}
+
+impl Actions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load bindings from a TOML file, with a list of bindings per action:
+    ///
+    /// ```toml
+    /// jump = ["Space", "Up"]
+    /// save = ["Ctrl+S"]
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, BindingsError> {
+        Ok(Self {
+            bindings: toml::from_str(toml)?,
+            ..Self::default()
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_toml())
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(&self.bindings).expect("bindings are always valid TOML")
+    }
+
+    /// Add a binding to an action.
+    pub fn bind(&mut self, action: impl Into<String>, binding: Binding) {
+        let bindings = self.bindings.entry(action.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Remove all bindings of an action, for instance before rebinding it.
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+        self.release(|_, _, held| held == action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// All actions with their bindings, sorted by name.
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[Binding])> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| (action.as_str(), bindings.as_slice()))
+    }
+
+    /// The action started this frame.
+    pub fn pressed(&self, action: &str) -> bool {
+        self.pressed.iter().any(|a| a == action)
+    }
+
+    /// The action stopped this frame.
+    pub fn released(&self, action: &str) -> bool {
+        self.released.iter().any(|a| a == action)
+    }
+
+    /// Any of the action's buttons is down.
+    pub fn held(&self, action: &str) -> bool {
+        self.held.iter().any(|(_, _, a)| a == action)
+    }
+
+    pub fn handle(&mut self, event: &Event) {
+        let (button, pad, state) = match *event {
+            Event::Key(key) => {
+                self.modifiers = key.modifiers;
+                match key.key {
+                    Some(code) => (Button::Key(code), None, key.state),
+                    None => return,
+                }
+            }
+            Event::MouseButton(button, state) => (Button::Mouse(button), None, state),
+            Event::GamepadButton(id, button, state) => (Button::Gamepad(button), Some(id), state),
+            // Its buttons won't be released
+            Event::GamepadDisconnected(id) => {
+                self.release(|_, held_pad, _| held_pad == Some(id));
+                return;
+            }
+            _ => return,
+        };
+
+        match state {
+            ElementState::Pressed => self.press(button, pad),
+            ElementState::Released => {
+                self.release(|held, held_pad, _| held == button && held_pad == pad)
+            }
+        }
+    }
+
+    /// Forget what was pressed and released this frame.
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    /// Release every action, for instance when the window loses focus
+    /// and the release events would never arrive.
+    pub fn release_all(&mut self) {
+        self.release(|_, _, _| true);
+    }
+
+    fn press(&mut self, button: Button, pad: Option<GamepadId>) {
+        // Key repeat
+        if self
+            .held
+            .iter()
+            .any(|(held, held_pad, _)| *held == button && *held_pad == pad)
+        {
+            return;
+        }
+
+        let modifiers = self.modifiers;
+        let matches = self
+            .bindings
+            .iter()
+            .filter_map(|(action, bindings)| {
+                bindings
+                    .iter()
+                    .filter(|b| b.button == button && modifiers.contains(b.modifiers))
+                    .map(|b| b.modifiers.bits().count_ones())
+                    .max()
+                    .map(|specificity| (action, specificity))
+            })
+            .collect::<Vec<_>>();
+
+        let most_specific = matches.iter().map(|(_, s)| *s).max();
+        for (action, specificity) in &matches {
+            if Some(*specificity) != most_specific {
+                continue;
+            }
+            if !self.held(action) {
+                self.pressed.push(action.to_string());
+            }
+            self.held.push((button, pad, action.to_string()));
+        }
+    }
+
+    fn release(&mut self, mut f: impl FnMut(Button, Option<GamepadId>, &str) -> bool) {
+        let (released, held) = self
+            .held
+            .drain(..)
+            .partition::<Vec<_>, _>(|(button, pad, action)| f(*button, *pad, action));
+        self.held = held;
+
+        for (_, _, action) in released {
+            if !self.held(&action) && !self.released(&action) {
+                self.released.push(action);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KeyEvent;
+
+    fn key(key: VirtualKeyCode, state: ElementState, modifiers: ModifiersState) -> Event {
+        Event::Key(KeyEvent {
+            scancode: 0,
+            state,
+            key: Some(key),
+            modifiers,
+        })
+    }
+
+    fn press(actions: &mut Actions, code: VirtualKeyCode, modifiers: ModifiersState) {
+        actions.handle(&key(code, ElementState::Pressed, modifiers));
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let binding: Binding = "ctrl+Shift+S".parse().unwrap();
+        assert_eq!(binding.button, Button::Key(VirtualKeyCode::S));
+        assert_eq!(
+            binding.modifiers,
+            ModifiersState::CTRL | ModifiersState::SHIFT
+        );
+        assert_eq!(binding.to_string(), "Ctrl+Shift+S");
+
+        let binding: Binding = "Alt+Mouse4".parse().unwrap();
+        assert_eq!(
+            binding,
+            Binding::mouse(MouseButton::Other(4)).with(ModifiersState::ALT)
+        );
+        assert_eq!(binding.to_string(), "Alt+Mouse4");
+
+        let binding: Binding = "GamepadDPadUp".parse().unwrap();
+        assert_eq!(binding, Binding::gamepad(GamepadButton::DPadUp));
+        assert_eq!(binding.to_string(), "GamepadDPadUp");
+
+        assert!("Hyper+S".parse::<Binding>().is_err());
+        assert!("GamepadA".parse::<Binding>().is_err());
+        assert!("Nope".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn pressed_held_and_released() {
+        let mut actions = Actions::new();
+        actions.bind("jump", Binding::key(VirtualKeyCode::Space));
+        actions.bind("jump", Binding::mouse(MouseButton::Left));
+        let none = ModifiersState::empty();
+
+        press(&mut actions, VirtualKeyCode::Space, none);
+        assert!(actions.pressed("jump"));
+        assert!(actions.held("jump"));
+        actions.end_frame();
+
+        // Key repeat and a second button don't press it again
+        press(&mut actions, VirtualKeyCode::Space, none);
+        actions.handle(&Event::MouseButton(
+            MouseButton::Left,
+            ElementState::Pressed,
+        ));
+        actions.handle(&key(VirtualKeyCode::Space, ElementState::Released, none));
+        assert!(!actions.pressed("jump"));
+        assert!(!actions.released("jump"));
+        assert!(actions.held("jump"));
+
+        actions.release_all();
+        assert!(actions.released("jump"));
+        assert!(!actions.held("jump"));
+    }
+
+    #[test]
+    fn most_specific_chord_wins() {
+        let mut actions = Actions::new();
+        actions.bind("down", Binding::key(VirtualKeyCode::S));
+        actions.bind(
+            "save",
+            Binding::key(VirtualKeyCode::S).with(ModifiersState::CTRL),
+        );
+
+        press(&mut actions, VirtualKeyCode::S, ModifiersState::CTRL);
+        assert!(actions.pressed("save"));
+        assert!(!actions.pressed("down"));
+
+        // Releasing ctrl first still releases the chord
+        actions.handle(&key(
+            VirtualKeyCode::S,
+            ElementState::Released,
+            ModifiersState::empty(),
+        ));
+        assert!(actions.released("save"));
+        actions.end_frame();
+
+        press(&mut actions, VirtualKeyCode::S, ModifiersState::SHIFT);
+        assert!(actions.pressed("down"));
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let toml = "fire = [\"MouseLeft\", \"Ctrl+F\"]\njump = [\"Space\"]\n";
+        let actions = Actions::from_toml(toml).unwrap();
+        assert_eq!(
+            actions.bindings("jump"),
+            &[Binding::key(VirtualKeyCode::Space)]
+        );
+        assert_eq!(actions.actions().count(), 2);
+        assert_eq!(Actions::from_toml(&actions.to_toml()).unwrap(), actions);
+
+        assert!(Actions::from_toml("jump = [\"Spacebar\"]").is_err());
+    }
+
+    #[test]
+    fn gamepad_buttons() {
+        let mut actions = Actions::new();
+        actions.bind("jump", Binding::gamepad(GamepadButton::South));
+        let pad = GamepadId(1);
+
+        actions.handle(&Event::GamepadButton(
+            pad,
+            GamepadButton::South,
+            ElementState::Pressed,
+        ));
+        assert!(actions.pressed("jump"));
+
+        // Unplugging the gamepad releases its buttons
+        actions.handle(&Event::GamepadDisconnected(pad));
+        assert!(actions.released("jump"));
+        assert!(!actions.held("jump"));
+    }
+
+    #[test]
+    fn gamepads_are_released_separately() {
+        let mut actions = Actions::new();
+        actions.bind("jump", Binding::gamepad(GamepadButton::South));
+        for id in 0..2 {
+            actions.handle(&Event::GamepadButton(
+                GamepadId(id),
+                GamepadButton::South,
+                ElementState::Pressed,
+            ));
+        }
+
+        actions.handle(&Event::GamepadButton(
+            GamepadId(0),
+            GamepadButton::South,
+            ElementState::Released,
+        ));
+        actions.handle(&Event::GamepadDisconnected(GamepadId(2)));
+        assert!(actions.held("jump"));
+
+        actions.handle(&Event::GamepadDisconnected(GamepadId(1)));
+        assert!(!actions.held("jump"));
+        assert!(actions.released("jump"));
+    }
+}
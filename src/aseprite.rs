@@ -0,0 +1,923 @@
+//! Import sprites and animations from [Aseprite](https://www.aseprite.org) files.
+//!
+//! RGBA, grayscale and indexed files are supported. Cels are decoded to
+//! [`Pixel`]s when the file is loaded, and frames are flattened on request.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), tinypixel::aseprite::AsepriteError> {
+//! let sprite = tinypixel::aseprite::Aseprite::load("player.aseprite")?;
+//! let walk = sprite.animation("walk").unwrap();
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use flate2::read::ZlibDecoder;
+
+use crate::{Image, Palette, Pixel, ScreenSize, Vec2D};
+
+const HEADER_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_BACKGROUND: u16 = 8;
+const HEADER_LAYER_OPACITY: u32 = 1;
+
+// -----------------------------------------------------------------------------
+//     - Error -
+// -----------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum AsepriteError {
+    Io(std::io::Error),
+    /// The file uses a feature that can not be loaded.
+    Unsupported(String),
+    /// The file is malformed.
+    Invalid(String),
+}
+
+impl fmt::Display for AsepriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsepriteError::Io(e) => write!(f, "failed to read file: {}", e),
+            AsepriteError::Unsupported(feature) => {
+                write!(f, "unsupported Aseprite feature: {}", feature)
+            }
+            AsepriteError::Invalid(reason) => write!(f, "invalid Aseprite file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AsepriteError::Io(e) => Some(e),
+            AsepriteError::Unsupported(_) | AsepriteError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for AsepriteError {
+    fn from(e: std::io::Error) -> Self {
+        AsepriteError::Io(e)
+    }
+}
+
+fn invalid<T>(reason: impl Into<String>) -> Result<T, AsepriteError> {
+    Err(AsepriteError::Invalid(reason.into()))
+}
+
+// -----------------------------------------------------------------------------
+//     - Reader -
+// -----------------------------------------------------------------------------
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], AsepriteError> {
+        match self.data.get(self.pos..self.pos + len) {
+            Some(bytes) => {
+                self.pos += len;
+                Ok(bytes)
+            }
+            None => invalid("unexpected end of file"),
+        }
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), AsepriteError> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, AsepriteError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AsepriteError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, AsepriteError> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, AsepriteError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, AsepriteError> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Aseprite -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColourMode {
+    Rgba,
+    Grayscale,
+    Indexed,
+}
+
+impl ColourMode {
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            ColourMode::Rgba => 4,
+            ColourMode::Grayscale => 2,
+            ColourMode::Indexed => 1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayerKind {
+    Normal,
+    Group,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub kind: LayerKind,
+    /// Nesting depth inside groups, zero for top level layers.
+    pub child_level: u16,
+    pub visible: bool,
+    pub background: bool,
+    pub opacity: u8,
+    /// Aseprite blend mode, where zero is normal.
+    /// Other blend modes are drawn as normal.
+    pub blend_mode: u16,
+}
+
+/// The image of one layer in one frame.
+#[derive(Debug, Clone)]
+pub struct Cel {
+    /// Index into [`Aseprite::layers`].
+    pub layer: usize,
+    /// Position of the image inside the frame.
+    pub pos: Vec2D<i32>,
+    pub opacity: u8,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub duration: Duration,
+    pub cels: Vec<Cel>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/// A named range of frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    /// First frame, inclusive.
+    pub from: usize,
+    /// Last frame, inclusive.
+    pub to: usize,
+    pub direction: Direction,
+    /// Number of times to play the tag, zero means forever.
+    pub repeat: u16,
+}
+
+impl Tag {
+    /// Frame indices for one pass through the tag, in playing order.
+    /// Ping-pong tags do not repeat the frames at either end.
+    pub fn frames(&self) -> Vec<usize> {
+        let forward = (self.from..=self.to).collect::<Vec<_>>();
+        let reverse = forward.iter().rev().copied().collect::<Vec<_>>();
+        let inner = |frames: &[usize]| {
+            let len = frames.len().saturating_sub(2);
+            frames.iter().skip(1).take(len).copied().collect::<Vec<_>>()
+        };
+
+        match self.direction {
+            Direction::Forward => forward,
+            Direction::Reverse => reverse,
+            Direction::PingPong => [forward.clone(), inner(&reverse)].concat(),
+            Direction::PingPongReverse => [reverse.clone(), inner(&forward)].concat(),
+        }
+    }
+}
+
+/// A parsed Aseprite file.
+#[derive(Debug, Clone)]
+pub struct Aseprite {
+    pub size: ScreenSize,
+    pub colour_mode: ColourMode,
+    pub palette: Palette,
+    /// The palette index drawn as transparent in indexed files.
+    pub transparent_index: u8,
+    pub layers: Vec<Layer>,
+    pub frames: Vec<Frame>,
+    pub tags: Vec<Tag>,
+}
+
+// A cel before its pixels are decoded, as the palette
+// might not have been read yet.
+enum RawCel {
+    Image {
+        layer: usize,
+        pos: Vec2D<i32>,
+        opacity: u8,
+        size: ScreenSize,
+        data: Vec<u8>,
+    },
+    Linked {
+        layer: usize,
+        frame: usize,
+    },
+}
+
+impl Aseprite {
+    /// Load an `.ase` or `.aseprite` file from disk.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AsepriteError> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Parse an Aseprite file.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, AsepriteError> {
+        let mut reader = Reader::new(data);
+
+        // Header
+        reader.skip(4)?;
+        if reader.u16()? != HEADER_MAGIC {
+            return invalid("not an Aseprite file");
+        }
+        let frame_count = reader.u16()? as usize;
+        let size = ScreenSize::new(reader.u16()? as u32, reader.u16()? as u32);
+        let colour_mode = match reader.u16()? {
+            32 => ColourMode::Rgba,
+            16 => ColourMode::Grayscale,
+            8 => ColourMode::Indexed,
+            depth => return invalid(format!("unknown colour depth {}", depth)),
+        };
+        let flags = reader.u32()?;
+        reader.skip(2 + 4 + 4)?;
+        let transparent_index = reader.u8()?;
+        reader.skip(128 - 29)?;
+
+        let mut aseprite = Self {
+            size,
+            colour_mode,
+            palette: Palette::new(Vec::new()),
+            transparent_index,
+            layers: Vec::new(),
+            frames: Vec::with_capacity(frame_count),
+            tags: Vec::new(),
+        };
+
+        let mut raw_frames = Vec::with_capacity(frame_count);
+        let mut has_new_palette = false;
+
+        for _ in 0..frame_count {
+            let start = reader.pos;
+            let frame_size = reader.u32()? as usize;
+            if reader.u16()? != FRAME_MAGIC {
+                return invalid("bad frame magic number");
+            }
+            let old_chunks = reader.u16()? as usize;
+            let duration = Duration::from_millis(reader.u16()? as u64);
+            reader.skip(2)?;
+            let chunks = match reader.u32()? as usize {
+                0 => old_chunks,
+                chunks => chunks,
+            };
+
+            let mut cels = Vec::new();
+            for _ in 0..chunks {
+                let chunk_start = reader.pos;
+                let chunk_size = reader.u32()? as usize;
+                let kind = reader.u16()?;
+                if chunk_size < 6 {
+                    return invalid("chunk is too small");
+                }
+                let mut chunk = Reader::new(reader.bytes(chunk_size - 6)?);
+
+                match kind {
+                    CHUNK_OLD_PALETTE if !has_new_palette => {
+                        aseprite.read_old_palette(&mut chunk)?
+                    }
+                    CHUNK_PALETTE => {
+                        has_new_palette = true;
+                        aseprite.read_palette(&mut chunk)?;
+                    }
+                    CHUNK_LAYER => {
+                        let layer = Self::read_layer(&mut chunk, flags)?;
+                        aseprite.layers.push(layer);
+                    }
+                    CHUNK_CEL => cels.push(aseprite.read_cel(&mut chunk)?),
+                    CHUNK_TAGS => aseprite.read_tags(&mut chunk)?,
+                    _ => {}
+                }
+
+                reader.pos = chunk_start + chunk_size;
+            }
+
+            raw_frames.push((duration, cels));
+            reader.pos = start + frame_size;
+        }
+
+        for (index, (duration, cels)) in raw_frames.into_iter().enumerate() {
+            let cels = cels
+                .into_iter()
+                .map(|cel| aseprite.decode_cel(cel, index))
+                .collect::<Result<_, _>>()?;
+            aseprite.frames.push(Frame { duration, cels });
+        }
+
+        Ok(aseprite)
+    }
+
+    fn read_old_palette(&mut self, chunk: &mut Reader) -> Result<(), AsepriteError> {
+        let mut colours = self.palette.colours().to_vec();
+        let mut index = 0;
+
+        for _ in 0..chunk.u16()? {
+            index += chunk.u8()? as usize;
+            let count = match chunk.u8()? {
+                0 => 256,
+                count => count as usize,
+            };
+
+            for _ in 0..count {
+                let rgb = chunk.bytes(3)?;
+                if colours.len() <= index {
+                    colours.resize(index + 1, Pixel::zero());
+                }
+                colours[index] = Pixel::rgb(rgb[0], rgb[1], rgb[2]);
+                index += 1;
+            }
+        }
+
+        self.palette = Palette::new(colours);
+        Ok(())
+    }
+
+    fn read_palette(&mut self, chunk: &mut Reader) -> Result<(), AsepriteError> {
+        let size = chunk.u32()? as usize;
+        let first = chunk.u32()? as usize;
+        let last = chunk.u32()? as usize;
+        chunk.skip(8)?;
+
+        // Palette indices are a single byte
+        if size > 256 || first > last || last >= size {
+            return invalid("palette is out of range");
+        }
+
+        let mut colours = self.palette.colours().to_vec();
+        colours.resize(size, Pixel::zero());
+
+        for index in first..=last {
+            let flags = chunk.u16()?;
+            let rgba = chunk.bytes(4)?;
+            if flags & 1 != 0 {
+                chunk.string()?;
+            }
+            if let Some(colour) = colours.get_mut(index) {
+                *colour = Pixel::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+            }
+        }
+
+        self.palette = Palette::new(colours);
+        Ok(())
+    }
+
+    fn read_layer(chunk: &mut Reader, header_flags: u32) -> Result<Layer, AsepriteError> {
+        let flags = chunk.u16()?;
+        let kind = match chunk.u16()? {
+            0 => LayerKind::Normal,
+            1 => LayerKind::Group,
+            2 => {
+                return Err(AsepriteError::Unsupported("tilemap layers".into()));
+            }
+            kind => return invalid(format!("unknown layer type {}", kind)),
+        };
+        let child_level = chunk.u16()?;
+        chunk.skip(4)?;
+        let blend_mode = chunk.u16()?;
+        let opacity = chunk.u8()?;
+        chunk.skip(3)?;
+        let name = chunk.string()?;
+
+        Ok(Layer {
+            name,
+            kind,
+            child_level,
+            visible: flags & LAYER_VISIBLE != 0,
+            background: flags & LAYER_BACKGROUND != 0,
+            opacity: if header_flags & HEADER_LAYER_OPACITY != 0 {
+                opacity
+            } else {
+                255
+            },
+            blend_mode,
+        })
+    }
+
+    fn read_cel(&self, chunk: &mut Reader) -> Result<RawCel, AsepriteError> {
+        let layer = chunk.u16()? as usize;
+        let pos = Vec2D::new(chunk.i16()? as i32, chunk.i16()? as i32);
+        let opacity = chunk.u8()?;
+        let kind = chunk.u16()?;
+        chunk.skip(2 + 5)?;
+
+        let cel = match kind {
+            0 | 2 => {
+                let size = ScreenSize::new(chunk.u16()? as u32, chunk.u16()? as u32);
+                let len = (size.width * size.height) as usize * self.colour_mode.bytes_per_pixel();
+                let rest = &chunk.data[chunk.pos..];
+
+                let data = if kind == 0 {
+                    chunk.bytes(len)?.to_vec()
+                } else {
+                    let mut data = Vec::new();
+                    ZlibDecoder::new(rest).take(len as u64).read_to_end(&mut data)?;
+                    data
+                };
+
+                if data.len() < len {
+                    return invalid("cel image data is too short");
+                }
+
+                RawCel::Image {
+                    layer,
+                    pos,
+                    opacity,
+                    size,
+                    data,
+                }
+            }
+            1 => RawCel::Linked {
+                layer,
+                frame: chunk.u16()? as usize,
+            },
+            3 => return Err(AsepriteError::Unsupported("tilemap cels".into())),
+            kind => return invalid(format!("unknown cel type {}", kind)),
+        };
+
+        Ok(cel)
+    }
+
+    fn read_tags(&mut self, chunk: &mut Reader) -> Result<(), AsepriteError> {
+        let count = chunk.u16()?;
+        chunk.skip(8)?;
+
+        for _ in 0..count {
+            let from = chunk.u16()? as usize;
+            let to = chunk.u16()? as usize;
+            let direction = match chunk.u8()? {
+                0 => Direction::Forward,
+                1 => Direction::Reverse,
+                2 => Direction::PingPong,
+                3 => Direction::PingPongReverse,
+                direction => return invalid(format!("unknown tag direction {}", direction)),
+            };
+            let repeat = chunk.u16()?;
+            chunk.skip(6 + 3 + 1)?;
+            let name = chunk.string()?;
+
+            self.tags.push(Tag {
+                name,
+                from,
+                to,
+                direction,
+                repeat,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn decode_cel(&self, cel: RawCel, frame: usize) -> Result<Cel, AsepriteError> {
+        match cel {
+            RawCel::Image {
+                layer,
+                pos,
+                opacity,
+                size,
+                data,
+            } => {
+                let background = self
+                    .layers
+                    .get(layer)
+                    .map(|l| l.background)
+                    .unwrap_or(false);
+                let pixels = data
+                    .chunks_exact(self.colour_mode.bytes_per_pixel())
+                    .take((size.width * size.height) as usize)
+                    .map(|p| match self.colour_mode {
+                        ColourMode::Rgba => Pixel::new(p[0], p[1], p[2], p[3]),
+                        ColourMode::Grayscale => Pixel::new(p[0], p[0], p[0], p[1]),
+                        // The background layer has no transparency
+                        ColourMode::Indexed if p[0] == self.transparent_index && !background => {
+                            Pixel::zero()
+                        }
+                        ColourMode::Indexed => self.palette.get(p[0]),
+                    })
+                    .collect();
+
+                Ok(Cel {
+                    layer,
+                    pos,
+                    opacity,
+                    image: Image::from_pixels(size, pixels),
+                })
+            }
+            RawCel::Linked {
+                layer,
+                frame: linked,
+            } => {
+                if linked >= frame {
+                    return invalid("linked cel does not refer to an earlier frame");
+                }
+
+                self.frames[linked]
+                    .cels
+                    .iter()
+                    .find(|cel| cel.layer == layer)
+                    .cloned()
+                    .ok_or_else(|| AsepriteError::Invalid("linked cel is missing".into()))
+            }
+        }
+    }
+
+    /// Find a tag by name.
+    pub fn tag(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Flatten all visible layers of a frame into a single image.
+    /// Layers inside hidden groups are not drawn.
+    pub fn frame(&self, index: usize) -> Image {
+        let mut image = Image::empty(self.size);
+        let frame = match self.frames.get(index) {
+            Some(frame) => frame,
+            None => return image,
+        };
+
+        let visible = self.visible_layers();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if !visible[index] {
+                continue;
+            }
+
+            let opacity = layer.opacity as u32;
+            for cel in frame.cels.iter().filter(|cel| cel.layer == index) {
+                let opacity = opacity * cel.opacity as u32 / 255;
+                draw_cel(&mut image, cel, opacity as u8);
+            }
+        }
+
+        image
+    }
+
+    /// Flatten every frame, along with its duration.
+    pub fn flatten(&self) -> Vec<(Image, Duration)> {
+        (0..self.frames.len())
+            .map(|index| (self.frame(index), self.frames[index].duration))
+            .collect()
+    }
+
+    /// The frames of a tag in playing order, along with their durations.
+    pub fn animation(&self, tag: &str) -> Option<Vec<(Image, Duration)>> {
+        let tag = self.tag(tag)?;
+        let frames = tag
+            .frames()
+            .into_iter()
+            .filter(|index| *index < self.frames.len())
+            .map(|index| (self.frame(index), self.frames[index].duration))
+            .collect();
+        Some(frames)
+    }
+
+    // A layer is visible if it and all the groups it is nested in are visible.
+    // Layers are stored parents first, so a stack of group visibility is enough.
+    fn visible_layers(&self) -> Vec<bool> {
+        let mut groups: Vec<bool> = Vec::new();
+
+        self.layers
+            .iter()
+            .map(|layer| {
+                groups.truncate(layer.child_level as usize);
+                let visible = layer.visible && groups.iter().all(|v| *v);
+                if layer.kind == LayerKind::Group {
+                    groups.push(visible);
+                }
+                visible && layer.kind == LayerKind::Normal
+            })
+            .collect()
+    }
+}
+
+fn draw_cel(image: &mut Image, cel: &Cel, opacity: u8) {
+    for (pos, src) in cel.image.enumerate_pixels() {
+        let x = pos.x as i32 + cel.pos.x;
+        let y = pos.y as i32 + cel.pos.y;
+        if x < 0 || y < 0 {
+            continue;
+        }
+
+        if let Some(dst) = image.get(x as u32, y as u32) {
+            image.set(x as u32, y as u32, blend(dst, *src, opacity));
+        }
+    }
+}
+
+// Draw `src` over `dst`
+fn blend(dst: Pixel, src: Pixel, opacity: u8) -> Pixel {
+    let src_a = src.a as f32 / 255.0 * opacity as f32 / 255.0;
+    if src_a <= 0.0 {
+        return dst;
+    }
+
+    let dst_a = dst.a as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    let channel = |s: u8, d: u8| {
+        let c = (s as f32 * src_a + d as f32 * dst_a * (1.0 - src_a)) / out_a;
+        c.round() as u8
+    };
+
+    Pixel::new(
+        channel(src.r, dst.r),
+        channel(src.g, dst.g),
+        channel(src.b, dst.b),
+        (out_a * 255.0).round() as u8,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    const RED: Pixel = Pixel::new(255, 0, 0, 255);
+    const BLUE: Pixel = Pixel::new(0, 0, 255, 255);
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend_from_slice(&(s.len() as u16).to_le_bytes());
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn chunk(kind: u16, data: &[u8]) -> Vec<u8> {
+        let mut out = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn layer(name: &str, flags: u16, kind: u16, child_level: u16, opacity: u8) -> Vec<u8> {
+        let mut data = Vec::new();
+        for word in &[flags, kind, child_level, 0, 0, 0] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        data.extend_from_slice(&[opacity, 0, 0, 0]);
+        string(&mut data, name);
+        chunk(CHUNK_LAYER, &data)
+    }
+
+    fn cel(layer: u16, x: i16, y: i16, kind: u16, body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&layer.to_le_bytes());
+        data.extend_from_slice(&x.to_le_bytes());
+        data.extend_from_slice(&y.to_le_bytes());
+        data.push(255);
+        data.extend_from_slice(&kind.to_le_bytes());
+        data.extend_from_slice(&[0; 7]);
+        data.extend_from_slice(body);
+        chunk(CHUNK_CEL, &data)
+    }
+
+    fn image_body(width: u16, height: u16, pixels: &[u8], compressed: bool) -> Vec<u8> {
+        let mut body = width.to_le_bytes().to_vec();
+        body.extend_from_slice(&height.to_le_bytes());
+        if compressed {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(pixels).unwrap();
+            body.extend_from_slice(&encoder.finish().unwrap());
+        } else {
+            body.extend_from_slice(pixels);
+        }
+        body
+    }
+
+    fn frame(duration: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut out = ((body.len() + 16) as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(&FRAME_MAGIC.to_le_bytes());
+        out.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+        out.extend_from_slice(&duration.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    fn file(depth: u16, size: (u16, u16), transparent: u8, frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = vec![0; 4];
+        out.extend_from_slice(&HEADER_MAGIC.to_le_bytes());
+        out.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+        out.extend_from_slice(&size.0.to_le_bytes());
+        out.extend_from_slice(&size.1.to_le_bytes());
+        out.extend_from_slice(&depth.to_le_bytes());
+        out.extend_from_slice(&HEADER_LAYER_OPACITY.to_le_bytes());
+        out.extend_from_slice(&[0; 10]);
+        out.push(transparent);
+        out.resize(128, 0);
+        out.extend_from_slice(&frames.concat());
+        let len = out.len() as u32;
+        out[..4].copy_from_slice(&len.to_le_bytes());
+        out
+    }
+
+    fn tags(tags: &[(u16, u16, u8, &str)]) -> Vec<u8> {
+        let mut data = (tags.len() as u16).to_le_bytes().to_vec();
+        data.extend_from_slice(&[0; 8]);
+        for (from, to, direction, name) in tags {
+            data.extend_from_slice(&from.to_le_bytes());
+            data.extend_from_slice(&to.to_le_bytes());
+            data.push(*direction);
+            data.extend_from_slice(&[0; 12]);
+            string(&mut data, name);
+        }
+        chunk(CHUNK_TAGS, &data)
+    }
+
+    fn rgba_file() -> Vec<u8> {
+        let red = [255, 0, 0, 255].repeat(4);
+        let blue = [0, 0, 255, 255];
+
+        file(
+            32,
+            (3, 2),
+            0,
+            &[
+                frame(
+                    100,
+                    &[
+                        layer("body", LAYER_VISIBLE, 0, 0, 255),
+                        layer("hat", LAYER_VISIBLE, 0, 0, 255),
+                        layer("hidden", 0, 0, 0, 255),
+                        tags(&[(0, 2, 0, "walk"), (0, 2, 2, "bounce")]),
+                        cel(0, 0, 0, 0, &image_body(2, 2, &red, false)),
+                        cel(1, 2, 1, 2, &image_body(1, 1, &blue, true)),
+                        cel(2, 0, 0, 0, &image_body(1, 1, &blue, false)),
+                    ],
+                ),
+                frame(200, &[cel(0, 1, 0, 2, &image_body(2, 2, &red, true))]),
+                frame(50, &[cel(0, 0, 0, 1, &1u16.to_le_bytes())]),
+            ],
+        )
+    }
+
+    #[test]
+    fn parse_rgba() {
+        let sprite = Aseprite::from_bytes(&rgba_file()).unwrap();
+        assert_eq!(sprite.size, ScreenSize::new(3, 2));
+        assert_eq!(sprite.colour_mode, ColourMode::Rgba);
+        assert_eq!(sprite.layers.len(), 3);
+        assert_eq!(sprite.frames.len(), 3);
+        assert_eq!(sprite.frames[1].duration, Duration::from_millis(200));
+
+        let first = sprite.frame(0);
+        assert_eq!(first.get(0, 0), Some(RED));
+        assert_eq!(first.get(2, 1), Some(BLUE));
+        assert_eq!(first.get(2, 0), Some(Pixel::zero()));
+
+        let second = sprite.frame(1);
+        assert_eq!(second.get(0, 0), Some(Pixel::zero()));
+        assert_eq!(second.get(2, 1), Some(RED));
+
+        // The third frame links to the cel of the second
+        assert_eq!(sprite.frame(2), second);
+    }
+
+    #[test]
+    fn tags_and_animations() {
+        let sprite = Aseprite::from_bytes(&rgba_file()).unwrap();
+
+        let walk = sprite.tag("walk").unwrap();
+        assert_eq!(walk.direction, Direction::Forward);
+        assert_eq!(walk.frames(), vec![0, 1, 2]);
+
+        let bounce = sprite.tag("bounce").unwrap();
+        assert_eq!(bounce.frames(), vec![0, 1, 2, 1]);
+
+        let animation = sprite.animation("bounce").unwrap();
+        let durations = animation
+            .iter()
+            .map(|(_, d)| d.as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(durations, vec![100, 200, 50, 200]);
+        assert!(sprite.animation("run").is_none());
+    }
+
+    #[test]
+    fn parse_indexed() {
+        let mut palette = Vec::new();
+        for word in &[3u32, 0, 2, 0, 0] {
+            palette.extend_from_slice(&word.to_le_bytes());
+        }
+        for rgba in &[[0, 0, 0, 0], [255, 0, 0, 255], [0, 0, 255, 255]] {
+            palette.extend_from_slice(&[0, 0]);
+            palette.extend_from_slice(rgba);
+        }
+
+        let data = file(
+            8,
+            (2, 1),
+            0,
+            &[frame(
+                100,
+                &[
+                    chunk(CHUNK_PALETTE, &palette),
+                    layer("sprite", LAYER_VISIBLE, 0, 0, 255),
+                    cel(0, 0, 0, 2, &image_body(2, 1, &[0, 2], true)),
+                ],
+            )],
+        );
+
+        let sprite = Aseprite::from_bytes(&data).unwrap();
+        assert_eq!(sprite.colour_mode, ColourMode::Indexed);
+        assert_eq!(sprite.palette.len(), 3);
+
+        let image = sprite.frame(0);
+        assert_eq!(image.get(0, 0), Some(Pixel::zero()));
+        assert_eq!(image.get(1, 0), Some(BLUE));
+    }
+
+    #[test]
+    fn hidden_groups_and_opacity() {
+        let data = file(
+            32,
+            (1, 1),
+            0,
+            &[frame(
+                100,
+                &[
+                    layer("group", 0, 1, 0, 255),
+                    layer("child", LAYER_VISIBLE, 0, 1, 255),
+                    layer("faded", LAYER_VISIBLE, 0, 0, 128),
+                    cel(1, 0, 0, 0, &image_body(1, 1, &[255, 0, 0, 255], false)),
+                    cel(2, 0, 0, 0, &image_body(1, 1, &[0, 0, 255, 255], false)),
+                ],
+            )],
+        );
+
+        let sprite = Aseprite::from_bytes(&data).unwrap();
+        assert_eq!(sprite.frame(0).get(0, 0), Some(Pixel::new(0, 0, 255, 128)));
+    }
+
+    #[test]
+    fn reject_bad_magic() {
+        let mut data = rgba_file();
+        data[4] = 0;
+        assert!(matches!(
+            Aseprite::from_bytes(&data),
+            Err(AsepriteError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn reject_oversized_palette() {
+        let mut data = Vec::new();
+        for word in &[u32::MAX, 0, 0, 0, 0] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        let file = file(32, (1, 1), 0, &[frame(100, &[chunk(CHUNK_PALETTE, &data)])]);
+
+        assert!(matches!(
+            Aseprite::from_bytes(&file),
+            Err(AsepriteError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,324 @@
+//! The named colours from CSS.
+
+use crate::Pixel;
+
+pub const ALICEBLUE: Pixel = Pixel::from_u32(0xf0f8ffff);
+pub const ANTIQUEWHITE: Pixel = Pixel::from_u32(0xfaebd7ff);
+pub const AQUA: Pixel = Pixel::from_u32(0x00ffffff);
+pub const AQUAMARINE: Pixel = Pixel::from_u32(0x7fffd4ff);
+pub const AZURE: Pixel = Pixel::from_u32(0xf0ffffff);
+pub const BEIGE: Pixel = Pixel::from_u32(0xf5f5dcff);
+pub const BISQUE: Pixel = Pixel::from_u32(0xffe4c4ff);
+pub const BLACK: Pixel = Pixel::from_u32(0x000000ff);
+pub const BLANCHEDALMOND: Pixel = Pixel::from_u32(0xffebcdff);
+pub const BLUE: Pixel = Pixel::from_u32(0x0000ffff);
+pub const BLUEVIOLET: Pixel = Pixel::from_u32(0x8a2be2ff);
+pub const BROWN: Pixel = Pixel::from_u32(0xa52a2aff);
+pub const BURLYWOOD: Pixel = Pixel::from_u32(0xdeb887ff);
+pub const CADETBLUE: Pixel = Pixel::from_u32(0x5f9ea0ff);
+pub const CHARTREUSE: Pixel = Pixel::from_u32(0x7fff00ff);
+pub const CHOCOLATE: Pixel = Pixel::from_u32(0xd2691eff);
+pub const CORAL: Pixel = Pixel::from_u32(0xff7f50ff);
+pub const CORNFLOWERBLUE: Pixel = Pixel::from_u32(0x6495edff);
+pub const CORNSILK: Pixel = Pixel::from_u32(0xfff8dcff);
+pub const CRIMSON: Pixel = Pixel::from_u32(0xdc143cff);
+pub const CYAN: Pixel = Pixel::from_u32(0x00ffffff);
+pub const DARKBLUE: Pixel = Pixel::from_u32(0x00008bff);
+pub const DARKCYAN: Pixel = Pixel::from_u32(0x008b8bff);
+pub const DARKGOLDENROD: Pixel = Pixel::from_u32(0xb8860bff);
+pub const DARKGRAY: Pixel = Pixel::from_u32(0xa9a9a9ff);
+pub const DARKGREEN: Pixel = Pixel::from_u32(0x006400ff);
+pub const DARKGREY: Pixel = Pixel::from_u32(0xa9a9a9ff);
+pub const DARKKHAKI: Pixel = Pixel::from_u32(0xbdb76bff);
+pub const DARKMAGENTA: Pixel = Pixel::from_u32(0x8b008bff);
+pub const DARKOLIVEGREEN: Pixel = Pixel::from_u32(0x556b2fff);
+pub const DARKORANGE: Pixel = Pixel::from_u32(0xff8c00ff);
+pub const DARKORCHID: Pixel = Pixel::from_u32(0x9932ccff);
+pub const DARKRED: Pixel = Pixel::from_u32(0x8b0000ff);
+pub const DARKSALMON: Pixel = Pixel::from_u32(0xe9967aff);
+pub const DARKSEAGREEN: Pixel = Pixel::from_u32(0x8fbc8fff);
+pub const DARKSLATEBLUE: Pixel = Pixel::from_u32(0x483d8bff);
+pub const DARKSLATEGRAY: Pixel = Pixel::from_u32(0x2f4f4fff);
+pub const DARKSLATEGREY: Pixel = Pixel::from_u32(0x2f4f4fff);
+pub const DARKTURQUOISE: Pixel = Pixel::from_u32(0x00ced1ff);
+pub const DARKVIOLET: Pixel = Pixel::from_u32(0x9400d3ff);
+pub const DEEPPINK: Pixel = Pixel::from_u32(0xff1493ff);
+pub const DEEPSKYBLUE: Pixel = Pixel::from_u32(0x00bfffff);
+pub const DIMGRAY: Pixel = Pixel::from_u32(0x696969ff);
+pub const DIMGREY: Pixel = Pixel::from_u32(0x696969ff);
+pub const DODGERBLUE: Pixel = Pixel::from_u32(0x1e90ffff);
+pub const FIREBRICK: Pixel = Pixel::from_u32(0xb22222ff);
+pub const FLORALWHITE: Pixel = Pixel::from_u32(0xfffaf0ff);
+pub const FORESTGREEN: Pixel = Pixel::from_u32(0x228b22ff);
+pub const FUCHSIA: Pixel = Pixel::from_u32(0xff00ffff);
+pub const GAINSBORO: Pixel = Pixel::from_u32(0xdcdcdcff);
+pub const GHOSTWHITE: Pixel = Pixel::from_u32(0xf8f8ffff);
+pub const GOLD: Pixel = Pixel::from_u32(0xffd700ff);
+pub const GOLDENROD: Pixel = Pixel::from_u32(0xdaa520ff);
+pub const GRAY: Pixel = Pixel::from_u32(0x808080ff);
+pub const GREEN: Pixel = Pixel::from_u32(0x008000ff);
+pub const GREENYELLOW: Pixel = Pixel::from_u32(0xadff2fff);
+pub const GREY: Pixel = Pixel::from_u32(0x808080ff);
+pub const HONEYDEW: Pixel = Pixel::from_u32(0xf0fff0ff);
+pub const HOTPINK: Pixel = Pixel::from_u32(0xff69b4ff);
+pub const INDIANRED: Pixel = Pixel::from_u32(0xcd5c5cff);
+pub const INDIGO: Pixel = Pixel::from_u32(0x4b0082ff);
+pub const IVORY: Pixel = Pixel::from_u32(0xfffff0ff);
+pub const KHAKI: Pixel = Pixel::from_u32(0xf0e68cff);
+pub const LAVENDER: Pixel = Pixel::from_u32(0xe6e6faff);
+pub const LAVENDERBLUSH: Pixel = Pixel::from_u32(0xfff0f5ff);
+pub const LAWNGREEN: Pixel = Pixel::from_u32(0x7cfc00ff);
+pub const LEMONCHIFFON: Pixel = Pixel::from_u32(0xfffacdff);
+pub const LIGHTBLUE: Pixel = Pixel::from_u32(0xadd8e6ff);
+pub const LIGHTCORAL: Pixel = Pixel::from_u32(0xf08080ff);
+pub const LIGHTCYAN: Pixel = Pixel::from_u32(0xe0ffffff);
+pub const LIGHTGOLDENRODYELLOW: Pixel = Pixel::from_u32(0xfafad2ff);
+pub const LIGHTGRAY: Pixel = Pixel::from_u32(0xd3d3d3ff);
+pub const LIGHTGREEN: Pixel = Pixel::from_u32(0x90ee90ff);
+pub const LIGHTGREY: Pixel = Pixel::from_u32(0xd3d3d3ff);
+pub const LIGHTPINK: Pixel = Pixel::from_u32(0xffb6c1ff);
+pub const LIGHTSALMON: Pixel = Pixel::from_u32(0xffa07aff);
+pub const LIGHTSEAGREEN: Pixel = Pixel::from_u32(0x20b2aaff);
+pub const LIGHTSKYBLUE: Pixel = Pixel::from_u32(0x87cefaff);
+pub const LIGHTSLATEGRAY: Pixel = Pixel::from_u32(0x778899ff);
+pub const LIGHTSLATEGREY: Pixel = Pixel::from_u32(0x778899ff);
+pub const LIGHTSTEELBLUE: Pixel = Pixel::from_u32(0xb0c4deff);
+pub const LIGHTYELLOW: Pixel = Pixel::from_u32(0xffffe0ff);
+pub const LIME: Pixel = Pixel::from_u32(0x00ff00ff);
+pub const LIMEGREEN: Pixel = Pixel::from_u32(0x32cd32ff);
+pub const LINEN: Pixel = Pixel::from_u32(0xfaf0e6ff);
+pub const MAGENTA: Pixel = Pixel::from_u32(0xff00ffff);
+pub const MAROON: Pixel = Pixel::from_u32(0x800000ff);
+pub const MEDIUMAQUAMARINE: Pixel = Pixel::from_u32(0x66cdaaff);
+pub const MEDIUMBLUE: Pixel = Pixel::from_u32(0x0000cdff);
+pub const MEDIUMORCHID: Pixel = Pixel::from_u32(0xba55d3ff);
+pub const MEDIUMPURPLE: Pixel = Pixel::from_u32(0x9370dbff);
+pub const MEDIUMSEAGREEN: Pixel = Pixel::from_u32(0x3cb371ff);
+pub const MEDIUMSLATEBLUE: Pixel = Pixel::from_u32(0x7b68eeff);
+pub const MEDIUMSPRINGGREEN: Pixel = Pixel::from_u32(0x00fa9aff);
+pub const MEDIUMTURQUOISE: Pixel = Pixel::from_u32(0x48d1ccff);
+pub const MEDIUMVIOLETRED: Pixel = Pixel::from_u32(0xc71585ff);
+pub const MIDNIGHTBLUE: Pixel = Pixel::from_u32(0x191970ff);
+pub const MINTCREAM: Pixel = Pixel::from_u32(0xf5fffaff);
+pub const MISTYROSE: Pixel = Pixel::from_u32(0xffe4e1ff);
+pub const MOCCASIN: Pixel = Pixel::from_u32(0xffe4b5ff);
+pub const NAVAJOWHITE: Pixel = Pixel::from_u32(0xffdeadff);
+pub const NAVY: Pixel = Pixel::from_u32(0x000080ff);
+pub const OLDLACE: Pixel = Pixel::from_u32(0xfdf5e6ff);
+pub const OLIVE: Pixel = Pixel::from_u32(0x808000ff);
+pub const OLIVEDRAB: Pixel = Pixel::from_u32(0x6b8e23ff);
+pub const ORANGE: Pixel = Pixel::from_u32(0xffa500ff);
+pub const ORANGERED: Pixel = Pixel::from_u32(0xff4500ff);
+pub const ORCHID: Pixel = Pixel::from_u32(0xda70d6ff);
+pub const PALEGOLDENROD: Pixel = Pixel::from_u32(0xeee8aaff);
+pub const PALEGREEN: Pixel = Pixel::from_u32(0x98fb98ff);
+pub const PALETURQUOISE: Pixel = Pixel::from_u32(0xafeeeeff);
+pub const PALEVIOLETRED: Pixel = Pixel::from_u32(0xdb7093ff);
+pub const PAPAYAWHIP: Pixel = Pixel::from_u32(0xffefd5ff);
+pub const PEACHPUFF: Pixel = Pixel::from_u32(0xffdab9ff);
+pub const PERU: Pixel = Pixel::from_u32(0xcd853fff);
+pub const PINK: Pixel = Pixel::from_u32(0xffc0cbff);
+pub const PLUM: Pixel = Pixel::from_u32(0xdda0ddff);
+pub const POWDERBLUE: Pixel = Pixel::from_u32(0xb0e0e6ff);
+pub const PURPLE: Pixel = Pixel::from_u32(0x800080ff);
+pub const REBECCAPURPLE: Pixel = Pixel::from_u32(0x663399ff);
+pub const RED: Pixel = Pixel::from_u32(0xff0000ff);
+pub const ROSYBROWN: Pixel = Pixel::from_u32(0xbc8f8fff);
+pub const ROYALBLUE: Pixel = Pixel::from_u32(0x4169e1ff);
+pub const SADDLEBROWN: Pixel = Pixel::from_u32(0x8b4513ff);
+pub const SALMON: Pixel = Pixel::from_u32(0xfa8072ff);
+pub const SANDYBROWN: Pixel = Pixel::from_u32(0xf4a460ff);
+pub const SEAGREEN: Pixel = Pixel::from_u32(0x2e8b57ff);
+pub const SEASHELL: Pixel = Pixel::from_u32(0xfff5eeff);
+pub const SIENNA: Pixel = Pixel::from_u32(0xa0522dff);
+pub const SILVER: Pixel = Pixel::from_u32(0xc0c0c0ff);
+pub const SKYBLUE: Pixel = Pixel::from_u32(0x87ceebff);
+pub const SLATEBLUE: Pixel = Pixel::from_u32(0x6a5acdff);
+pub const SLATEGRAY: Pixel = Pixel::from_u32(0x708090ff);
+pub const SLATEGREY: Pixel = Pixel::from_u32(0x708090ff);
+pub const SNOW: Pixel = Pixel::from_u32(0xfffafaff);
+pub const SPRINGGREEN: Pixel = Pixel::from_u32(0x00ff7fff);
+pub const STEELBLUE: Pixel = Pixel::from_u32(0x4682b4ff);
+pub const TAN: Pixel = Pixel::from_u32(0xd2b48cff);
+pub const TEAL: Pixel = Pixel::from_u32(0x008080ff);
+pub const THISTLE: Pixel = Pixel::from_u32(0xd8bfd8ff);
+pub const TOMATO: Pixel = Pixel::from_u32(0xff6347ff);
+pub const TURQUOISE: Pixel = Pixel::from_u32(0x40e0d0ff);
+pub const VIOLET: Pixel = Pixel::from_u32(0xee82eeff);
+pub const WHEAT: Pixel = Pixel::from_u32(0xf5deb3ff);
+pub const WHITE: Pixel = Pixel::from_u32(0xffffffff);
+pub const WHITESMOKE: Pixel = Pixel::from_u32(0xf5f5f5ff);
+pub const YELLOW: Pixel = Pixel::from_u32(0xffff00ff);
+pub const YELLOWGREEN: Pixel = Pixel::from_u32(0x9acd32ff);
+
+#[rustfmt::skip]
+const NAMES: &[(&str, Pixel)] = &[
+    ("aliceblue", ALICEBLUE),
+    ("antiquewhite", ANTIQUEWHITE),
+    ("aqua", AQUA),
+    ("aquamarine", AQUAMARINE),
+    ("azure", AZURE),
+    ("beige", BEIGE),
+    ("bisque", BISQUE),
+    ("black", BLACK),
+    ("blanchedalmond", BLANCHEDALMOND),
+    ("blue", BLUE),
+    ("blueviolet", BLUEVIOLET),
+    ("brown", BROWN),
+    ("burlywood", BURLYWOOD),
+    ("cadetblue", CADETBLUE),
+    ("chartreuse", CHARTREUSE),
+    ("chocolate", CHOCOLATE),
+    ("coral", CORAL),
+    ("cornflowerblue", CORNFLOWERBLUE),
+    ("cornsilk", CORNSILK),
+    ("crimson", CRIMSON),
+    ("cyan", CYAN),
+    ("darkblue", DARKBLUE),
+    ("darkcyan", DARKCYAN),
+    ("darkgoldenrod", DARKGOLDENROD),
+    ("darkgray", DARKGRAY),
+    ("darkgreen", DARKGREEN),
+    ("darkgrey", DARKGREY),
+    ("darkkhaki", DARKKHAKI),
+    ("darkmagenta", DARKMAGENTA),
+    ("darkolivegreen", DARKOLIVEGREEN),
+    ("darkorange", DARKORANGE),
+    ("darkorchid", DARKORCHID),
+    ("darkred", DARKRED),
+    ("darksalmon", DARKSALMON),
+    ("darkseagreen", DARKSEAGREEN),
+    ("darkslateblue", DARKSLATEBLUE),
+    ("darkslategray", DARKSLATEGRAY),
+    ("darkslategrey", DARKSLATEGREY),
+    ("darkturquoise", DARKTURQUOISE),
+    ("darkviolet", DARKVIOLET),
+    ("deeppink", DEEPPINK),
+    ("deepskyblue", DEEPSKYBLUE),
+    ("dimgray", DIMGRAY),
+    ("dimgrey", DIMGREY),
+    ("dodgerblue", DODGERBLUE),
+    ("firebrick", FIREBRICK),
+    ("floralwhite", FLORALWHITE),
+    ("forestgreen", FORESTGREEN),
+    ("fuchsia", FUCHSIA),
+    ("gainsboro", GAINSBORO),
+    ("ghostwhite", GHOSTWHITE),
+    ("gold", GOLD),
+    ("goldenrod", GOLDENROD),
+    ("gray", GRAY),
+    ("green", GREEN),
+    ("greenyellow", GREENYELLOW),
+    ("grey", GREY),
+    ("honeydew", HONEYDEW),
+    ("hotpink", HOTPINK),
+    ("indianred", INDIANRED),
+    ("indigo", INDIGO),
+    ("ivory", IVORY),
+    ("khaki", KHAKI),
+    ("lavender", LAVENDER),
+    ("lavenderblush", LAVENDERBLUSH),
+    ("lawngreen", LAWNGREEN),
+    ("lemonchiffon", LEMONCHIFFON),
+    ("lightblue", LIGHTBLUE),
+    ("lightcoral", LIGHTCORAL),
+    ("lightcyan", LIGHTCYAN),
+    ("lightgoldenrodyellow", LIGHTGOLDENRODYELLOW),
+    ("lightgray", LIGHTGRAY),
+    ("lightgreen", LIGHTGREEN),
+    ("lightgrey", LIGHTGREY),
+    ("lightpink", LIGHTPINK),
+    ("lightsalmon", LIGHTSALMON),
+    ("lightseagreen", LIGHTSEAGREEN),
+    ("lightskyblue", LIGHTSKYBLUE),
+    ("lightslategray", LIGHTSLATEGRAY),
+    ("lightslategrey", LIGHTSLATEGREY),
+    ("lightsteelblue", LIGHTSTEELBLUE),
+    ("lightyellow", LIGHTYELLOW),
+    ("lime", LIME),
+    ("limegreen", LIMEGREEN),
+    ("linen", LINEN),
+    ("magenta", MAGENTA),
+    ("maroon", MAROON),
+    ("mediumaquamarine", MEDIUMAQUAMARINE),
+    ("mediumblue", MEDIUMBLUE),
+    ("mediumorchid", MEDIUMORCHID),
+    ("mediumpurple", MEDIUMPURPLE),
+    ("mediumseagreen", MEDIUMSEAGREEN),
+    ("mediumslateblue", MEDIUMSLATEBLUE),
+    ("mediumspringgreen", MEDIUMSPRINGGREEN),
+    ("mediumturquoise", MEDIUMTURQUOISE),
+    ("mediumvioletred", MEDIUMVIOLETRED),
+    ("midnightblue", MIDNIGHTBLUE),
+    ("mintcream", MINTCREAM),
+    ("mistyrose", MISTYROSE),
+    ("moccasin", MOCCASIN),
+    ("navajowhite", NAVAJOWHITE),
+    ("navy", NAVY),
+    ("oldlace", OLDLACE),
+    ("olive", OLIVE),
+    ("olivedrab", OLIVEDRAB),
+    ("orange", ORANGE),
+    ("orangered", ORANGERED),
+    ("orchid", ORCHID),
+    ("palegoldenrod", PALEGOLDENROD),
+    ("palegreen", PALEGREEN),
+    ("paleturquoise", PALETURQUOISE),
+    ("palevioletred", PALEVIOLETRED),
+    ("papayawhip", PAPAYAWHIP),
+    ("peachpuff", PEACHPUFF),
+    ("peru", PERU),
+    ("pink", PINK),
+    ("plum", PLUM),
+    ("powderblue", POWDERBLUE),
+    ("purple", PURPLE),
+    ("rebeccapurple", REBECCAPURPLE),
+    ("red", RED),
+    ("rosybrown", ROSYBROWN),
+    ("royalblue", ROYALBLUE),
+    ("saddlebrown", SADDLEBROWN),
+    ("salmon", SALMON),
+    ("sandybrown", SANDYBROWN),
+    ("seagreen", SEAGREEN),
+    ("seashell", SEASHELL),
+    ("sienna", SIENNA),
+    ("silver", SILVER),
+    ("skyblue", SKYBLUE),
+    ("slateblue", SLATEBLUE),
+    ("slategray", SLATEGRAY),
+    ("slategrey", SLATEGREY),
+    ("snow", SNOW),
+    ("springgreen", SPRINGGREEN),
+    ("steelblue", STEELBLUE),
+    ("tan", TAN),
+    ("teal", TEAL),
+    ("thistle", THISTLE),
+    ("tomato", TOMATO),
+    ("turquoise", TURQUOISE),
+    ("violet", VIOLET),
+    ("wheat", WHEAT),
+    ("white", WHITE),
+    ("whitesmoke", WHITESMOKE),
+    ("yellow", YELLOW),
+    ("yellowgreen", YELLOWGREEN),
+];
+
+/// Look up a colour by its CSS name. The name is not case sensitive.
+pub fn by_name(name: &str) -> Option<Pixel> {
+    let name = name.to_ascii_lowercase();
+    NAMES.iter().find(|(n, _)| *n == name).map(|(_, pixel)| *pixel)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup_by_name() {
+        assert_eq!(NAMES.len(), 148);
+        assert_eq!(by_name("RebeccaPurple"), Some(REBECCAPURPLE));
+        assert_eq!(by_name("grey"), by_name("gray"));
+        assert_eq!(by_name("not a colour"), None);
+        assert_eq!(Pixel::from_hex("#ff6347"), Ok(TOMATO));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{Pixel, ScreenPos, ScreenRect, Viewport};
+
+/// Identifies a viewport added to a [`Compositor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ViewportId(usize);
+
+struct Layer {
+    id: ViewportId,
+    z: i32,
+    viewport: Viewport,
+}
+
+// -----------------------------------------------------------------------------
+//     - Compositor -
+// -----------------------------------------------------------------------------
+/// Owns a set of viewports and combines them into one image.
+///
+/// Viewports with a higher z-index are drawn on top.
+/// Where viewports overlap, the topmost visible pixel wins: a transparent
+/// pixel shows whatever is underneath it rather than erasing it,
+/// and changes to a viewport that is covered do not show through.
+#[derive(Default)]
+pub struct Compositor {
+    // Sorted by z-index, bottom first
+    layers: Vec<Layer>,
+    next_id: usize,
+    // Areas that need redrawing regardless of what the viewports report
+    dirty: Vec<ScreenRect>,
+}
+
+impl Compositor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a viewport. Viewports with the same z-index are stacked in the
+    /// order they were added.
+    pub fn add(&mut self, viewport: Viewport, z: i32) -> ViewportId {
+        let id = ViewportId(self.next_id);
+        self.next_id += 1;
+        self.insert(Layer { id, z, viewport });
+        id
+    }
+
+    /// Remove a viewport, revealing what is underneath it.
+    pub fn remove(&mut self, id: ViewportId) -> Option<Viewport> {
+        let index = self.index(id)?;
+        let layer = self.layers.remove(index);
+        self.invalidate(&layer.viewport);
+        Some(layer.viewport)
+    }
+
+    pub fn get(&self, id: ViewportId) -> Option<&Viewport> {
+        self.layers
+            .iter()
+            .find(|layer| layer.id == id)
+            .map(|layer| &layer.viewport)
+    }
+
+    pub fn get_mut(&mut self, id: ViewportId) -> Option<&mut Viewport> {
+        self.layers
+            .iter_mut()
+            .find(|layer| layer.id == id)
+            .map(|layer| &mut layer.viewport)
+    }
+
+    pub fn z(&self, id: ViewportId) -> Option<i32> {
+        self.layers
+            .iter()
+            .find(|layer| layer.id == id)
+            .map(|layer| layer.z)
+    }
+
+    /// Move a viewport up or down the stack.
+    pub fn set_z(&mut self, id: ViewportId, z: i32) {
+        if let Some(index) = self.index(id) {
+            let mut layer = self.layers.remove(index);
+            layer.z = z;
+            self.invalidate(&layer.viewport);
+            self.insert(layer);
+        }
+    }
+
+    /// Viewports from the bottom of the stack to the top.
+    pub fn viewports(&self) -> impl Iterator<Item = (ViewportId, &Viewport)> {
+        self.layers.iter().map(|layer| (layer.id, &layer.viewport))
+    }
+
+    /// The changed pixels of all viewports, resolved against each other.
+    pub(crate) fn pixels(&mut self) -> Vec<(Pixel, ScreenPos)> {
+        let mut positions = Vec::new();
+
+        for layer in &mut self.layers {
+            positions.extend(layer.viewport.pixels().into_iter().map(|(_, pos)| pos));
+        }
+
+        for rect in self.dirty.drain(..) {
+            let (min, max) = (rect.min(), rect.max());
+            for y in min.y..max.y {
+                positions.extend((min.x..max.x).map(|x| ScreenPos::new(x, y)));
+            }
+        }
+
+        positions.sort_by_key(|pos| (pos.y, pos.x));
+        positions.dedup();
+
+        let layers = &self.layers;
+        positions
+            .into_iter()
+            .map(|pos| (resolve(layers, pos), pos))
+            .collect()
+    }
+
+    // Redraw both where the viewport is now, and where it is still
+    // shown if it moved since the last frame
+    fn invalidate(&mut self, viewport: &Viewport) {
+        self.dirty.push(viewport.rect());
+        if viewport.presented_rect() != viewport.rect() {
+            self.dirty.push(viewport.presented_rect());
+        }
+    }
+
+    fn index(&self, id: ViewportId) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.id == id)
+    }
+
+    fn insert(&mut self, layer: Layer) {
+        let index = self.layers.partition_point(|other| other.z <= layer.z);
+        self.layers.insert(index, layer);
+    }
+}
+
+/// The topmost visible pixel at a position, or a transparent one if
+/// no viewport has anything there.
+fn resolve(layers: &[Layer], pos: ScreenPos) -> Pixel {
+    layers
+        .iter()
+        .rev()
+        .filter_map(|layer| layer.viewport.presented(pos))
+        .find(|pixel| pixel.a > 0)
+        .unwrap_or_else(Pixel::zero)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ScreenSize;
+
+    const RED: Pixel = Pixel::rgb(255, 0, 0);
+    const BLUE: Pixel = Pixel::rgb(0, 0, 255);
+
+    fn viewport(x: u32, y: u32, colour: Pixel) -> Viewport {
+        let mut viewport = Viewport::new(ScreenPos::new(x, y), ScreenSize::new(2, 2));
+        viewport.fill(colour);
+        viewport
+    }
+
+    fn at(pixels: &[(Pixel, ScreenPos)], x: u32, y: u32) -> Option<Pixel> {
+        let pos = ScreenPos::new(x, y);
+        pixels
+            .iter()
+            .find(|(_, p)| *p == pos)
+            .map(|(pixel, _)| *pixel)
+    }
+
+    #[test]
+    fn higher_z_is_on_top() {
+        let mut compositor = Compositor::new();
+        // Added top first, so the order of calls doesn't matter
+        compositor.add(viewport(1, 1, BLUE), 1);
+        compositor.add(viewport(0, 0, RED), 0);
+
+        let pixels = compositor.pixels();
+        assert_eq!(pixels.len(), 7);
+        assert_eq!(at(&pixels, 0, 0), Some(RED));
+        assert_eq!(at(&pixels, 1, 1), Some(BLUE));
+    }
+
+    #[test]
+    fn transparent_pixels_reveal_lower_viewports() {
+        let mut compositor = Compositor::new();
+        let bottom = compositor.add(viewport(0, 0, RED), 0);
+        let top = compositor.add(viewport(1, 1, BLUE), 1);
+        compositor.pixels();
+
+        // Both redraw the same, except the top one is now empty
+        compositor.get_mut(bottom).unwrap().fill(RED);
+        let pixels = compositor.pixels();
+
+        assert_eq!(at(&pixels, 1, 1), Some(RED));
+        assert_eq!(at(&pixels, 2, 2), Some(Pixel::zero()));
+        assert_eq!(at(&pixels, 0, 0), None);
+        assert_eq!(pixels.len(), 4);
+        assert!(compositor.get(top).is_some());
+    }
+
+    #[test]
+    fn covered_changes_are_hidden() {
+        let mut compositor = Compositor::new();
+        let bottom = compositor.add(viewport(0, 0, RED), 0);
+        let top = compositor.add(viewport(1, 1, BLUE), 1);
+        compositor.pixels();
+
+        compositor.get_mut(bottom).unwrap().fill(BLUE);
+        compositor.get_mut(top).unwrap().fill(BLUE);
+        let pixels = compositor.pixels();
+
+        // The overlapping pixel changed underneath but is still blue on top
+        assert_eq!(at(&pixels, 0, 0), Some(BLUE));
+        assert_eq!(at(&pixels, 1, 1), Some(BLUE));
+        assert!(pixels.iter().all(|(pixel, _)| *pixel == BLUE));
+    }
+
+    #[test]
+    fn moving_reveals_lower_viewports() {
+        let mut compositor = Compositor::new();
+        let bottom = compositor.add(viewport(0, 0, RED), 0);
+        let top = compositor.add(viewport(1, 1, BLUE), 1);
+        compositor.pixels();
+
+        compositor.get_mut(bottom).unwrap().fill(RED);
+        let top_viewport = compositor.get_mut(top).unwrap();
+        top_viewport.position = ScreenPos::new(2, 2);
+        top_viewport.fill(BLUE);
+        let pixels = compositor.pixels();
+
+        assert_eq!(at(&pixels, 1, 1), Some(RED));
+        assert_eq!(at(&pixels, 2, 1), Some(Pixel::zero()));
+        assert_eq!(at(&pixels, 3, 3), Some(BLUE));
+        assert_eq!(at(&pixels, 2, 2), None);
+    }
+
+    #[test]
+    fn reordering_and_removing() {
+        let mut compositor = Compositor::new();
+        let bottom = compositor.add(viewport(0, 0, RED), 0);
+        let top = compositor.add(viewport(1, 1, BLUE), 1);
+        compositor.pixels();
+
+        compositor.set_z(bottom, 2);
+        compositor.get_mut(bottom).unwrap().fill(RED);
+        compositor.get_mut(top).unwrap().fill(BLUE);
+        let pixels = compositor.pixels();
+        assert_eq!(at(&pixels, 1, 1), Some(RED));
+        assert_eq!(compositor.z(bottom), Some(2));
+
+        compositor.get_mut(top).unwrap().fill(BLUE);
+        compositor.remove(bottom);
+        let pixels = compositor.pixels();
+        assert_eq!(at(&pixels, 0, 0), Some(Pixel::zero()));
+        assert_eq!(at(&pixels, 1, 1), Some(BLUE));
+        assert_eq!(compositor.viewports().count(), 1);
+    }
+
+    #[test]
+    fn removing_a_moved_viewport() {
+        let mut compositor = Compositor::new();
+        let top = compositor.add(viewport(0, 0, BLUE), 0);
+        compositor.pixels();
+
+        // Moved, but still shown at the old position until the next frame
+        compositor.get_mut(top).unwrap().position = ScreenPos::new(4, 4);
+        compositor.remove(top);
+        let pixels = compositor.pixels();
+        assert_eq!(at(&pixels, 0, 0), Some(Pixel::zero()));
+        assert_eq!(at(&pixels, 1, 1), Some(Pixel::zero()));
+    }
+}
@@ -1,3 +1,7 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
 use winit::{
     event::Event as WinitEvent,
     event::*,
@@ -5,63 +9,368 @@ use winit::{
     window::Window,
 };
 
-use crate::ScreenSize;
 use crate::renderer::Renderer;
+use crate::{
+    screenshot, FrameLimiter, FrameStage, GamepadAxis, GamepadButton, GamepadId, Gamepads, Image,
+    Recorded, Recorder, ScreenPos, ScreenSize, SizeChange, SizePolicy, Vec2D,
+    MAX_TEXTURE_DIMENSION,
+};
+
+/// A key press or release, with the modifier keys held at the time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyEvent {
+    /// The physical key, regardless of the keyboard layout.
+    pub scancode: u32,
+    pub state: ElementState,
+    /// The key according to the keyboard layout.
+    pub key: Option<VirtualKeyCode>,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyEvent {
+    pub fn pressed(&self) -> bool {
+        self.state == ElementState::Pressed
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    Key(KeyEvent),
+    /// A typed character, after the keyboard layout, dead keys
+    /// and input methods have been applied. Use this for text input.
+    Char(char),
+    /// The cursor moved, in pixels from the top left corner of the window.
+    MouseMove(ScreenPos),
+    MouseButton(MouseButton, ElementState),
+    /// Scrolled by a number of lines. Positive `y` is scrolling up.
+    Scroll(Vec2D<f32>),
+    GamepadButton(GamepadId, GamepadButton, ElementState),
+    /// An axis moved, after dead zones were applied (see [`DeadZones`](crate::DeadZones)).
+    /// Sticks go from -1.0 to 1.0, with positive `y` up. Triggers go from 0.0 to 1.0.
+    GamepadAxis(GamepadId, GamepadAxis, f32),
+    GamepadConnected(GamepadId),
+    /// The gamepad was unplugged. Its axes are back at zero.
+    GamepadDisconnected(GamepadId),
+}
 
-pub enum Event<'a> {
-    Key(&'a KeyboardInput),
-    Mouse,
+/// Changes to the window rather than input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Lifecycle {
+    /// The window gained (true) or lost (false) keyboard focus.
+    Focused(bool),
+    /// The window moved, to this position on the desktop.
+    Moved(Vec2D<i32>),
+    /// The window was minimised. Nothing is updated or drawn until it is restored.
+    Minimised,
+    Restored,
+    /// A file is being dragged over the window.
+    HoveredFile(PathBuf),
+    /// The file being dragged over the window left it without being dropped.
+    HoveredFileCancelled,
+    DroppedFile(PathBuf),
 }
 
 pub trait EventLoop: 'static {
     fn draw(&mut self, renderer: &mut Renderer);
-    fn update(&mut self);
+    /// Called once per frame with the time since the previous update.
+    fn update(&mut self, dt: Duration);
+    /// Called with the size rendered at, which may be smaller than the window
+    /// (see [`SizePolicy`]). Not called while minimised.
     fn resize(&mut self, new_size: ScreenSize);
-    fn input<'a>(&mut self, event: Event<'a>);
+    fn input(&mut self, event: Event);
+
+    /// Called when the window gains focus, moves, is minimised and so on.
+    fn lifecycle(&mut self, _event: Lifecycle) {}
+
+    /// Called when the window is asked to close, or Escape is pressed.
+    /// Return false to keep it open, for instance to ask about unsaved changes first.
+    fn close_requested(&mut self) -> bool {
+        true
+    }
+
+    /// Cap the frame rate. Checked every frame, so the cap can change at any time.
+    fn max_fps(&self) -> Option<u32> {
+        None
+    }
+
+    /// Pressing this key takes a screenshot (see [`Renderer::screenshot`]).
+    fn screenshot_key(&self) -> Option<VirtualKeyCode> {
+        None
+    }
+
+    /// Where gamepad events come from. Called once, on start.
+    fn gamepads(&mut self) -> Gamepads {
+        Gamepads::system()
+    }
+
+    /// Record input and timesteps to this file, to replay later with
+    /// [`Recording::replay`](crate::Recording::replay). Checked once, on start.
+    fn record_to(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Called with the screenshot taken when the screenshot key is pressed.
+    /// Saves it as a PNG in the working directory by default.
+    fn screenshot(&mut self, image: Image) {
+        let path = screenshot::file_name();
+        if let Err(e) = image.save_png(&path) {
+            eprintln!("Failed to save {}: {}", path, e);
+        }
+    }
 }
 
-pub fn start<T: std::fmt::Debug>(mut el: impl EventLoop, window: Window, event_loop: WinitEventLoop<T>) {
+/// Pixel scroll deltas (from touchpads) are converted to lines.
+const PIXELS_PER_LINE: f32 = 16.0;
+
+pub fn start<T: std::fmt::Debug>(
+    mut el: impl EventLoop,
+    window: Window,
+    event_loop: WinitEventLoop<T>,
+) {
     let mut renderer = Renderer::new(&window);
+    let mut last_update = Instant::now();
+    let mut limiter = FrameLimiter::new(None);
+    let mut modifiers = ModifiersState::empty();
+    let mut gamepads = el.gamepads();
+    let window_size = window.inner_size();
+    let mut size_policy = SizePolicy::new(
+        ScreenSize::new(window_size.width, window_size.height),
+        MAX_TEXTURE_DIMENSION,
+    );
+    let mut was_paused = size_policy.is_paused();
+
+    let mut recorder = el
+        .record_to()
+        .and_then(|path| match Recorder::create(&path) {
+            Ok(mut recorder) => {
+                // So the replay starts out at the same size
+                if let Some(size) = size_policy.current() {
+                    recorder.record(Recorded::Resize(size));
+                }
+                Some(recorder)
+            }
+            Err(e) => {
+                eprintln!("Failed to record to {}: {}", path.display(), e);
+                None
+            }
+        });
 
     event_loop.run(move |event, _, control_flow| {
         match event {
-            WinitEvent::RedrawRequested(window_id) if window_id == window.id() => {
+            WinitEvent::RedrawRequested(window_id)
+                if window_id == window.id() && !size_policy.is_paused() =>
+            {
                 renderer.render();
             }
             WinitEvent::MainEventsCleared => {
-                el.update();
+                // Resizes are applied once per frame, however many arrived
+                match size_policy.apply() {
+                    Some(SizeChange::Paused) => {
+                        let minimised = Recorded::Lifecycle(Lifecycle::Minimised);
+                        send(minimised, &mut el, &mut renderer, &mut recorder);
+                    }
+                    Some(SizeChange::Resized(size)) => {
+                        if was_paused {
+                            last_update = Instant::now();
+                            let restored = Recorded::Lifecycle(Lifecycle::Restored);
+                            send(restored, &mut el, &mut renderer, &mut recorder);
+                        }
+                        send(
+                            Recorded::Resize(size),
+                            &mut el,
+                            &mut renderer,
+                            &mut recorder,
+                        );
+                    }
+                    None => {}
+                }
+                was_paused = size_policy.is_paused();
+
+                if was_paused {
+                    *control_flow = ControlFlow::Wait;
+                    return;
+                }
+
+                let now = Instant::now();
+                limiter.set_max_fps(el.max_fps());
+                if let Some(next_frame) = limiter.wait_until(now) {
+                    *control_flow = ControlFlow::WaitUntil(next_frame);
+                    return;
+                }
+                *control_flow = ControlFlow::Poll;
+
+                for event in gamepads.poll() {
+                    send(
+                        Recorded::Input(event),
+                        &mut el,
+                        &mut renderer,
+                        &mut recorder,
+                    );
+                }
+
+                let dt = now - last_update;
+                el.update(dt);
+                last_update = now;
+                let draw_start = Instant::now();
+                renderer
+                    .stats_mut()
+                    .add(FrameStage::Update, draw_start - now);
+
                 el.draw(&mut renderer);
+                renderer
+                    .stats_mut()
+                    .add(FrameStage::Draw, draw_start.elapsed());
+
+                if let Some(rec) = &mut recorder {
+                    if let Err(e) = rec.end_frame(dt, renderer.frame_hash()) {
+                        eprintln!("Failed to record: {}", e);
+                        recorder = None;
+                    }
+                }
                 window.request_redraw();
             }
-            WinitEvent::WindowEvent { ref event, window_id, .. } if window_id == window.id() => {
+            WinitEvent::WindowEvent {
+                ref event,
+                window_id,
+                ..
+            } if window_id == window.id() => {
                 match event {
-                    WindowEvent::Resized(physical_size) => {
-                        el.resize(ScreenSize::new(physical_size.width, physical_size.height));
-                        renderer.resize(*physical_size);
+                    // Minimising shows up as a resize to 0x0
+                    WindowEvent::Resized(size) => {
+                        size_policy.request(ScreenSize::new(size.width, size.height))
+                    }
+                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => size_policy
+                        .request(ScreenSize::new(new_inner_size.width, new_inner_size.height)),
+                    _ => {}
+                }
+
+                let recorded = match event {
+                    WindowEvent::CloseRequested => Some(Recorded::CloseRequested),
+                    WindowEvent::CursorMoved { position, .. } => {
+                        let x = position.x.max(0.0) as u32;
+                        let y = position.y.max(0.0) as u32;
+                        Some(Recorded::Input(Event::MouseMove(ScreenPos::new(x, y))))
                     }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        el.resize(ScreenSize::new(new_inner_size.width, new_inner_size.height));
-                        renderer.resize(**new_inner_size)
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        Some(Recorded::Input(Event::MouseButton(*button, *state)))
                     }
-                    WindowEvent::KeyboardInput { input, .. } => {
-                        el.input(Event::Key(input));
-
-                        match input {
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            } => {
-                                // quit
-                                *control_flow = ControlFlow::Exit;
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        let delta = match delta {
+                            MouseScrollDelta::LineDelta(x, y) => Vec2D::new(*x, *y),
+                            MouseScrollDelta::PixelDelta(pos) => {
+                                Vec2D::new(pos.x as f32, pos.y as f32) / PIXELS_PER_LINE
                             }
-                            _ => {}
-                        }
+                        };
+                        Some(Recorded::Input(Event::Scroll(delta)))
+                    }
+                    WindowEvent::ModifiersChanged(state) => {
+                        modifiers = *state;
+                        None
+                    }
+                    WindowEvent::ReceivedCharacter(c) => Some(Recorded::Input(Event::Char(*c))),
+                    WindowEvent::KeyboardInput { input, .. } => {
+                        Some(Recorded::Input(Event::Key(KeyEvent {
+                            scancode: input.scancode,
+                            state: input.state,
+                            key: input.virtual_keycode,
+                            modifiers,
+                        })))
+                    }
+                    _ => lifecycle(event).map(Recorded::Lifecycle),
+                };
+
+                let mut close = match recorded {
+                    Some(event) => send(event, &mut el, &mut renderer, &mut recorder),
+                    None => false,
+                };
+
+                if let WindowEvent::KeyboardInput { input, .. } = event {
+                    let screenshot_key = el.screenshot_key();
+                    if input.state == ElementState::Pressed
+                        && input.virtual_keycode.is_some()
+                        && input.virtual_keycode == screenshot_key
+                    {
+                        send(Recorded::Screenshot, &mut el, &mut renderer, &mut recorder);
+                    }
+
+                    if let KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
+                        ..
+                    } = input
+                    {
+                        // Escape asks to quit, the same as closing the window
+                        let event = Recorded::CloseRequested;
+                        close |= send(event, &mut el, &mut renderer, &mut recorder);
                     }
-                    _ => {}
+                }
+
+                if close {
+                    *control_flow = ControlFlow::Exit;
                 }
             }
-            _ => { }
+            _ => {}
         }
     });
 }
+
+// Pass an event on, recording it first if a recording is running.
+// Returns true if the event loop agreed to close.
+fn send(
+    event: Recorded,
+    el: &mut impl EventLoop,
+    renderer: &mut Renderer,
+    recorder: &mut Option<Recorder>,
+) -> bool {
+    if let Some(recorder) = recorder {
+        recorder.record(event.clone());
+    }
+    event.send(el, renderer)
+}
+
+// Window events that map directly to a `Lifecycle` event.
+// Minimising and restoring depend on the size policy, so they are handled in `start`.
+fn lifecycle(event: &WindowEvent) -> Option<Lifecycle> {
+    match event {
+        WindowEvent::Focused(focused) => Some(Lifecycle::Focused(*focused)),
+        WindowEvent::Moved(pos) => Some(Lifecycle::Moved(Vec2D::new(pos.x, pos.y))),
+        WindowEvent::HoveredFile(path) => Some(Lifecycle::HoveredFile(path.clone())),
+        WindowEvent::HoveredFileCancelled => Some(Lifecycle::HoveredFileCancelled),
+        WindowEvent::DroppedFile(path) => Some(Lifecycle::DroppedFile(path.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use winit::dpi::PhysicalPosition;
+
+    use super::*;
+
+    #[test]
+    fn lifecycle_events() {
+        let path = PathBuf::from("level.tmx");
+
+        assert_eq!(
+            lifecycle(&WindowEvent::Focused(false)),
+            Some(Lifecycle::Focused(false))
+        );
+        assert_eq!(
+            lifecycle(&WindowEvent::Moved(PhysicalPosition::new(-4, 8))),
+            Some(Lifecycle::Moved(Vec2D::new(-4, 8)))
+        );
+        assert_eq!(
+            lifecycle(&WindowEvent::HoveredFile(path.clone())),
+            Some(Lifecycle::HoveredFile(path.clone()))
+        );
+        assert_eq!(
+            lifecycle(&WindowEvent::HoveredFileCancelled),
+            Some(Lifecycle::HoveredFileCancelled)
+        );
+        assert_eq!(
+            lifecycle(&WindowEvent::DroppedFile(path.clone())),
+            Some(Lifecycle::DroppedFile(path))
+        );
+        assert_eq!(lifecycle(&WindowEvent::CloseRequested), None);
+    }
+}
@@ -0,0 +1,132 @@
+//! A tiny built-in 5x7 pixel font covering printable ASCII.
+use crate::{Image, Pixel, ScreenPos, ScreenSize, Viewport};
+
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+/// Horizontal distance from one character to the next.
+pub const ADVANCE: u32 = GLYPH_WIDTH + 1;
+/// Vertical distance from one line to the next.
+pub const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 1;
+
+// One byte per column, the lowest bit is the top row.
+// Starts at ' ' (0x20) and ends at '~' (0x7e).
+#[rustfmt::skip]
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00], // ' ' ! "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62], // # $ %
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1c, 0x22, 0x41, 0x00], // & ' (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], [0x08, 0x2a, 0x1c, 0x2a, 0x08], [0x08, 0x08, 0x3e, 0x08, 0x08], // ) * +
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00], // , - .
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00], // / 0 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31], [0x18, 0x14, 0x12, 0x7f, 0x10], // 2 3 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03], // 5 6 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], [0x00, 0x36, 0x36, 0x00, 0x00], // 8 9 :
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14], // ; < =
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3e], // > ? @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22], // A B C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], [0x7f, 0x09, 0x09, 0x01, 0x01], // D E F
+    [0x3e, 0x41, 0x41, 0x51, 0x32], [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00], // G H I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41], [0x7f, 0x40, 0x40, 0x40, 0x40], // J K L
+    [0x7f, 0x02, 0x04, 0x02, 0x7f], [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e], // M N O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], [0x7f, 0x09, 0x19, 0x29, 0x46], // P Q R
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f], // S T U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x7f, 0x20, 0x18, 0x20, 0x7f], [0x63, 0x14, 0x08, 0x14, 0x63], // V W X
+    [0x03, 0x04, 0x78, 0x04, 0x03], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00], // Y Z [
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04], // \ ] ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78], // _ ` a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7f], // b c d
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7e, 0x09, 0x01, 0x02], [0x08, 0x54, 0x54, 0x54, 0x3c], // e f g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3d, 0x00], // h i j
+    [0x00, 0x7f, 0x10, 0x28, 0x44], [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78], // k l m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7c, 0x14, 0x14, 0x14, 0x08], // n o p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20], // q r s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], [0x1c, 0x20, 0x40, 0x20, 0x1c], // t u v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c], // w x y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7f, 0x00, 0x00], // z { |
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],                                 // } ~
+];
+
+/// The columns of a character, or `None` if the font does not have it.
+pub fn glyph(c: char) -> Option<[u8; 5]> {
+    let index = (c as u32).checked_sub(0x20)?;
+    GLYPHS.get(index as usize).copied()
+}
+
+/// The size of `text` when drawn. Lines are separated by `\n`.
+pub fn text_size(text: &str) -> ScreenSize {
+    let lines = text.split('\n');
+    let (count, width) = lines.fold((0, 0), |(count, width), line| {
+        (count + 1, width.max(line.chars().count() as u32))
+    });
+
+    match width {
+        0 if count <= 1 => ScreenSize::zero(),
+        _ => ScreenSize::new((width * ADVANCE).saturating_sub(1), count * LINE_HEIGHT - 1),
+    }
+}
+
+/// Positions of the pixels that make up `text`, relative to its top left corner.
+/// Characters outside of the font are drawn as `?`.
+pub fn text_pixels(text: &str) -> impl Iterator<Item = ScreenPos> + '_ {
+    text.split('\n').enumerate().flat_map(|(line, text)| {
+        text.chars().enumerate().flat_map(move |(column, c)| {
+            let columns = glyph(c).or_else(|| glyph('?')).unwrap_or_default();
+            let origin = ScreenPos::new(column as u32 * ADVANCE, line as u32 * LINE_HEIGHT);
+
+            (0..GLYPH_WIDTH).flat_map(move |x| {
+                (0..GLYPH_HEIGHT)
+                    .filter(move |y| columns[x as usize] >> y & 1 != 0)
+                    .map(move |y| ScreenPos::new(origin.x + x, origin.y + y))
+            })
+        })
+    })
+}
+
+impl Image {
+    /// Draw text with its top left corner at `pos`.
+    pub fn draw_text(&mut self, text: &str, pos: ScreenPos, colour: Pixel) {
+        for offset in text_pixels(text) {
+            self.set(pos.x + offset.x, pos.y + offset.y, colour);
+        }
+    }
+}
+
+impl Viewport {
+    /// Draw text with its top left corner at `pos`.
+    pub fn draw_text(&mut self, text: &str, pos: ScreenPos, colour: Pixel) {
+        for offset in text_pixels(text) {
+            self.draw_pixel(colour, ScreenPos::new(pos.x + offset.x, pos.y + offset.y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(text_size(""), ScreenSize::zero());
+        assert_eq!(text_size("a"), ScreenSize::new(5, 7));
+        assert_eq!(text_size("abc\nde"), ScreenSize::new(17, 15));
+    }
+
+    #[test]
+    fn draws_glyphs() {
+        let white = Pixel::rgb(255, 255, 255);
+        let mut image = Image::empty(ScreenSize::new(12, 8));
+        image.draw_text("|-", ScreenPos::zero(), white);
+
+        // A vertical bar in the middle column of the first glyph
+        assert!((0..7).all(|y| image.get(2, y) == Some(white)));
+        assert_eq!(image.get(1, 0), Some(Pixel::zero()));
+        // A dash across the middle row of the second glyph
+        assert!((6..11).all(|x| image.get(x, 3) == Some(white)));
+    }
+
+    #[test]
+    fn unknown_characters() {
+        assert!(glyph('é').is_none());
+        assert_eq!(text_pixels("é").count(), text_pixels("?").count());
+    }
+}
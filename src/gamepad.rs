@@ -0,0 +1,451 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Event;
+
+/// A gamepad. Every input source numbers its own gamepads, and
+/// [`Gamepads`] renumbers them so ids are unique across sources.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+/// Buttons are named by their position, so `South` is A on an Xbox
+/// controller and cross on a PlayStation controller.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftShoulder,
+    RightShoulder,
+    /// The trigger as a button. See [`GamepadAxis::LeftTrigger`] for how far it is pressed.
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    /// Pressing the left stick down.
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// The x and y axes of the stick this axis belongs to.
+    pub fn stick(self) -> Option<(GamepadAxis, GamepadAxis)> {
+        match self {
+            GamepadAxis::LeftStickX | GamepadAxis::LeftStickY => {
+                Some((GamepadAxis::LeftStickX, GamepadAxis::LeftStickY))
+            }
+            GamepadAxis::RightStickX | GamepadAxis::RightStickY => {
+                Some((GamepadAxis::RightStickX, GamepadAxis::RightStickY))
+            }
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => None,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Input sources -
+// -----------------------------------------------------------------------------
+/// Somewhere gamepad events come from.
+///
+/// Sources report raw axis values; dead zones are applied by [`Gamepads`].
+pub trait InputSource {
+    /// The events since the last poll. Called once per frame.
+    fn poll(&mut self) -> Vec<Event>;
+
+    /// The name of a gamepad, by the id this source gave it.
+    fn name(&self, _id: GamepadId) -> Option<String> {
+        None
+    }
+}
+
+/// Plays back a list of events, one frame at a time. For tests.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedSource {
+    frames: VecDeque<Vec<Event>>,
+}
+
+impl ScriptedSource {
+    pub fn new(frames: impl IntoIterator<Item = Vec<Event>>) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+        }
+    }
+
+    /// Add a frame of events after the ones already scripted.
+    pub fn push(&mut self, events: Vec<Event>) {
+        self.frames.push_back(events);
+    }
+}
+
+impl InputSource for ScriptedSource {
+    fn poll(&mut self) -> Vec<Event> {
+        self.frames.pop_front().unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "gilrs")]
+pub use self::system::GilrsSource;
+
+#[cfg(feature = "gilrs")]
+mod system {
+    use gilrs::{Axis, Button, EventType, Gilrs};
+
+    use super::{GamepadAxis, GamepadButton, GamepadId, InputSource};
+    use crate::{ElementState, Event};
+
+    /// Gamepads connected to this machine, through gilrs.
+    pub struct GilrsSource(Gilrs);
+
+    impl GilrsSource {
+        pub fn new() -> Result<Self, Box<gilrs::Error>> {
+            Gilrs::new().map(Self).map_err(Box::new)
+        }
+    }
+
+    impl InputSource for GilrsSource {
+        fn poll(&mut self) -> Vec<Event> {
+            let mut events = Vec::new();
+            while let Some(gilrs::Event { id, event, .. }) = self.0.next_event() {
+                let id = GamepadId(id.into());
+                let event = match event {
+                    EventType::ButtonPressed(button, _) => {
+                        button_event(id, button, ElementState::Pressed)
+                    }
+                    EventType::ButtonReleased(button, _) => {
+                        button_event(id, button, ElementState::Released)
+                    }
+                    EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                        Some(Event::GamepadAxis(id, GamepadAxis::LeftTrigger, value))
+                    }
+                    EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                        Some(Event::GamepadAxis(id, GamepadAxis::RightTrigger, value))
+                    }
+                    EventType::AxisChanged(axis, value, _) => {
+                        axis_of(axis).map(|axis| Event::GamepadAxis(id, axis, value))
+                    }
+                    EventType::Connected => Some(Event::GamepadConnected(id)),
+                    EventType::Disconnected => Some(Event::GamepadDisconnected(id)),
+                    _ => None,
+                };
+                events.extend(event);
+            }
+            events
+        }
+
+        /// The name reported by the driver.
+        fn name(&self, id: GamepadId) -> Option<String> {
+            self.0
+                .gamepads()
+                .find(|(gamepad_id, _)| usize::from(*gamepad_id) == id.0)
+                .map(|(_, gamepad)| gamepad.name().to_string())
+        }
+    }
+
+    fn button_event(id: GamepadId, button: Button, state: ElementState) -> Option<Event> {
+        let button = match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftShoulder,
+            Button::RightTrigger => GamepadButton::RightShoulder,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::Mode => GamepadButton::Mode,
+            Button::LeftThumb => GamepadButton::LeftStick,
+            Button::RightThumb => GamepadButton::RightStick,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            Button::C | Button::Z | Button::Unknown => return None,
+        };
+        Some(Event::GamepadButton(id, button, state))
+    }
+
+    fn axis_of(axis: Axis) -> Option<GamepadAxis> {
+        match axis {
+            Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+            Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+            Axis::RightStickX => Some(GamepadAxis::RightStickX),
+            Axis::RightStickY => Some(GamepadAxis::RightStickY),
+            // The d-pad is reported as buttons, and triggers through `ButtonChanged`
+            _ => None,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Gamepads -
+// -----------------------------------------------------------------------------
+/// How far a stick or trigger has to move before it counts, from 0.0 to 1.0.
+/// Past the dead zone, values are scaled to still cover the full range.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DeadZones {
+    /// Applied to the distance from the centre, so diagonals aren't cut off.
+    pub stick: f32,
+    pub trigger: f32,
+}
+
+impl Default for DeadZones {
+    fn default() -> Self {
+        Self {
+            stick: 0.15,
+            trigger: 0.05,
+        }
+    }
+}
+
+/// Collects events from input sources and applies dead zones.
+/// Axis events are only sent when the value after the dead zone changes.
+#[derive(Default)]
+pub struct Gamepads {
+    sources: Vec<Box<dyn InputSource>>,
+    pub dead_zones: DeadZones,
+    // The id of every gamepad by source index and the id the source gave it
+    ids: HashMap<(usize, GamepadId), GamepadId>,
+    raw: HashMap<(GamepadId, GamepadAxis), f32>,
+    sent: HashMap<(GamepadId, GamepadAxis), f32>,
+}
+
+impl Gamepads {
+    /// No sources, so no gamepads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The gamepads connected to this machine. This needs the `gilrs` feature,
+    /// otherwise there are no gamepads.
+    pub fn system() -> Self {
+        #[allow(unused_mut)]
+        let mut gamepads = Self::new();
+        #[cfg(feature = "gilrs")]
+        match GilrsSource::new() {
+            Ok(source) => gamepads.add_source(source),
+            Err(e) => eprintln!("Gamepads are not available: {}", e),
+        }
+        gamepads
+    }
+
+    pub fn add_source(&mut self, source: impl InputSource + 'static) {
+        self.sources.push(Box::new(source));
+    }
+
+    /// The name of a gamepad, if its source knows it.
+    pub fn name(&self, id: GamepadId) -> Option<String> {
+        let (source, local) = self.ids.iter().find(|(_, gamepad)| **gamepad == id)?.0;
+        self.sources[*source].name(*local)
+    }
+
+    /// The events of all sources since the last poll.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let raw = self
+            .sources
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(index, source)| source.poll().into_iter().map(move |e| (index, e)))
+            .collect::<Vec<_>>();
+
+        let mut events = Vec::new();
+        for (source, event) in raw {
+            match self.renumber(source, event) {
+                Event::GamepadAxis(id, axis, value) => {
+                    self.raw.insert((id, axis), value);
+                    let axes = match axis.stick() {
+                        Some((x, y)) => vec![x, y],
+                        None => vec![axis],
+                    };
+                    for axis in axes {
+                        let value = self.value(id, axis);
+                        let sent = self.sent.insert((id, axis), value).unwrap_or(0.0);
+                        if value != sent {
+                            events.push(Event::GamepadAxis(id, axis, value));
+                        }
+                    }
+                }
+                Event::GamepadDisconnected(id) => {
+                    self.raw.retain(|(gamepad, _), _| *gamepad != id);
+                    self.sent.retain(|(gamepad, _), _| *gamepad != id);
+                    events.push(Event::GamepadDisconnected(id));
+                }
+                event => events.push(event),
+            }
+        }
+        events
+    }
+
+    // Two sources can both have a gamepad 0, so ids are handed out in the order
+    // the gamepads are first seen. The id is kept when a gamepad reconnects.
+    fn renumber(&mut self, source: usize, event: Event) -> Event {
+        let mut id = |id| {
+            let next = GamepadId(self.ids.len());
+            *self.ids.entry((source, id)).or_insert(next)
+        };
+
+        match event {
+            Event::GamepadButton(pad, button, state) => {
+                Event::GamepadButton(id(pad), button, state)
+            }
+            Event::GamepadAxis(pad, axis, value) => Event::GamepadAxis(id(pad), axis, value),
+            Event::GamepadConnected(pad) => Event::GamepadConnected(id(pad)),
+            Event::GamepadDisconnected(pad) => Event::GamepadDisconnected(id(pad)),
+            event => event,
+        }
+    }
+
+    // The value of an axis after the dead zone
+    fn value(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        let raw = |axis| self.raw.get(&(id, axis)).copied().unwrap_or(0.0);
+        match axis.stick() {
+            Some((x, y)) => {
+                let length = raw(x).hypot(raw(y));
+                if length <= self.dead_zones.stick {
+                    return 0.0;
+                }
+                let scaled = rescale(length.min(1.0), self.dead_zones.stick);
+                raw(axis) / length * scaled
+            }
+            None => rescale(raw(axis).clamp(0.0, 1.0), self.dead_zones.trigger),
+        }
+    }
+}
+
+fn rescale(value: f32, dead_zone: f32) -> f32 {
+    ((value - dead_zone) / (1.0 - dead_zone)).max(0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ElementState;
+
+    const PAD: GamepadId = GamepadId(0);
+
+    fn axis(axis: GamepadAxis, value: f32) -> Event {
+        Event::GamepadAxis(PAD, axis, value)
+    }
+
+    fn gamepads(frames: Vec<Vec<Event>>) -> Gamepads {
+        let mut gamepads = Gamepads::new();
+        gamepads.dead_zones = DeadZones {
+            stick: 0.2,
+            trigger: 0.5,
+        };
+        gamepads.add_source(ScriptedSource::new(frames));
+        gamepads
+    }
+
+    #[test]
+    fn stick_dead_zone() {
+        let mut gamepads = gamepads(vec![
+            vec![axis(GamepadAxis::LeftStickX, 0.1)],
+            vec![axis(GamepadAxis::LeftStickY, 0.6)],
+            vec![
+                axis(GamepadAxis::LeftStickX, 0.0),
+                axis(GamepadAxis::LeftStickY, 1.0),
+            ],
+        ]);
+
+        // Jitter around the centre is ignored
+        assert!(gamepads.poll().is_empty());
+
+        // Leaving the dead zone moves both axes of the stick
+        let events = gamepads.poll();
+        assert_eq!(events.len(), 2);
+        match events[1] {
+            Event::GamepadAxis(_, GamepadAxis::LeftStickY, y) => assert!(y > 0.5 && y < 0.51),
+            _ => panic!("expected the y axis, got {:?}", events[1]),
+        }
+
+        let events = gamepads.poll();
+        assert_eq!(events.last(), Some(&axis(GamepadAxis::LeftStickY, 1.0)));
+        assert!(events.contains(&axis(GamepadAxis::LeftStickX, 0.0)));
+        assert!(gamepads.poll().is_empty());
+    }
+
+    #[test]
+    fn trigger_dead_zone() {
+        let mut gamepads = gamepads(vec![
+            vec![axis(GamepadAxis::RightTrigger, 0.25)],
+            vec![axis(GamepadAxis::RightTrigger, 0.75)],
+        ]);
+
+        assert!(gamepads.poll().is_empty());
+        assert_eq!(gamepads.poll(), vec![axis(GamepadAxis::RightTrigger, 0.5)]);
+    }
+
+    #[test]
+    fn buttons_and_connections_pass_through() {
+        let press = Event::GamepadButton(PAD, GamepadButton::South, ElementState::Pressed);
+        let mut gamepads = gamepads(vec![
+            vec![Event::GamepadConnected(PAD), press],
+            vec![axis(GamepadAxis::LeftTrigger, 1.0)],
+            vec![Event::GamepadDisconnected(PAD)],
+            vec![axis(GamepadAxis::LeftTrigger, 1.0)],
+        ]);
+
+        assert_eq!(gamepads.poll(), vec![Event::GamepadConnected(PAD), press]);
+        assert_eq!(gamepads.poll().len(), 1);
+        assert_eq!(gamepads.poll(), vec![Event::GamepadDisconnected(PAD)]);
+
+        // Reconnected gamepads start from zero again
+        assert_eq!(gamepads.poll().len(), 1);
+    }
+
+    #[test]
+    fn ids_are_unique_across_sources() {
+        let press =
+            |id| Event::GamepadButton(GamepadId(id), GamepadButton::South, ElementState::Pressed);
+        let mut gamepads = Gamepads::new();
+        gamepads.add_source(ScriptedSource::new(vec![vec![press(0)], vec![press(0)]]));
+        gamepads.add_source(ScriptedSource::new(vec![vec![press(0), press(7)]]));
+
+        assert_eq!(gamepads.poll(), vec![press(0), press(1), press(2)]);
+        assert_eq!(gamepads.poll(), vec![press(0)]);
+    }
+
+    // Names its gamepads after the id it gave them
+    struct Named(ScriptedSource);
+
+    impl InputSource for Named {
+        fn poll(&mut self) -> Vec<Event> {
+            self.0.poll()
+        }
+
+        fn name(&self, id: GamepadId) -> Option<String> {
+            Some(format!("pad {}", id.0))
+        }
+    }
+
+    #[test]
+    fn names_by_renumbered_id() {
+        let connect = |id| Event::GamepadConnected(GamepadId(id));
+        let mut gamepads = Gamepads::new();
+        gamepads.add_source(ScriptedSource::new(vec![vec![connect(0)]]));
+        gamepads.add_source(Named(ScriptedSource::new(vec![vec![connect(7)]])));
+        gamepads.poll();
+
+        assert_eq!(gamepads.name(GamepadId(0)), None);
+        assert_eq!(gamepads.name(GamepadId(1)), Some("pad 7".to_string()));
+        assert_eq!(gamepads.name(GamepadId(2)), None);
+    }
+}
@@ -0,0 +1,837 @@
+//! A small immediate-mode UI drawn into a [`Viewport`].
+//!
+//! Widgets are functions called every frame. They draw themselves and
+//! report what happened to them since the last frame, while the values
+//! they edit stay with the application:
+//!
+//! ```no_run
+//! # use tinypixel::{gui::Ui, ScreenPos, ScreenSize, Viewport};
+//! # let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(200, 100));
+//! let mut ui = Ui::new();
+//! let mut grid = true;
+//!
+//! // Feed `ui.handle(&event)` from `EventLoop::input`, then every frame:
+//! let mut frame = ui.begin(&mut viewport);
+//! frame.row(|row| {
+//!     if row.button("Save") {
+//!         // ...
+//!     }
+//!     row.checkbox("Grid", &mut grid);
+//! });
+//! ```
+use std::ops::RangeInclusive;
+
+use crate::font::{self, GLYPH_HEIGHT, LINE_HEIGHT};
+use crate::{
+    Clipboard, ElementState, Event, KeyEvent, LineEditor, MemoryClipboard, ModifiersState,
+    MouseButton, Pixel, ScreenPos, ScreenRect, ScreenSize, Viewport, VirtualKeyCode,
+};
+
+/// Space between widgets.
+const SPACING: u32 = 2;
+/// Space between the edge of a widget and its content.
+const PADDING: u32 = 2;
+const CHECKBOX_SIZE: u32 = GLYPH_HEIGHT;
+
+// -----------------------------------------------------------------------------
+//     - Theme -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Theme {
+    pub text: Pixel,
+    pub panel: Pixel,
+    pub border: Pixel,
+    pub widget: Pixel,
+    pub hovered: Pixel,
+    pub active: Pixel,
+    pub accent: Pixel,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            text: Pixel::rgb(0xe0, 0xe0, 0xe0),
+            panel: Pixel::rgb(0x20, 0x20, 0x28),
+            border: Pixel::rgb(0x50, 0x50, 0x60),
+            widget: Pixel::rgb(0x34, 0x34, 0x40),
+            hovered: Pixel::rgb(0x44, 0x44, 0x54),
+            active: Pixel::rgb(0x24, 0x24, 0x2c),
+            accent: Pixel::rgb(0x5a, 0x9a, 0xe0),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Input -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Default, Clone)]
+struct Input {
+    // In window pixels
+    mouse: Option<ScreenPos>,
+    down: bool,
+    // Since the last frame
+    pressed: bool,
+    released: bool,
+    scroll: f32,
+    // Typed characters and key presses, in order
+    typed: Vec<Typed>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Typed {
+    Char(char),
+    Key(KeyEvent),
+}
+
+/// Identifies a widget by the order it was created in during a frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct WidgetId(usize);
+
+/// The scroll position and selection of a [`Frame::list`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ListState {
+    pub selected: Option<usize>,
+    /// The first visible item.
+    pub scroll: usize,
+}
+
+// -----------------------------------------------------------------------------
+//     - Ui -
+// -----------------------------------------------------------------------------
+/// Input and interaction state that lives from one frame to the next.
+pub struct Ui {
+    pub theme: Theme,
+    /// Window pixels per viewport pixel, e.g. the upscaler factor.
+    pub scale: u32,
+    /// Used by text fields to cut, copy and paste.
+    pub clipboard: Box<dyn Clipboard>,
+    input: Input,
+    // The widget the mouse button was pressed on
+    active: Option<WidgetId>,
+    // The text field receiving keyboard input
+    focus: Option<WidgetId>,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            theme: Theme::default(),
+            scale: 1,
+            clipboard: Box::new(MemoryClipboard::default()),
+            input: Input::default(),
+            active: None,
+            focus: None,
+        }
+    }
+
+    /// Feed an event from `EventLoop::input`.
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::Key(key) if key.pressed() => self.input.typed.push(Typed::Key(*key)),
+            Event::Key(_) => {}
+            Event::Char(c) => self.type_char(*c),
+            Event::MouseMove(pos) => self.input.mouse = Some(*pos),
+            Event::MouseButton(MouseButton::Left, state) => match state {
+                ElementState::Pressed => {
+                    self.input.down = true;
+                    self.input.pressed = true;
+                }
+                ElementState::Released => {
+                    self.input.down = false;
+                    self.input.released = true;
+                }
+            },
+            Event::MouseButton(..) => {}
+            Event::Scroll(delta) => self.input.scroll += delta.y,
+            Event::GamepadButton(..)
+            | Event::GamepadAxis(..)
+            | Event::GamepadConnected(_)
+            | Event::GamepadDisconnected(_) => {}
+        }
+    }
+
+    /// Feed a key press without modifiers, for keys that edit text.
+    pub fn press_key(&mut self, key: VirtualKeyCode) {
+        self.input.typed.push(Typed::Key(KeyEvent {
+            scancode: 0,
+            state: ElementState::Pressed,
+            key: Some(key),
+            modifiers: ModifiersState::empty(),
+        }));
+    }
+
+    /// Feed a typed character to the focused text field.
+    pub fn type_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.input.typed.push(Typed::Char(c));
+        }
+    }
+
+    /// True if a text field has keyboard focus, so key presses
+    /// should probably not be used for anything else.
+    pub fn wants_keyboard(&self) -> bool {
+        self.focus.is_some()
+    }
+
+    /// Start drawing widgets into a viewport.
+    /// The frame ends, and the input since the last frame is used up,
+    /// when the returned `Frame` is dropped.
+    pub fn begin<'a>(&'a mut self, viewport: &'a mut Viewport) -> Frame<'a> {
+        let scale = self.scale.max(1);
+        let (rect, scroll) = (viewport.rect(), viewport.scroll());
+        // The mouse is only over the ui while it is inside the viewport
+        let mouse = self
+            .input
+            .mouse
+            .map(|pos| pos / scale)
+            .filter(|pos| rect.contains(*pos))
+            .map(|pos| pos - rect.origin.to_vector() + scroll.to_vector());
+
+        Frame {
+            ui: self,
+            viewport,
+            mouse,
+            next_id: 0,
+            layouts: vec![Layout::new(Direction::Column, ScreenPos::zero())],
+            focus_taken: false,
+        }
+    }
+
+    fn end_frame(&mut self, focus_taken: bool) {
+        if self.input.pressed && !focus_taken {
+            self.focus = None;
+        }
+        if !self.input.down {
+            self.active = None;
+        }
+
+        self.input.pressed = false;
+        self.input.released = false;
+        self.input.scroll = 0.0;
+        self.input.typed.clear();
+    }
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Layout -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Direction {
+    Row,
+    Column,
+}
+
+struct Layout {
+    direction: Direction,
+    origin: ScreenPos,
+    // The area used so far
+    size: ScreenSize,
+}
+
+impl Layout {
+    fn new(direction: Direction, origin: ScreenPos) -> Self {
+        Self {
+            direction,
+            origin,
+            size: ScreenSize::zero(),
+        }
+    }
+
+    fn next(&self) -> ScreenPos {
+        let gap = |used: u32| if used > 0 { used + SPACING } else { 0 };
+        match self.direction {
+            Direction::Row => ScreenPos::new(self.origin.x + gap(self.size.width), self.origin.y),
+            Direction::Column => {
+                ScreenPos::new(self.origin.x, self.origin.y + gap(self.size.height))
+            }
+        }
+    }
+
+    fn allocate(&mut self, size: ScreenSize) -> ScreenRect {
+        let rect = ScreenRect::new(self.next(), size);
+        let end = rect.max() - self.origin.to_vector();
+        self.size = self.size.max(ScreenSize::new(end.x, end.y));
+        rect
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Frame -
+// -----------------------------------------------------------------------------
+/// Draws widgets for one frame. Widgets are laid out in a column,
+/// unless they are placed in a [`Frame::row`].
+pub struct Frame<'a> {
+    ui: &'a mut Ui,
+    viewport: &'a mut Viewport,
+    // In viewport canvas pixels
+    mouse: Option<ScreenPos>,
+    next_id: usize,
+    layouts: Vec<Layout>,
+    focus_taken: bool,
+}
+
+struct Response {
+    hovered: bool,
+    clicked: bool,
+    active: bool,
+}
+
+impl<'a> Frame<'a> {
+    /// Lay out the widgets added in `f` from left to right.
+    pub fn row(&mut self, f: impl FnOnce(&mut Self)) {
+        self.nested(Direction::Row, f);
+    }
+
+    /// Lay out the widgets added in `f` from top to bottom.
+    pub fn column(&mut self, f: impl FnOnce(&mut Self)) {
+        self.nested(Direction::Column, f);
+    }
+
+    /// A panel with a background and border. The widgets in `f`
+    /// are placed in a column inside it.
+    pub fn panel(&mut self, size: ScreenSize, f: impl FnOnce(&mut Self)) {
+        let rect = self.allocate(size);
+        let theme = self.ui.theme;
+        self.fill(rect, theme.panel);
+        self.outline(rect, theme.border);
+
+        let inner = rect.origin + ScreenSize::new(PADDING + 1, PADDING + 1).to_vector();
+        self.layouts.push(Layout::new(Direction::Column, inner));
+        f(self);
+        self.layouts.pop();
+    }
+
+    pub fn label(&mut self, text: &str) {
+        let rect = self.allocate(font::text_size(text));
+        let colour = self.ui.theme.text;
+        self.viewport.draw_text(text, rect.origin, colour);
+    }
+
+    /// A button with a text label. Returns true when clicked.
+    pub fn button(&mut self, text: &str) -> bool {
+        let size = font::text_size(text) + ScreenSize::new(PADDING * 2, PADDING * 2);
+        let rect = self.allocate(size);
+        let response = self.interact(rect);
+
+        let theme = self.ui.theme;
+        let background = match (response.active, response.hovered) {
+            (true, _) => theme.active,
+            (false, true) => theme.hovered,
+            (false, false) => theme.widget,
+        };
+        self.fill(rect, background);
+        self.outline(rect, theme.border);
+        self.text(text, rect, theme.text);
+
+        response.clicked
+    }
+
+    /// A checkbox with a label. Returns true when toggled.
+    pub fn checkbox(&mut self, text: &str, checked: &mut bool) -> bool {
+        let label = font::text_size(text);
+        let size = ScreenSize::new(
+            CHECKBOX_SIZE + SPACING + label.width,
+            CHECKBOX_SIZE.max(label.height),
+        );
+        let rect = self.allocate(size);
+        let response = self.interact(rect);
+        if response.clicked {
+            *checked = !*checked;
+        }
+
+        let theme = self.ui.theme;
+        let tick = ScreenRect::new(rect.origin, ScreenSize::new(CHECKBOX_SIZE, CHECKBOX_SIZE));
+        let background = if response.hovered {
+            theme.hovered
+        } else {
+            theme.widget
+        };
+        self.fill(tick, background);
+        self.outline(tick, theme.border);
+        if *checked {
+            let inner = ScreenRect::new(
+                tick.origin + ScreenSize::new(2, 2).to_vector(),
+                ScreenSize::new(CHECKBOX_SIZE - 4, CHECKBOX_SIZE - 4),
+            );
+            self.fill(inner, theme.accent);
+        }
+        let text_pos = ScreenPos::new(tick.max_x() + SPACING, rect.origin.y);
+        self.viewport.draw_text(text, text_pos, theme.text);
+
+        response.clicked
+    }
+
+    /// A horizontal slider. Returns true when the value changed.
+    pub fn slider(&mut self, value: &mut f32, range: RangeInclusive<f32>, width: u32) -> bool {
+        let rect = self.allocate(ScreenSize::new(width.max(3), CHECKBOX_SIZE));
+        let response = self.interact(rect);
+        let (min, max) = (*range.start(), *range.end());
+
+        let previous = *value;
+        if let (true, Some(mouse)) = (response.active, self.mouse) {
+            let x = mouse
+                .x
+                .saturating_sub(rect.origin.x)
+                .min(rect.size.width - 1);
+            let t = x as f32 / (rect.size.width - 1) as f32;
+            *value = min + (max - min) * t;
+        }
+        *value = value.max(min.min(max)).min(max.max(min));
+
+        let theme = self.ui.theme;
+        let track = ScreenRect::new(
+            ScreenPos::new(rect.origin.x, rect.center().y),
+            ScreenSize::new(rect.size.width, 1),
+        );
+        self.fill(track, theme.border);
+
+        let t = if max > min {
+            (*value - min) / (max - min)
+        } else {
+            0.0
+        };
+        let x = rect.origin.x + (t * (rect.size.width - 1) as f32).round() as u32;
+        let handle = ScreenRect::new(
+            ScreenPos::new(x.saturating_sub(1), rect.origin.y),
+            ScreenSize::new(3, rect.size.height),
+        );
+        let colour = if response.hovered || response.active {
+            theme.accent
+        } else {
+            theme.text
+        };
+        self.fill(handle, colour);
+
+        *value != previous
+    }
+
+    /// A single line text field. Click it to type into it.
+    /// Returns true when the text changed.
+    pub fn text_field(&mut self, editor: &mut LineEditor, width: u32) -> bool {
+        let size = ScreenSize::new(width, GLYPH_HEIGHT + PADDING * 2);
+        let rect = self.allocate(size);
+        let id = WidgetId(self.next_id);
+        let response = self.interact(rect);
+        if response.active && self.ui.input.pressed {
+            self.ui.focus = Some(id);
+            self.focus_taken = true;
+        }
+
+        let mut changed = false;
+        if self.ui.focus == Some(id) {
+            for typed in &self.ui.input.typed {
+                match typed {
+                    Typed::Char(c) => changed |= editor.insert_char(*c),
+                    Typed::Key(key) => match key.key {
+                        Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Escape) => {
+                            self.ui.focus = None;
+                            break;
+                        }
+                        _ => changed |= editor.handle_key(*key, &mut *self.ui.clipboard),
+                    },
+                }
+            }
+        }
+        let focused = self.ui.focus == Some(id);
+
+        let theme = self.ui.theme;
+        self.fill(rect, theme.active);
+        self.outline(rect, if focused { theme.accent } else { theme.border });
+
+        // Scroll the text so the cursor is visible
+        let columns = (rect.size.width.saturating_sub(PADDING * 2) / font::ADVANCE) as usize;
+        let first = (editor.cursor() + 1).saturating_sub(columns);
+        let visible = editor.text().chars().skip(first).take(columns);
+        let visible = visible.collect::<String>();
+        let pos = rect.origin + ScreenSize::new(PADDING, PADDING).to_vector();
+        let column_x = |column: usize| pos.x + column.saturating_sub(first) as u32 * font::ADVANCE;
+
+        if let (true, Some(selection)) = (focused, editor.selection()) {
+            let start = column_x(selection.start.max(first));
+            let end = column_x(selection.end.min(first + columns));
+            let highlight = ScreenRect::new(
+                ScreenPos::new(start, pos.y),
+                ScreenSize::new(end.saturating_sub(start), GLYPH_HEIGHT),
+            );
+            self.fill(highlight, theme.accent);
+        }
+        self.viewport.draw_text(&visible, pos, theme.text);
+
+        if focused {
+            let cursor = ScreenRect::new(
+                ScreenPos::new(column_x(editor.cursor()), pos.y),
+                ScreenSize::new(1, GLYPH_HEIGHT),
+            );
+            self.fill(cursor, theme.text);
+        }
+
+        changed
+    }
+
+    /// A scrollable list showing `rows` items at a time.
+    /// Click an item to select it, or scroll with the mouse wheel.
+    /// Returns true when the selection changed.
+    pub fn list<S: AsRef<str>>(
+        &mut self,
+        items: &[S],
+        state: &mut ListState,
+        width: u32,
+        rows: u32,
+    ) -> bool {
+        let rect = self.allocate(ScreenSize::new(
+            width.max(3),
+            rows * LINE_HEIGHT + PADDING * 2,
+        ));
+        let response = self.interact(rect);
+
+        let max_scroll = items.len().saturating_sub(rows as usize);
+        if response.hovered {
+            let lines = self.ui.input.scroll.round() as isize;
+            state.scroll = (state.scroll as isize - lines).max(0) as usize;
+        }
+        state.scroll = state.scroll.min(max_scroll);
+
+        let previous = state.selected;
+        // Clicks in the padding around the rows select nothing
+        let row_at = |y: u32| {
+            let row = y.checked_sub(rect.origin.y + PADDING)? / LINE_HEIGHT;
+            if row < rows {
+                Some(row as usize)
+            } else {
+                None
+            }
+        };
+        if let (true, Some(mouse)) = (response.clicked, self.mouse) {
+            if let Some(row) = row_at(mouse.y) {
+                let index = state.scroll + row;
+                if index < items.len() {
+                    state.selected = Some(index);
+                }
+            }
+        }
+
+        let theme = self.ui.theme;
+        self.fill(rect, theme.active);
+        self.outline(rect, theme.border);
+
+        let visible = items
+            .iter()
+            .enumerate()
+            .skip(state.scroll)
+            .take(rows as usize);
+        for (row, (index, item)) in visible.enumerate() {
+            let y = rect.origin.y + PADDING + row as u32 * LINE_HEIGHT;
+            if state.selected == Some(index) {
+                let highlight = ScreenRect::new(
+                    ScreenPos::new(rect.origin.x + 1, y),
+                    ScreenSize::new(rect.size.width - 2, LINE_HEIGHT),
+                );
+                self.fill(highlight, theme.accent);
+            }
+            let pos = ScreenPos::new(rect.origin.x + PADDING, y);
+            self.viewport.draw_text(item.as_ref(), pos, theme.text);
+        }
+
+        // A scroll bar, if not everything fits
+        if max_scroll > 0 {
+            let height = rect.size.height - 2;
+            let bar = (height * rows / items.len() as u32).max(1);
+            let y = (height - bar) * state.scroll as u32 / max_scroll as u32;
+            let bar = ScreenRect::new(
+                ScreenPos::new(rect.max_x() - 2, rect.origin.y + 1 + y),
+                ScreenSize::new(1, bar),
+            );
+            self.fill(bar, theme.text);
+        }
+
+        state.selected != previous
+    }
+
+    fn nested(&mut self, direction: Direction, f: impl FnOnce(&mut Self)) {
+        let origin = self.layout().next();
+        self.layouts.push(Layout::new(direction, origin));
+        f(self);
+        let size = self
+            .layouts
+            .pop()
+            .map(|layout| layout.size)
+            .unwrap_or_default();
+        self.allocate(size);
+    }
+
+    fn layout(&mut self) -> &mut Layout {
+        self.layouts
+            .last_mut()
+            .expect("the root layout is never removed")
+    }
+
+    fn allocate(&mut self, size: ScreenSize) -> ScreenRect {
+        self.layout().allocate(size)
+    }
+
+    fn interact(&mut self, rect: ScreenRect) -> Response {
+        let id = WidgetId(self.next_id);
+        self.next_id += 1;
+
+        let hovered = self.mouse.map(|pos| rect.contains(pos)).unwrap_or(false);
+        if hovered && self.ui.input.pressed {
+            self.ui.active = Some(id);
+        }
+
+        let active = self.ui.active == Some(id);
+        Response {
+            hovered,
+            clicked: active && hovered && self.ui.input.released,
+            active,
+        }
+    }
+
+    fn text(&mut self, text: &str, rect: ScreenRect, colour: Pixel) {
+        let size = font::text_size(text);
+        let x = rect.origin.x + rect.size.width.saturating_sub(size.width) / 2;
+        let y = rect.origin.y + rect.size.height.saturating_sub(size.height) / 2;
+        self.viewport.draw_text(text, ScreenPos::new(x, y), colour);
+    }
+
+    fn fill(&mut self, rect: ScreenRect, colour: Pixel) {
+        for y in rect.min_y()..rect.max_y() {
+            for x in rect.min_x()..rect.max_x() {
+                self.viewport.draw_pixel(colour, ScreenPos::new(x, y));
+            }
+        }
+    }
+
+    fn outline(&mut self, rect: ScreenRect, colour: Pixel) {
+        if rect.is_empty() {
+            return;
+        }
+        let (min, max) = (rect.min(), rect.max() - ScreenSize::new(1, 1).to_vector());
+        for x in min.x..=max.x {
+            self.viewport.draw_pixel(colour, ScreenPos::new(x, min.y));
+            self.viewport.draw_pixel(colour, ScreenPos::new(x, max.y));
+        }
+        for y in min.y..=max.y {
+            self.viewport.draw_pixel(colour, ScreenPos::new(min.x, y));
+            self.viewport.draw_pixel(colour, ScreenPos::new(max.x, y));
+        }
+    }
+}
+
+impl Drop for Frame<'_> {
+    fn drop(&mut self) {
+        self.ui.end_frame(self.focus_taken);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn viewport() -> Viewport {
+        Viewport::new(ScreenPos::new(10, 10), ScreenSize::new(100, 100))
+    }
+
+    // Move to a position in viewport pixels
+    fn move_to(ui: &mut Ui, x: u32, y: u32) {
+        ui.handle(&Event::MouseMove(ScreenPos::new(x + 10, y + 10)));
+    }
+
+    // Press and release the mouse button, drawing a frame after each
+    fn click(ui: &mut Ui, viewport: &mut Viewport, mut f: impl FnMut(&mut Frame<'_>)) {
+        ui.handle(&Event::MouseButton(
+            MouseButton::Left,
+            ElementState::Pressed,
+        ));
+        f(&mut ui.begin(viewport));
+        ui.handle(&Event::MouseButton(
+            MouseButton::Left,
+            ElementState::Released,
+        ));
+        f(&mut ui.begin(viewport));
+    }
+
+    #[test]
+    fn buttons_click_on_release() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        move_to(&mut ui, 2, 2);
+
+        ui.handle(&Event::MouseButton(
+            MouseButton::Left,
+            ElementState::Pressed,
+        ));
+        assert!(!ui.begin(&mut viewport).button("Ok"));
+        ui.handle(&Event::MouseButton(
+            MouseButton::Left,
+            ElementState::Released,
+        ));
+        assert!(ui.begin(&mut viewport).button("Ok"));
+        assert!(!ui.begin(&mut viewport).button("Ok"));
+
+        // Drawn with a border and a label
+        let pixels = viewport.pixels();
+        assert!(pixels.contains(&(ui.theme.border, ScreenPos::new(10, 10))));
+        assert!(pixels.iter().any(|(pixel, _)| *pixel == ui.theme.text));
+    }
+
+    #[test]
+    fn clicks_outside_the_viewport_are_ignored() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        ui.handle(&Event::MouseMove(ScreenPos::new(9, 9)));
+
+        let mut clicked = false;
+        click(&mut ui, &mut viewport, |frame| {
+            clicked |= frame.button("Ok")
+        });
+        assert!(!clicked);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        let mut clicked = Vec::new();
+        let mut layout = |frame: &mut Frame<'_>| {
+            frame.row(|row| {
+                // "A" is 9x11 with padding, so "B" starts at x = 11
+                clicked.push(row.button("A"));
+                clicked.push(row.button("B"));
+            });
+            // Below the row
+            clicked.push(frame.button("C"));
+        };
+
+        move_to(&mut ui, 20, 13);
+        click(&mut ui, &mut viewport, &mut layout);
+        move_to(&mut ui, 3, 14);
+        click(&mut ui, &mut viewport, &mut layout);
+
+        let expected = [false, false, false, false, false, false];
+        assert_eq!(clicked[..6], expected);
+        assert_eq!(clicked[6..], [false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn checkbox_and_slider() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        let mut checked = false;
+        let mut value = 0.0;
+        let mut changed = Vec::new();
+        let mut widgets = |frame: &mut Frame<'_>| {
+            frame.checkbox("Grid", &mut checked);
+            changed.push(frame.slider(&mut value, 0.0..=10.0, 11));
+        };
+
+        move_to(&mut ui, 1, 1);
+        click(&mut ui, &mut viewport, &mut widgets);
+
+        // Drag the slider handle, which is below the checkbox
+        move_to(&mut ui, 2, 10);
+        ui.handle(&Event::MouseButton(
+            MouseButton::Left,
+            ElementState::Pressed,
+        ));
+        widgets(&mut ui.begin(&mut viewport));
+
+        // Keeps following the mouse outside of the slider until released
+        move_to(&mut ui, 50, 50);
+        widgets(&mut ui.begin(&mut viewport));
+        assert_eq!(changed, vec![false, false, true, true]);
+        assert!(checked);
+        assert_eq!(value, 10.0);
+    }
+
+    #[test]
+    fn text_field_focus_and_typing() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        let mut text = LineEditor::new("ab");
+
+        ui.type_char('x');
+        assert!(!ui.begin(&mut viewport).text_field(&mut text, 40));
+        assert_eq!(text.text(), "ab");
+
+        move_to(&mut ui, 5, 5);
+        click(&mut ui, &mut viewport, |frame| {
+            frame.text_field(&mut text, 40);
+        });
+        assert!(ui.wants_keyboard());
+
+        ui.type_char('c');
+        ui.press_key(VirtualKeyCode::Left);
+        ui.press_key(VirtualKeyCode::Back);
+        ui.handle(&Event::Char('d'));
+        assert!(ui.begin(&mut viewport).text_field(&mut text, 40));
+        assert_eq!(text.text(), "adc");
+
+        // Clicking elsewhere removes focus
+        move_to(&mut ui, 90, 90);
+        click(&mut ui, &mut viewport, |frame| {
+            frame.text_field(&mut text, 40);
+        });
+        assert!(!ui.wants_keyboard());
+    }
+
+    #[test]
+    fn list_scroll_and_select() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        let items = ["one", "two", "three", "four", "five"];
+        let mut state = ListState::default();
+        let mut changed = Vec::new();
+
+        move_to(&mut ui, 5, 5);
+        ui.handle(&Event::Scroll(crate::Vec2D::new(0.0, -10.0)));
+        changed.push(ui.begin(&mut viewport).list(&items, &mut state, 40, 2));
+        assert_eq!(state.scroll, 3);
+
+        // The second visible row
+        move_to(&mut ui, 5, 12);
+        click(&mut ui, &mut viewport, |frame| {
+            changed.push(frame.list(&items, &mut state, 40, 2));
+        });
+        assert_eq!(state.selected, Some(4));
+        assert_eq!(changed, vec![false, false, true]);
+
+        // The bottom padding, below the last visible row
+        state.selected = None;
+        state.scroll = 0;
+        move_to(&mut ui, 5, 2 * LINE_HEIGHT + PADDING);
+        click(&mut ui, &mut viewport, |frame| {
+            frame.list(&items, &mut state, 40, 2);
+        });
+        assert_eq!(state.selected, None);
+
+        // Too narrow for the highlight
+        state.selected = Some(3);
+        ui.begin(&mut viewport).list(&items, &mut state, 0, 2);
+    }
+
+    #[test]
+    fn panels_contain_widgets() {
+        let mut ui = Ui::new();
+        let mut viewport = viewport();
+        let mut frame = ui.begin(&mut viewport);
+        frame.panel(ScreenSize::new(30, 20), |panel| panel.label("Hi"));
+        frame.label("After");
+        drop(frame);
+
+        let pixels = viewport.pixels();
+        let theme = Theme::default();
+        assert!(pixels.contains(&(theme.panel, ScreenPos::new(11, 11))));
+        assert!(pixels.contains(&(theme.border, ScreenPos::new(39, 29))));
+        // The label after the panel starts below it
+        let text = |y| pixels.iter().any(|(p, pos)| *p == theme.text && pos.y == y);
+        assert!(text(13));
+        assert!(!text(31));
+        assert!(text(32));
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{Pixel, PixelBuffer, ScreenPos, ScreenRect, ScreenSize};
+
+// -----------------------------------------------------------------------------
+//     - Image -
+// -----------------------------------------------------------------------------
+/// A pixel buffer that knows its own width and height.
+/// Pixels are stored row by row, starting in the top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    size: ScreenSize,
+    pub(crate) buf: PixelBuffer,
+}
+
+impl Image {
+    /// Create a new image where every pixel is transparent.
+    pub fn empty(size: ScreenSize) -> Self {
+        Self {
+            size,
+            buf: PixelBuffer::empty((size.width * size.height) as usize),
+        }
+    }
+
+    /// Create a new image filled with one pixel.
+    pub fn new(size: ScreenSize, pixel: Pixel) -> Self {
+        Self {
+            size,
+            buf: PixelBuffer::new((size.width * size.height) as usize, pixel),
+        }
+    }
+
+    /// Create an image from existing pixels.
+    /// Panics if the number of pixels does not match the size.
+    pub fn from_pixels(size: ScreenSize, pixels: Vec<Pixel>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (size.width * size.height) as usize,
+            "pixel count does not match image size"
+        );
+
+        Self {
+            size,
+            buf: PixelBuffer { inner: pixels },
+        }
+    }
+
+    pub fn size(&self) -> ScreenSize {
+        self.size
+    }
+
+    pub fn width(&self) -> u32 {
+        self.size.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.size.height
+    }
+
+    /// A rect covering the entire image.
+    pub fn rect(&self) -> ScreenRect {
+        ScreenRect::from_size(self.size)
+    }
+
+    /// Get the pixel at x, y, or `None` if the position is outside of the image.
+    pub fn get(&self, x: u32, y: u32) -> Option<Pixel> {
+        self.index(x, y).map(|index| self.buf.inner[index])
+    }
+
+    /// Set the pixel at x, y.
+    /// Positions outside of the image are ignored.
+    pub fn set(&mut self, x: u32, y: u32, pixel: Pixel) {
+        if let Some(index) = self.index(x, y) {
+            self.buf.set_pixel(index, pixel);
+        }
+    }
+
+    /// All pixels, row by row.
+    pub fn pixels(&self) -> &[Pixel] {
+        &self.buf.inner
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Pixel] {
+        &mut self.buf.inner
+    }
+
+    /// The raw RGBA bytes of the image.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// A hash of the size and pixels (64 bit FNV-1a).
+    /// Unlike `std::hash`, this is the same on every platform and Rust version,
+    /// so it can be stored and compared later.
+    pub fn content_hash(&self) -> u64 {
+        let size = [self.width().to_le_bytes(), self.height().to_le_bytes()];
+        size.iter()
+            .flatten()
+            .chain(self.as_bytes())
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// A single row of pixels.
+    /// Panics if `y` is outside of the image.
+    pub fn row(&self, y: u32) -> &[Pixel] {
+        let start = (y * self.size.width) as usize;
+        &self.buf.inner[start..start + self.size.width as usize]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [Pixel] {
+        let start = (y * self.size.width) as usize;
+        &mut self.buf.inner[start..start + self.size.width as usize]
+    }
+
+    /// Iterate over the rows of the image, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Pixel]> {
+        self.buf.inner.chunks(self.size.width.max(1) as usize)
+    }
+
+    /// Iterate over every position in the image, row by row.
+    pub fn coords(&self) -> Coords {
+        Coords::new(self.size)
+    }
+
+    /// Iterate over every pixel along with its position.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (ScreenPos, &Pixel)> {
+        self.coords().zip(self.buf.inner.iter())
+    }
+
+    /// Fill the entire image with one pixel.
+    pub fn fill(&mut self, pixel: Pixel) {
+        self.buf.inner.iter_mut().for_each(|p| *p = pixel);
+    }
+
+    /// A view of the entire image.
+    pub fn as_view(&self) -> ImageView<'_> {
+        ImageView {
+            rect: self.rect(),
+            image: self,
+        }
+    }
+
+    /// A view of a part of the image.
+    /// The rect is clipped to the image.
+    pub fn view(&self, rect: ScreenRect) -> ImageView<'_> {
+        ImageView {
+            rect: self.clip(rect),
+            image: self,
+        }
+    }
+
+    /// A mutable view of a part of the image.
+    /// The rect is clipped to the image.
+    pub fn view_mut(&mut self, rect: ScreenRect) -> ImageViewMut<'_> {
+        ImageViewMut {
+            rect: self.clip(rect),
+            image: self,
+        }
+    }
+
+    /// Copy the pixels inside `src` from `other` into this image, placing
+    /// the top left corner of `src` at `dst`.
+    /// Anything that falls outside of either image is skipped.
+    pub fn copy_from(&mut self, other: &Image, src: ScreenRect, dst: ScreenPos) {
+        let src = other.clip(src);
+        let dst_rect = self.clip(ScreenRect::new(dst, src.size));
+
+        for y in 0..dst_rect.size.height {
+            let from = other.index(src.origin.x, src.origin.y + y).unwrap_or(0);
+            let to = self.index(dst_rect.origin.x, dst_rect.origin.y + y).unwrap_or(0);
+            let len = dst_rect.size.width as usize;
+            self.buf.inner[to..to + len].copy_from_slice(&other.buf.inner[from..from + len]);
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.size.width && y < self.size.height {
+            Some((y * self.size.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn clip(&self, rect: ScreenRect) -> ScreenRect {
+        rect.intersection(&self.rect())
+            .unwrap_or_else(|| ScreenRect::new(rect.origin, ScreenSize::zero()))
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Image view -
+// -----------------------------------------------------------------------------
+/// A rectangular part of an image.
+/// All positions are relative to the top left corner of the view.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a> {
+    image: &'a Image,
+    rect: ScreenRect,
+}
+
+impl<'a> ImageView<'a> {
+    pub fn size(&self) -> ScreenSize {
+        self.rect.size
+    }
+
+    pub fn width(&self) -> u32 {
+        self.rect.size.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rect.size.height
+    }
+
+    /// The area of the underlying image covered by this view.
+    pub fn rect(&self) -> ScreenRect {
+        self.rect
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Pixel> {
+        if x < self.width() && y < self.height() {
+            self.image.get(self.rect.origin.x + x, self.rect.origin.y + y)
+        } else {
+            None
+        }
+    }
+
+    /// A single row of the view.
+    /// Panics if `y` is outside of the view.
+    pub fn row(&self, y: u32) -> &'a [Pixel] {
+        assert!(y < self.height(), "row outside of view");
+        let start = self.rect.origin.x as usize;
+        &self.image.row(self.rect.origin.y + y)[start..start + self.width() as usize]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [Pixel]> {
+        let view = *self;
+        (0..self.height()).map(move |y| view.row(y))
+    }
+
+    pub fn coords(&self) -> Coords {
+        Coords::new(self.size())
+    }
+
+    /// A view inside this view.
+    pub fn view(&self, rect: ScreenRect) -> ImageView<'a> {
+        let rect = rect.translate(self.rect.origin.to_vector());
+        ImageView {
+            image: self.image,
+            rect: rect
+                .intersection(&self.rect)
+                .unwrap_or_else(|| ScreenRect::new(rect.origin, ScreenSize::zero())),
+        }
+    }
+
+    /// Copy the view into a new image.
+    pub fn to_image(&self) -> Image {
+        let mut image = Image::empty(self.size());
+        image.copy_from(self.image, self.rect, ScreenPos::zero());
+        image
+    }
+}
+
+impl<'a> From<&'a Image> for ImageView<'a> {
+    fn from(image: &'a Image) -> Self {
+        image.as_view()
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Mutable image view -
+// -----------------------------------------------------------------------------
+/// A mutable rectangular part of an image.
+/// All positions are relative to the top left corner of the view.
+#[derive(Debug)]
+pub struct ImageViewMut<'a> {
+    image: &'a mut Image,
+    rect: ScreenRect,
+}
+
+impl<'a> ImageViewMut<'a> {
+    pub fn size(&self) -> ScreenSize {
+        self.rect.size
+    }
+
+    pub fn width(&self) -> u32 {
+        self.rect.size.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rect.size.height
+    }
+
+    pub fn rect(&self) -> ScreenRect {
+        self.rect
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Pixel> {
+        if x < self.width() && y < self.height() {
+            self.image.get(self.rect.origin.x + x, self.rect.origin.y + y)
+        } else {
+            None
+        }
+    }
+
+    /// Set a pixel inside the view.
+    /// Positions outside of the view are ignored.
+    pub fn set(&mut self, x: u32, y: u32, pixel: Pixel) {
+        if x < self.width() && y < self.height() {
+            self.image.set(self.rect.origin.x + x, self.rect.origin.y + y, pixel);
+        }
+    }
+
+    pub fn row(&self, y: u32) -> &[Pixel] {
+        assert!(y < self.height(), "row outside of view");
+        let start = self.rect.origin.x as usize;
+        &self.image.row(self.rect.origin.y + y)[start..start + self.width() as usize]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [Pixel] {
+        assert!(y < self.height(), "row outside of view");
+        let start = self.rect.origin.x as usize;
+        let width = self.width() as usize;
+        &mut self.image.row_mut(self.rect.origin.y + y)[start..start + width]
+    }
+
+    pub fn coords(&self) -> Coords {
+        Coords::new(self.size())
+    }
+
+    pub fn fill(&mut self, pixel: Pixel) {
+        for y in 0..self.height() {
+            self.row_mut(y).iter_mut().for_each(|p| *p = pixel);
+        }
+    }
+
+    /// Copy pixels from another image into this view.
+    /// See [`Image::copy_from`].
+    pub fn copy_from(&mut self, other: &Image, src: ScreenRect, dst: ScreenPos) {
+        let size = ScreenSize::new(
+            src.size.width.min(self.width().saturating_sub(dst.x)),
+            src.size.height.min(self.height().saturating_sub(dst.y)),
+        );
+        let dst = ScreenPos::new(self.rect.origin.x + dst.x, self.rect.origin.y + dst.y);
+        self.image.copy_from(other, ScreenRect::new(src.origin, size), dst);
+    }
+
+    pub fn as_view(&self) -> ImageView<'_> {
+        ImageView {
+            image: self.image,
+            rect: self.rect,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Coords -
+// -----------------------------------------------------------------------------
+/// Iterator over every position inside a size, row by row.
+#[derive(Debug, Clone)]
+pub struct Coords {
+    size: ScreenSize,
+    index: u32,
+}
+
+impl Coords {
+    pub fn new(size: ScreenSize) -> Self {
+        Self { size, index: 0 }
+    }
+}
+
+impl Iterator for Coords {
+    type Item = ScreenPos;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.size.width * self.size.height {
+            return None;
+        }
+
+        let pos = ScreenPos::new(self.index % self.size.width, self.index / self.size.width);
+        self.index += 1;
+        Some(pos)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.size.width * self.size.height - self.index) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Coords {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: Pixel = Pixel::new(255, 0, 0, 255);
+    const BLUE: Pixel = Pixel::new(0, 0, 255, 255);
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> ScreenRect {
+        ScreenRect::new(ScreenPos::new(x, y), ScreenSize::new(width, height))
+    }
+
+    #[test]
+    fn get_and_set() {
+        let mut image = Image::empty(ScreenSize::new(4, 3));
+        image.set(3, 2, RED);
+        image.set(4, 0, RED);
+
+        assert_eq!(image.get(3, 2), Some(RED));
+        assert_eq!(image.get(0, 0), Some(Pixel::zero()));
+        assert_eq!(image.get(4, 0), None);
+        assert_eq!(image.pixels()[11], RED);
+    }
+
+    #[test]
+    fn rows() {
+        let mut image = Image::empty(ScreenSize::new(3, 2));
+        image.row_mut(1).iter_mut().for_each(|p| *p = BLUE);
+
+        assert_eq!(image.rows().count(), 2);
+        assert_eq!(image.row(0), &[Pixel::zero(); 3]);
+        assert_eq!(image.row(1), &[BLUE; 3]);
+    }
+
+    #[test]
+    fn coords_row_by_row() {
+        let coords = Coords::new(ScreenSize::new(2, 2)).collect::<Vec<_>>();
+        let expected = vec![
+            ScreenPos::new(0, 0),
+            ScreenPos::new(1, 0),
+            ScreenPos::new(0, 1),
+            ScreenPos::new(1, 1),
+        ];
+        assert_eq!(coords, expected);
+    }
+
+    #[test]
+    fn views_are_clipped() {
+        let mut image = Image::empty(ScreenSize::new(4, 4));
+        image.set(2, 2, RED);
+
+        let view = image.view(rect(2, 2, 10, 10));
+        assert_eq!(view.size(), ScreenSize::new(2, 2));
+        assert_eq!(view.get(0, 0), Some(RED));
+        assert_eq!(view.get(2, 0), None);
+
+        let inner = view.view(rect(0, 0, 1, 1));
+        assert_eq!(inner.to_image(), Image::new(ScreenSize::new(1, 1), RED));
+    }
+
+    #[test]
+    fn mutable_view() {
+        let mut image = Image::empty(ScreenSize::new(4, 4));
+        let mut view = image.view_mut(rect(1, 1, 2, 2));
+        view.fill(BLUE);
+        view.set(5, 5, RED);
+
+        assert_eq!(image.get(0, 0), Some(Pixel::zero()));
+        assert_eq!(image.get(1, 1), Some(BLUE));
+        assert_eq!(image.get(2, 2), Some(BLUE));
+        assert_eq!(image.get(3, 3), Some(Pixel::zero()));
+    }
+
+    #[test]
+    fn copy_from_clips_both_images() {
+        let src = Image::new(ScreenSize::new(3, 3), RED);
+        let mut dst = Image::empty(ScreenSize::new(4, 4));
+        dst.copy_from(&src, rect(1, 1, 5, 5), ScreenPos::new(2, 2));
+
+        let red = dst.enumerate_pixels().filter(|(_, p)| **p == RED).count();
+        assert_eq!(red, 4);
+        assert_eq!(dst.get(1, 1), Some(Pixel::zero()));
+        assert_eq!(dst.get(3, 3), Some(RED));
+    }
+}
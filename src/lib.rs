@@ -1,23 +1,66 @@
+mod actions;
+pub mod aseprite;
+pub mod colours;
+mod compositor;
+pub mod font;
+mod gamepad;
+pub mod gui;
 mod events;
+mod image;
+mod palette;
+mod particles;
 mod pixel;
+mod postprocess;
+mod quantize;
+mod record;
 mod renderer;
+mod resize;
+mod screenshot;
+mod shader;
+mod text;
 mod viewport;
 mod texture;
+mod tilemap;
+mod timing;
+pub mod tiled;
+mod transform;
+mod upscale;
 
 // -----------------------------------------------------------------------------
 //     - Reexports -
 // -----------------------------------------------------------------------------
-pub use events::{start, EventLoop, Event};
-pub use pixel::{Pixel, PixelBuffer};
+pub use actions::{Actions, Binding, BindingsError, Button};
+pub use compositor::{Compositor, ViewportId};
+pub use events::{start, EventLoop, Event, KeyEvent, Lifecycle};
+#[cfg(feature = "gilrs")]
+pub use gamepad::GilrsSource;
+pub use gamepad::{
+    DeadZones, GamepadAxis, GamepadButton, GamepadId, Gamepads, InputSource, ScriptedSource,
+};
+pub use image::{Coords, Image, ImageView, ImageViewMut};
+pub use palette::{IndexedImage, Palette, PaletteCycle};
+pub use particles::{Emitter, Gradient, Particle, ParticleShape, ParticleSystem, Rng};
+pub use pixel::{ParseColourError, Pixel, PixelBuffer};
+pub use postprocess::{PassUniforms, PostPass};
+pub use quantize::{quantize, Dither, Oklab};
+pub use record::{Desync, Recorded, RecordedFrame, Recorder, Recording};
 pub use renderer::Renderer;
+pub use resize::{effective_size, SizeChange, SizePolicy, MAX_TEXTURE_DIMENSION};
+pub use shader::{compile_fragment, compile_vertex, ShaderError, ShaderLanguage, ShaderWatcher};
+pub use text::{Clipboard, LineEditor, MemoryClipboard};
+pub use tilemap::{Tile, TileAnimation, TileLayer, Tilemap, Tileset};
+pub use timing::{FrameLimiter, FrameStage, FrameStats, StatsOverlay, Summary};
+pub use transform::ResizeFilter;
+pub use upscale::{eagle, scale2x, scale3x, xbr, Upscaler};
 pub use viewport::Viewport;
 
 // -----------------------------------------------------------------------------
 //     - Winit -
 // -----------------------------------------------------------------------------
-pub use winit::event::{VirtualKeyCode, KeyboardInput, ElementState};
+pub use winit::event::{VirtualKeyCode, KeyboardInput, ElementState, ModifiersState, MouseButton};
 pub use winit::event_loop::EventLoop as WinitEventLoop;
 pub use winit::window::WindowBuilder;
+pub use wgpu::PresentMode;
 
 // -----------------------------------------------------------------------------
 //     - Euclid -
@@ -37,7 +80,7 @@ pub type ScreenPos = euclid::Point2D<u32, Screen>;
 pub type WorldPos = euclid::Point2D<f32, World>;
 
 /// A rect on screen
-pub type ScreenRect = euclid::Rect<u16, Screen>;
+pub type ScreenRect = euclid::Rect<u32, Screen>;
 
 /// A rect in the world
 pub type WorldRect = euclid::Rect<f32, World>;
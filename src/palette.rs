@@ -0,0 +1,318 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::{Image, Pixel, ScreenPos, ScreenSize};
+
+const fn hex(rgb: u32) -> Pixel {
+    Pixel::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+}
+
+#[rustfmt::skip]
+const PICO8: [Pixel; 16] = [
+    hex(0x000000), hex(0x1d2b53), hex(0x7e2553), hex(0x008751),
+    hex(0xab5236), hex(0x5f574f), hex(0xc2c3c7), hex(0xfff1e8),
+    hex(0xff004d), hex(0xffa300), hex(0xffec27), hex(0x00e436),
+    hex(0x29adff), hex(0x83769c), hex(0xff77a8), hex(0xffccaa),
+];
+
+#[rustfmt::skip]
+const CGA: [Pixel; 16] = [
+    hex(0x000000), hex(0x0000aa), hex(0x00aa00), hex(0x00aaaa),
+    hex(0xaa0000), hex(0xaa00aa), hex(0xaa5500), hex(0xaaaaaa),
+    hex(0x555555), hex(0x5555ff), hex(0x55ff55), hex(0x55ffff),
+    hex(0xff5555), hex(0xff55ff), hex(0xffff55), hex(0xffffff),
+];
+
+const GAMEBOY: [Pixel; 4] = [hex(0x9bbc0f), hex(0x8bac0f), hex(0x306230), hex(0x0f380f)];
+
+// -----------------------------------------------------------------------------
+//     - Palette -
+// -----------------------------------------------------------------------------
+/// A list of colours, looked up by index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    colours: Vec<Pixel>,
+}
+
+impl Palette {
+    pub fn new(colours: Vec<Pixel>) -> Self {
+        Self { colours }
+    }
+
+    /// The 16 colour PICO-8 palette.
+    pub fn pico8() -> Self {
+        Self::new(PICO8.to_vec())
+    }
+
+    /// The 16 colour CGA palette.
+    pub fn cga() -> Self {
+        Self::new(CGA.to_vec())
+    }
+
+    /// The full 64 colour EGA palette.
+    /// Index bits are `rgbRGB`, where the upper case bits add 2/3 intensity
+    /// and the lower case bits add 1/3.
+    pub fn ega() -> Self {
+        let channel = |index: u8, high: u8, low: u8| {
+            0xaa * (index >> high & 1) + 0x55 * (index >> low & 1)
+        };
+
+        let colours = (0..64)
+            .map(|i| Pixel::new(channel(i, 2, 5), channel(i, 1, 4), channel(i, 0, 3), 255))
+            .collect();
+
+        Self::new(colours)
+    }
+
+    /// The four shades of green of the original Game Boy (DMG), lightest first.
+    pub fn gameboy() -> Self {
+        Self::new(GAMEBOY.to_vec())
+    }
+
+    pub fn colours(&self) -> &[Pixel] {
+        &self.colours
+    }
+
+    pub fn len(&self) -> usize {
+        self.colours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colours.is_empty()
+    }
+
+    /// Get a colour from the palette.
+    /// Indices outside of the palette are transparent.
+    pub fn get(&self, index: u8) -> Pixel {
+        self.colours.get(index as usize).copied().unwrap_or_else(Pixel::zero)
+    }
+
+    /// Replace a colour in the palette.
+    /// Indices outside of the palette are ignored.
+    pub fn set(&mut self, index: u8, pixel: Pixel) {
+        if let Some(colour) = self.colours.get_mut(index as usize) {
+            *colour = pixel;
+        }
+    }
+
+    /// Shift the colours inside `range` by `steps`.
+    /// A positive step moves every colour to a higher index,
+    /// wrapping the last colour in the range around to the start.
+    pub fn cycle(&mut self, range: Range<usize>, steps: isize) {
+        let range = range.start.min(self.len())..range.end.min(self.len());
+        if range.start >= range.end {
+            return;
+        }
+        let colours = &mut self.colours[range];
+
+        let steps = steps.rem_euclid(colours.len() as isize) as usize;
+        colours.rotate_right(steps);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Palette cycle -
+// -----------------------------------------------------------------------------
+/// Animates a palette by cycling a range of colours at a fixed interval.
+#[derive(Debug, Clone)]
+pub struct PaletteCycle {
+    pub range: Range<usize>,
+    pub interval: Duration,
+    /// Cycle towards lower indices instead of higher ones.
+    pub reverse: bool,
+    elapsed: Duration,
+}
+
+impl PaletteCycle {
+    pub fn new(range: Range<usize>, interval: Duration) -> Self {
+        Self {
+            range,
+            interval,
+            reverse: false,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    /// Advance the animation, cycling the palette once for every
+    /// interval that has passed.
+    pub fn update(&mut self, dt: Duration, palette: &mut Palette) {
+        if self.interval == Duration::from_secs(0) {
+            return;
+        }
+
+        self.elapsed += dt;
+        let mut steps = 0;
+        while self.elapsed >= self.interval {
+            self.elapsed -= self.interval;
+            steps += 1;
+        }
+
+        if steps > 0 {
+            let steps = if self.reverse { -steps } else { steps };
+            palette.cycle(self.range.clone(), steps);
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Indexed image -
+// -----------------------------------------------------------------------------
+/// An image where every pixel is an index into a palette.
+/// Changing the palette changes the colours of the entire image,
+/// which makes palette swaps and cycling animations cheap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedImage {
+    size: ScreenSize,
+    indices: Vec<u8>,
+    pub palette: Palette,
+    /// An index that is never drawn.
+    pub transparent: Option<u8>,
+}
+
+impl IndexedImage {
+    /// Create a new image where every pixel is index zero.
+    pub fn new(size: ScreenSize, palette: Palette) -> Self {
+        Self {
+            size,
+            indices: vec![0; (size.width * size.height) as usize],
+            palette,
+            transparent: None,
+        }
+    }
+
+    /// Create an image from existing indices.
+    /// Panics if the number of indices does not match the size.
+    pub fn from_indices(size: ScreenSize, indices: Vec<u8>, palette: Palette) -> Self {
+        assert_eq!(
+            indices.len(),
+            (size.width * size.height) as usize,
+            "index count does not match image size"
+        );
+
+        Self {
+            size,
+            indices,
+            palette,
+            transparent: None,
+        }
+    }
+
+    pub fn size(&self) -> ScreenSize {
+        self.size
+    }
+
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// Get the palette index at x, y.
+    pub fn get(&self, x: u32, y: u32) -> Option<u8> {
+        if x < self.size.width && y < self.size.height {
+            Some(self.indices[(y * self.size.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Set the palette index at x, y.
+    /// Positions outside of the image are ignored.
+    pub fn set(&mut self, x: u32, y: u32, index: u8) {
+        if x < self.size.width && y < self.size.height {
+            self.indices[(y * self.size.width + x) as usize] = index;
+        }
+    }
+
+    /// Replace the palette, returning the old one.
+    pub fn swap_palette(&mut self, palette: Palette) -> Palette {
+        std::mem::replace(&mut self.palette, palette)
+    }
+
+    /// The colour of the pixel at x, y, taking transparency into account.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        self.get(x, y).map(|index| self.colour(index))
+    }
+
+    /// Iterate over every pixel colour along with its position.
+    pub fn pixels(&self) -> impl Iterator<Item = (ScreenPos, Pixel)> + '_ {
+        crate::Coords::new(self.size)
+            .zip(self.indices.iter())
+            .map(move |(pos, index)| (pos, self.colour(*index)))
+    }
+
+    /// Convert the image to RGBA using the current palette.
+    pub fn to_image(&self) -> Image {
+        Image::from_pixels(self.size, self.pixels().map(|(_, pixel)| pixel).collect())
+    }
+
+    fn colour(&self, index: u8) -> Pixel {
+        if self.transparent == Some(index) {
+            Pixel::zero()
+        } else {
+            self.palette.get(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builtin_palettes() {
+        assert_eq!(Palette::pico8().len(), 16);
+        assert_eq!(Palette::cga().len(), 16);
+        assert_eq!(Palette::gameboy().len(), 4);
+
+        let ega = Palette::ega();
+        assert_eq!(ega.len(), 64);
+        assert_eq!(ega.get(0), hex(0x000000));
+        assert_eq!(ega.get(0b000001), hex(0x0000aa));
+        assert_eq!(ega.get(0b111000), hex(0x555555));
+        assert_eq!(ega.get(63), hex(0xffffff));
+    }
+
+    #[test]
+    fn cycle_wraps_inside_range() {
+        let mut palette = Palette::new((0..5).map(|i| Pixel::new(i, 0, 0, 255)).collect());
+        palette.cycle(1..4, 1);
+
+        let reds = palette.colours().iter().map(|p| p.r).collect::<Vec<_>>();
+        assert_eq!(reds, vec![0, 3, 1, 2, 4]);
+
+        palette.cycle(1..4, -1);
+        let reds = palette.colours().iter().map(|p| p.r).collect::<Vec<_>>();
+        assert_eq!(reds, vec![0, 1, 2, 3, 4]);
+
+        // Inverted ranges are empty
+        #[allow(clippy::reversed_empty_ranges)]
+        palette.cycle(4..2, 1);
+        assert_eq!(palette.colours()[2].r, 2);
+    }
+
+    #[test]
+    fn cycle_animation_steps_on_interval() {
+        let mut palette = Palette::new((0..3).map(|i| Pixel::new(i, 0, 0, 255)).collect());
+        let mut cycle = PaletteCycle::new(0..3, Duration::from_millis(100));
+
+        cycle.update(Duration::from_millis(50), &mut palette);
+        assert_eq!(palette.get(0).r, 0);
+
+        cycle.update(Duration::from_millis(160), &mut palette);
+        assert_eq!(palette.get(0).r, 1);
+    }
+
+    #[test]
+    fn indexed_to_rgba() {
+        let indices = vec![0, 1, 2, 3];
+        let mut image = IndexedImage::from_indices(ScreenSize::new(2, 2), indices, Palette::gameboy());
+        image.transparent = Some(3);
+
+        let rgba = image.to_image();
+        assert_eq!(rgba.get(0, 0), Some(GAMEBOY[0]));
+        assert_eq!(rgba.get(0, 1), Some(GAMEBOY[2]));
+        assert_eq!(rgba.get(1, 1), Some(Pixel::zero()));
+
+        image.swap_palette(Palette::cga());
+        assert_eq!(image.pixel(1, 0), Some(CGA[1]));
+    }
+}
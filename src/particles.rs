@@ -0,0 +1,418 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::{Image, Pixel, ScreenPos, Vec2D, Viewport};
+
+// -----------------------------------------------------------------------------
+//     - Rng -
+// -----------------------------------------------------------------------------
+/// A small xorshift random number generator.
+/// The same seed always produces the same numbers.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero
+        Self(if seed == 0 {
+            0x9e37_79b9_7f4a_7c15
+        } else {
+            seed
+        })
+    }
+
+    /// Seed the generator from the system clock.
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A number in `range`. An empty range always gives `range.start`.
+    pub fn range(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Gradient -
+// -----------------------------------------------------------------------------
+/// Colours over the lifetime of a particle, where 0.0 is the
+/// moment it spawns and 1.0 the moment it dies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, Pixel)>,
+}
+
+impl Gradient {
+    /// Create a gradient from `(position, colour)` stops.
+    /// The stops are sorted by position.
+    pub fn new(mut stops: Vec<(f32, Pixel)>) -> Self {
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { stops }
+    }
+
+    /// A gradient with the colours spread out evenly.
+    pub fn even(colours: &[Pixel]) -> Self {
+        let step = 1.0 / colours.len().saturating_sub(1).max(1) as f32;
+        Self::new(
+            colours
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i as f32 * step, *c))
+                .collect(),
+        )
+    }
+
+    /// A gradient of a single colour.
+    pub fn solid(colour: Pixel) -> Self {
+        Self::new(vec![(0.0, colour)])
+    }
+
+    /// The colour at `t`, blending between the nearest stops.
+    pub fn sample(&self, t: f32) -> Pixel {
+        let first = match self.stops.first() {
+            Some(first) => first,
+            None => return Pixel::zero(),
+        };
+
+        if t <= first.0 {
+            return first.1;
+        }
+
+        for pair in self.stops.windows(2) {
+            let ((start, from), (end, to)) = (pair[0], pair[1]);
+            if t <= end {
+                let span = end - start;
+                let t = if span > 0.0 { (t - start) / span } else { 1.0 };
+                return from.lerp(to, t);
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Particle -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub pos: Vec2D<f32>,
+    pub velocity: Vec2D<f32>,
+    /// Seconds since the particle spawned.
+    pub age: f32,
+    /// Seconds the particle lives for.
+    pub lifetime: f32,
+}
+
+impl Particle {
+    /// How far through its life the particle is, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.lifetime > 0.0 {
+            (self.age / self.lifetime).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// How a particle is drawn.
+#[derive(Debug, Clone)]
+pub enum ParticleShape {
+    /// A single pixel in the gradient colour.
+    Pixel,
+    /// A sprite centred on the particle, tinted by the gradient colour.
+    Sprite(Image),
+}
+
+// -----------------------------------------------------------------------------
+//     - Emitter -
+// -----------------------------------------------------------------------------
+/// Spawns, moves and draws particles.
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    /// Where new particles spawn, in viewport pixels.
+    pub position: Vec2D<f32>,
+    /// New particles per second while active.
+    pub rate: f32,
+    /// Lifetime range in seconds.
+    pub lifetime: Range<f32>,
+    /// Starting velocity ranges in pixels per second.
+    pub velocity_x: Range<f32>,
+    pub velocity_y: Range<f32>,
+    /// Acceleration in pixels per second squared.
+    pub gravity: Vec2D<f32>,
+    /// Fraction of velocity lost per second.
+    pub drag: f32,
+    pub colours: Gradient,
+    pub shape: ParticleShape,
+    /// Stop spawning new particles, while existing ones live on.
+    pub active: bool,
+    /// No new particles spawn while this many are alive.
+    pub max_particles: usize,
+    particles: Vec<Particle>,
+    spawn_timer: f32,
+    rng: Rng,
+}
+
+impl Emitter {
+    /// Create an emitter with white single pixel particles that
+    /// live for a second and do not move.
+    pub fn new(position: Vec2D<f32>, seed: u64) -> Self {
+        Self {
+            position,
+            rate: 10.0,
+            lifetime: 1.0..1.0,
+            velocity_x: 0.0..0.0,
+            velocity_y: 0.0..0.0,
+            gravity: Vec2D::zero(),
+            drag: 0.0,
+            colours: Gradient::solid(Pixel::rgb(255, 255, 255)),
+            shape: ParticleShape::Pixel,
+            active: true,
+            max_particles: 1000,
+            particles: Vec::new(),
+            spawn_timer: 0.0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Remove all living particles.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Spawn `count` particles at once, regardless of the rate.
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+
+    /// Spawn new particles, then age and move every particle by `dt`.
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+
+        if self.active && self.rate > 0.0 {
+            self.spawn_timer += dt * self.rate;
+            while self.spawn_timer >= 1.0 {
+                self.spawn_timer -= 1.0;
+                self.spawn();
+            }
+        }
+
+        let gravity = self.gravity;
+        let drag = (1.0 - self.drag * dt).max(0.0);
+        self.particles.retain_mut(|particle| {
+            particle.age += dt;
+            particle.velocity = (particle.velocity + gravity * dt) * drag;
+            particle.pos += particle.velocity * dt;
+            particle.age < particle.lifetime
+        });
+    }
+
+    /// Draw every living particle. Particles outside the viewport are skipped.
+    pub fn draw(&self, viewport: &mut Viewport) {
+        for particle in &self.particles {
+            let colour = self.colours.sample(particle.progress());
+            let pos = Vec2D::new(particle.pos.x.floor() as i32, particle.pos.y.floor() as i32);
+
+            match &self.shape {
+                ParticleShape::Pixel => draw_pixel(viewport, colour, pos),
+                ParticleShape::Sprite(sprite) => {
+                    let origin =
+                        pos - Vec2D::new(sprite.width() as i32, sprite.height() as i32) / 2;
+                    for (offset, pixel) in sprite.enumerate_pixels() {
+                        let pos = origin + Vec2D::new(offset.x as i32, offset.y as i32);
+                        draw_pixel(viewport, tint(*pixel, colour), pos);
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.max_particles {
+            return;
+        }
+
+        let particle = Particle {
+            pos: self.position,
+            velocity: Vec2D::new(
+                self.rng.range(self.velocity_x.clone()),
+                self.rng.range(self.velocity_y.clone()),
+            ),
+            age: 0.0,
+            lifetime: self.rng.range(self.lifetime.clone()),
+        };
+        self.particles.push(particle);
+    }
+}
+
+fn draw_pixel(viewport: &mut Viewport, pixel: Pixel, pos: Vec2D<i32>) {
+    if pixel.a == 0 || pos.x < 0 || pos.y < 0 {
+        return;
+    }
+    viewport.draw_pixel(pixel, ScreenPos::new(pos.x as u32, pos.y as u32));
+}
+
+fn tint(pixel: Pixel, colour: Pixel) -> Pixel {
+    let mul = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+    Pixel::new(
+        mul(pixel.r, colour.r),
+        mul(pixel.g, colour.g),
+        mul(pixel.b, colour.b),
+        mul(pixel.a, colour.a),
+    )
+}
+
+// -----------------------------------------------------------------------------
+//     - Particle system -
+// -----------------------------------------------------------------------------
+/// A collection of emitters, updated and drawn together.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleSystem {
+    pub emitters: Vec<Emitter>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an emitter, returning its index.
+    pub fn add(&mut self, emitter: Emitter) -> usize {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    /// The number of living particles across all emitters.
+    pub fn particle_count(&self) -> usize {
+        self.emitters.iter().map(|e| e.particles().len()).sum()
+    }
+
+    pub fn update(&mut self, dt: Duration) {
+        self.emitters.iter_mut().for_each(|e| e.update(dt));
+    }
+
+    pub fn draw(&self, viewport: &mut Viewport) {
+        self.emitters.iter().for_each(|e| e.draw(viewport));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ScreenSize;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn seeded_rng_is_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            let n = a.next_f32();
+            assert_eq!(n, b.next_f32());
+            assert!((0.0..1.0).contains(&n));
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn gradient_sampling() {
+        let black = Pixel::rgb(0, 0, 0);
+        let white = Pixel::rgb(255, 255, 255);
+        let gradient = Gradient::even(&[black, white, black]);
+
+        assert_eq!(gradient.sample(-1.0), black);
+        assert_eq!(gradient.sample(0.5), white);
+        assert_eq!(gradient.sample(0.25), Pixel::rgb(128, 128, 128));
+        assert_eq!(gradient.sample(2.0), black);
+    }
+
+    #[test]
+    fn spawn_rate_and_lifetime() {
+        let mut emitter = Emitter::new(Vec2D::zero(), 1);
+        emitter.rate = 10.0;
+        emitter.lifetime = 1.5..1.5;
+
+        emitter.update(SECOND);
+        assert_eq!(emitter.particles().len(), 10);
+
+        emitter.active = false;
+        emitter.update(SECOND);
+        assert!(emitter.particles().is_empty());
+    }
+
+    #[test]
+    fn movement() {
+        let mut emitter = Emitter::new(Vec2D::new(10.0, 10.0), 1);
+        emitter.rate = 0.0;
+        emitter.velocity_x = 4.0..4.0;
+        emitter.lifetime = 2.0..2.0;
+        emitter.gravity = Vec2D::new(0.0, 2.0);
+        emitter.burst(1);
+
+        emitter.update(Duration::from_millis(500));
+        let particle = &emitter.particles()[0];
+        assert_eq!(particle.velocity, Vec2D::new(4.0, 1.0));
+        assert_eq!(particle.pos, Vec2D::new(12.0, 10.5));
+
+        emitter.drag = 1.0;
+        emitter.update(Duration::from_millis(500));
+        assert_eq!(emitter.particles()[0].velocity, Vec2D::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn same_seed_same_particles() {
+        let emitter = |seed| {
+            let mut emitter = Emitter::new(Vec2D::zero(), seed);
+            emitter.velocity_x = -5.0..5.0;
+            emitter.velocity_y = -5.0..5.0;
+            emitter.lifetime = 0.5..2.0;
+            emitter.update(SECOND);
+            emitter
+        };
+
+        assert_eq!(emitter(7).particles(), emitter(7).particles());
+        assert_ne!(emitter(7).particles(), emitter(8).particles());
+    }
+
+    #[test]
+    fn draw_pixels_and_sprites() {
+        let red = Pixel::rgb(255, 0, 0);
+        let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(8, 8));
+
+        let mut emitter = Emitter::new(Vec2D::new(2.5, 3.5), 1);
+        emitter.colours = Gradient::solid(red);
+        emitter.burst(1);
+        emitter.draw(&mut viewport);
+        assert_eq!(viewport.pixels(), vec![(red, ScreenPos::new(2, 3))]);
+
+        emitter.shape =
+            ParticleShape::Sprite(Image::new(ScreenSize::new(2, 1), Pixel::rgb(255, 128, 0)));
+        emitter.draw(&mut viewport);
+        // The pixel at 2, 3 is unchanged since the last frame
+        assert_eq!(viewport.pixels(), vec![(red, ScreenPos::new(1, 3))]);
+    }
+}
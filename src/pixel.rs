@@ -1,6 +1,5 @@
 use std::ops::{Deref, DerefMut};
 
-
 // -----------------------------------------------------------------------------
 //     - Pixel -
 // -----------------------------------------------------------------------------
@@ -14,6 +13,15 @@ pub struct Pixel {
 }
 
 impl Pixel {
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// An opaque pixel.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::new(r, g, b, 255)
+    }
+
     pub fn zero() -> Self {
         Self {
             r: 0,
@@ -22,14 +30,282 @@ impl Pixel {
             a: 0,
         }
     }
+
+    /// Create a pixel from a packed `0xRRGGBBAA` value.
+    pub const fn from_u32(rgba: u32) -> Self {
+        Self::new((rgba >> 24) as u8, (rgba >> 16) as u8, (rgba >> 8) as u8, rgba as u8)
+    }
+
+    /// Pack the pixel into a `0xRRGGBBAA` value.
+    pub const fn to_u32(&self) -> u32 {
+        (self.r as u32) << 24 | (self.g as u32) << 16 | (self.b as u32) << 8 | self.a as u32
+    }
+
+    /// Parse a hex colour: `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+    /// The leading `#` is optional.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseColourError> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+
+        let mut digits = Vec::with_capacity(8);
+        for c in hex.chars() {
+            let digit = c.to_digit(16).ok_or(ParseColourError::InvalidDigit(c))?;
+            digits.push(digit as u8);
+        }
+
+        let short = |i: usize| digits[i] * 17;
+        let long = |i: usize| digits[i * 2] << 4 | digits[i * 2 + 1];
+
+        match digits.len() {
+            3 => Ok(Self::rgb(short(0), short(1), short(2))),
+            4 => Ok(Self::new(short(0), short(1), short(2), short(3))),
+            6 => Ok(Self::rgb(long(0), long(1), long(2))),
+            8 => Ok(Self::new(long(0), long(1), long(2), long(3))),
+            len => Err(ParseColourError::InvalidLength(len)),
+        }
+    }
+
+    /// Format the pixel as `#rrggbb`, or `#rrggbbaa` if it is not fully opaque.
+    pub fn to_hex(&self) -> String {
+        match self.a {
+            255 => format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b),
+            a => format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, a),
+        }
+    }
+
+    /// Create a pixel from channels in the range 0.0 to 1.0.
+    /// Values outside of the range are clamped.
+    pub fn from_f32(r: f32, g: f32, b: f32, a: f32) -> Self {
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::new(channel(r), channel(g), channel(b), channel(a))
+    }
+
+    /// The channels in the range 0.0 to 1.0, as `[r, g, b, a]`.
+    pub fn to_f32(&self) -> [f32; 4] {
+        [
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+            self.a as f32 / 255.0,
+        ]
+    }
+
+    /// Create an opaque pixel from hue (in degrees), saturation and value,
+    /// where saturation and value are in the range 0.0 to 1.0.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        let (r, g, b) = from_hue(hue, chroma, value - chroma);
+        Self::from_f32(r, g, b, 1.0)
+    }
+
+    /// Hue (in degrees), saturation and value.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    /// Create an opaque pixel from hue (in degrees), saturation and lightness,
+    /// where saturation and lightness are in the range 0.0 to 1.0.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let (r, g, b) = from_hue(hue, chroma, lightness - chroma / 2.0);
+        Self::from_f32(r, g, b, 1.0)
+    }
+
+    /// Hue (in degrees), saturation and lightness.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue();
+        let lightness = (max + min) / 2.0;
+        let saturation = match lightness {
+            l if l <= 0.0 || l >= 1.0 => 0.0,
+            l => (max - min) / (1.0 - (2.0 * l - 1.0).abs()),
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Linear interpolation between two pixels, where `t` is in the range 0.0 to 1.0.
+    pub fn lerp(&self, other: Pixel, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+            mix(self.a, other.a),
+        )
+    }
+
+    /// Add `amount` (in the range -1.0 to 1.0) to every colour channel.
+    pub fn brightness(&self, amount: f32) -> Self {
+        self.map_rgb(|c| c + amount)
+    }
+
+    /// Scale the distance of every colour channel from mid grey by `factor`.
+    /// A factor above 1.0 increases contrast and below 1.0 reduces it.
+    pub fn contrast(&self, factor: f32) -> Self {
+        self.map_rgb(|c| (c - 0.5) * factor + 0.5)
+    }
+
+    /// Multiply the colour channels by alpha.
+    pub fn premultiply(&self) -> Self {
+        let scale = |c: u8| ((c as u16 * self.a as u16 + 127) / 255) as u8;
+        Self::new(scale(self.r), scale(self.g), scale(self.b), self.a)
+    }
+
+    /// Divide the colour channels by alpha.
+    /// Precision is lost for low alpha values.
+    pub fn unpremultiply(&self) -> Self {
+        if self.a == 0 {
+            return Self::zero();
+        }
+
+        let a = self.a as u16;
+        let scale = |c: u8| ((c as u16 * 255 + a / 2) / a).min(255) as u8;
+        Self::new(scale(self.r), scale(self.g), scale(self.b), self.a)
+    }
+
+    /// Relative luminance in the range 0.0 to 1.0, as defined by Rec. 709.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * to_linear(self.r as f32)
+            + 0.7152 * to_linear(self.g as f32)
+            + 0.0722 * to_linear(self.b as f32)
+    }
+
+    fn map_rgb(&self, f: impl Fn(f32) -> f32) -> Self {
+        let [r, g, b, a] = self.to_f32();
+        Self::from_f32(f(r), f(g), f(b), a)
+    }
+
+    // Hue in degrees, along with the largest and smallest channel
+    fn hue(&self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_f32();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+
+        (hue, max, min)
+    }
+}
+
+// Red, green and blue for a hue, given the chroma and the amount to add to each channel
+fn from_hue(hue: f32, chroma: f32, offset: f32) -> (f32, f32, f32) {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    (r + offset, g + offset, b + offset)
 }
 
+/// sRGB channel (0 to 255) to linear light (0.0 to 1.0)
+pub(crate) fn to_linear(c: f32) -> f32 {
+    let c = c / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear light (0.0 to 1.0) to sRGB channel (0 to 255)
+pub(crate) fn from_linear(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    c * 255.0
+}
+
+impl From<(u8, u8, u8)> for Pixel {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Pixel {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Self::new(r, g, b, a)
+    }
+}
+
+impl From<[u8; 3]> for Pixel {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<[u8; 4]> for Pixel {
+    fn from([r, g, b, a]: [u8; 4]) -> Self {
+        Self::new(r, g, b, a)
+    }
+}
+
+impl From<Pixel> for (u8, u8, u8, u8) {
+    fn from(p: Pixel) -> Self {
+        (p.r, p.g, p.b, p.a)
+    }
+}
+
+impl From<Pixel> for [u8; 4] {
+    fn from(p: Pixel) -> Self {
+        [p.r, p.g, p.b, p.a]
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Parse colour error -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseColourError {
+    /// The hex string did not have 3, 4, 6 or 8 digits.
+    InvalidLength(usize),
+    /// The hex string contained a character that is not a hex digit.
+    InvalidDigit(char),
+}
+
+impl std::fmt::Display for ParseColourError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseColourError::InvalidLength(len) => {
+                write!(f, "expected 3, 4, 6 or 8 hex digits, found {}", len)
+            }
+            ParseColourError::InvalidDigit(c) => write!(f, "invalid hex digit: {:?}", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseColourError {}
+
 unsafe impl bytemuck::Pod for Pixel {}
 unsafe impl bytemuck::Zeroable for Pixel {}
 
 // -----------------------------------------------------------------------------
 //     - Pixel buffer -
 // -----------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
 pub struct PixelBuffer {
     pub(crate) inner: Vec<Pixel>,
 }
@@ -70,3 +346,104 @@ impl DerefMut for PixelBuffer {
     }
 }
 
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const COLOURS: &[Pixel] = &[
+        Pixel::new(0, 0, 0, 255),
+        Pixel::new(255, 255, 255, 255),
+        Pixel::new(255, 0, 0, 255),
+        Pixel::new(12, 200, 99, 255),
+        Pixel::new(102, 51, 153, 255),
+        Pixel::new(250, 128, 114, 255),
+        Pixel::new(1, 2, 3, 4),
+    ];
+
+    #[test]
+    fn hex_round_trip() {
+        for pixel in COLOURS {
+            assert_eq!(Pixel::from_hex(&pixel.to_hex()), Ok(*pixel));
+        }
+
+        assert_eq!(Pixel::from_hex("#f80"), Ok(Pixel::rgb(255, 136, 0)));
+        assert_eq!(Pixel::from_hex("f808"), Ok(Pixel::new(255, 136, 0, 136)));
+        assert_eq!(Pixel::from_hex("#663399"), Ok(Pixel::rgb(102, 51, 153)));
+        assert_eq!(Pixel::from_hex("#12345"), Err(ParseColourError::InvalidLength(5)));
+        assert_eq!(Pixel::from_hex("#12345g"), Err(ParseColourError::InvalidDigit('g')));
+    }
+
+    #[test]
+    fn packed_and_float_round_trip() {
+        for pixel in COLOURS {
+            assert_eq!(Pixel::from_u32(pixel.to_u32()), *pixel);
+
+            let [r, g, b, a] = pixel.to_f32();
+            assert_eq!(Pixel::from_f32(r, g, b, a), *pixel);
+        }
+
+        assert_eq!(Pixel::from_u32(0x11223344), Pixel::new(0x11, 0x22, 0x33, 0x44));
+    }
+
+    #[test]
+    fn hsv_and_hsl_round_trip() {
+        for pixel in COLOURS.iter().filter(|p| p.a == 255) {
+            let (h, s, v) = pixel.to_hsv();
+            assert_eq!(Pixel::from_hsv(h, s, v), *pixel);
+
+            let (h, s, l) = pixel.to_hsl();
+            assert_eq!(Pixel::from_hsl(h, s, l), *pixel);
+        }
+
+        assert_eq!(Pixel::from_hsv(120.0, 1.0, 1.0), Pixel::rgb(0, 255, 0));
+        assert_eq!(Pixel::from_hsl(240.0, 1.0, 0.5), Pixel::rgb(0, 0, 255));
+        assert_eq!(Pixel::from_hsv(-120.0, 1.0, 1.0), Pixel::rgb(0, 0, 255));
+    }
+
+    #[test]
+    fn tuples_and_arrays() {
+        for pixel in COLOURS {
+            let tuple: (u8, u8, u8, u8) = (*pixel).into();
+            assert_eq!(Pixel::from(tuple), *pixel);
+
+            let array: [u8; 4] = (*pixel).into();
+            assert_eq!(Pixel::from(array), *pixel);
+        }
+
+        assert_eq!(Pixel::from((1, 2, 3)), Pixel::rgb(1, 2, 3));
+        assert_eq!(Pixel::from([1, 2, 3]), Pixel::rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn premultiply_round_trip() {
+        let pixel = Pixel::new(200, 100, 50, 128);
+        assert_eq!(pixel.premultiply(), Pixel::new(100, 50, 25, 128));
+        assert_eq!(pixel.premultiply().unpremultiply(), Pixel::new(199, 100, 50, 128));
+
+        let opaque = Pixel::rgb(200, 100, 50);
+        assert_eq!(opaque.premultiply().unpremultiply(), opaque);
+        assert_eq!(Pixel::new(10, 10, 10, 0).unpremultiply(), Pixel::zero());
+    }
+
+    #[test]
+    fn adjustments() {
+        let black = Pixel::rgb(0, 0, 0);
+        let white = Pixel::rgb(255, 255, 255);
+
+        assert_eq!(black.lerp(white, 0.5), Pixel::rgb(128, 128, 128));
+        assert_eq!(black.lerp(white, 2.0), white);
+        assert_eq!(black.brightness(0.2), Pixel::rgb(51, 51, 51));
+        assert_eq!(white.brightness(1.0), white);
+        assert_eq!(Pixel::rgb(64, 128, 192).contrast(0.0), Pixel::rgb(128, 128, 128));
+        assert_eq!(Pixel::rgb(0, 255, 255).contrast(0.5), Pixel::rgb(64, 191, 191));
+        assert_eq!(Pixel::rgb(64, 191, 191).contrast(4.0), Pixel::rgb(0, 255, 255));
+    }
+
+    #[test]
+    fn luminance() {
+        assert_eq!(Pixel::rgb(0, 0, 0).luminance(), 0.0);
+        assert!((Pixel::rgb(255, 255, 255).luminance() - 1.0).abs() < 1e-6);
+        assert!(Pixel::rgb(0, 255, 0).luminance() > Pixel::rgb(255, 0, 0).luminance());
+    }
+}
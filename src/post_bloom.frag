@@ -0,0 +1,34 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_diffuse;
+layout(set = 0, binding = 1) uniform sampler s_diffuse;
+
+layout(set = 1, binding = 0) uniform PassUniforms {
+    vec2 resolution;
+    float time;
+    float frame;
+    vec4 params;
+};
+layout(set = 1, binding = 1) uniform texture2D t_lookup;
+
+// params.x: brightness threshold
+// params.y: intensity
+// params.z: radius in pixels
+void main() {
+    vec4 colour = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+    vec2 texel = params.z / resolution / 2.0;
+    vec3 glow = vec3(0.0);
+
+    for (int y = -2; y <= 2; y++) {
+        for (int x = -2; x <= 2; x++) {
+            vec2 uv = v_tex_coords + vec2(float(x), float(y)) * texel;
+            vec3 sample_colour = texture(sampler2D(t_diffuse, s_diffuse), uv).rgb;
+            glow += max(sample_colour - vec3(params.x), vec3(0.0));
+        }
+    }
+
+    f_color = vec4(colour.rgb + glow / 25.0 * params.y, colour.a);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_diffuse;
+layout(set = 0, binding = 1) uniform sampler s_diffuse;
+
+layout(set = 1, binding = 0) uniform PassUniforms {
+    vec2 resolution;
+    float time;
+    float frame;
+    vec4 params;
+};
+layout(set = 1, binding = 1) uniform texture2D t_lookup;
+
+// params.x: horizontal offset of the red and blue channels in pixels
+void main() {
+    vec2 offset = vec2(params.x / resolution.x, 0.0);
+    vec4 colour = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+    float r = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords + offset).r;
+    float b = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords - offset).b;
+    f_color = vec4(r, colour.g, b, colour.a);
+}
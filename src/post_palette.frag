@@ -0,0 +1,39 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_diffuse;
+layout(set = 0, binding = 1) uniform sampler s_diffuse;
+
+layout(set = 1, binding = 0) uniform PassUniforms {
+    vec2 resolution;
+    float time;
+    float frame;
+    vec4 params;
+};
+layout(set = 1, binding = 1) uniform texture2D t_lookup;
+
+// Snap every colour to the nearest colour in the lookup texture.
+// params.x: number of colours in the lookup texture
+void main() {
+    vec4 colour = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+    vec3 nearest = colour.rgb;
+    float best = 1000.0;
+
+    for (int i = 0; i < 256; i++) {
+        if (float(i) >= params.x) {
+            break;
+        }
+
+        vec3 candidate = texelFetch(sampler2D(t_lookup, s_diffuse), ivec2(i, 0), 0).rgb;
+        vec3 diff = candidate - colour.rgb;
+        float dist = dot(diff, diff);
+        if (dist < best) {
+            best = dist;
+            nearest = candidate;
+        }
+    }
+
+    f_color = vec4(nearest, colour.a);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_diffuse;
+layout(set = 0, binding = 1) uniform sampler s_diffuse;
+
+layout(set = 1, binding = 0) uniform PassUniforms {
+    vec2 resolution;
+    float time;
+    float frame;
+    vec4 params;
+};
+layout(set = 1, binding = 1) uniform texture2D t_lookup;
+
+// params.x: how much to darken every other line
+// params.y: line height in pixels
+void main() {
+    vec4 colour = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+    float height = max(params.y, 1.0);
+    float line = mod(floor(v_tex_coords.y * resolution.y / height), 2.0);
+    f_color = vec4(colour.rgb * (1.0 - params.x * line), colour.a);
+}
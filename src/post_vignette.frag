@@ -0,0 +1,24 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_diffuse;
+layout(set = 0, binding = 1) uniform sampler s_diffuse;
+
+layout(set = 1, binding = 0) uniform PassUniforms {
+    vec2 resolution;
+    float time;
+    float frame;
+    vec4 params;
+};
+layout(set = 1, binding = 1) uniform texture2D t_lookup;
+
+// params.x: strength
+// params.y: distance from the centre where darkening starts, 1.0 being the corners
+void main() {
+    vec4 colour = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+    float dist = length(v_tex_coords - vec2(0.5)) * 1.41421356;
+    float shade = 1.0 - params.x * smoothstep(params.y, 1.0, dist);
+    f_color = vec4(colour.rgb * shade, colour.a);
+}
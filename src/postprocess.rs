@@ -0,0 +1,378 @@
+use std::mem::size_of;
+use std::path::Path;
+
+use wgpu::util::DeviceExt;
+
+use crate::renderer::{bind_group, bind_group_layout, create_pipeline};
+use crate::shader::{self, ShaderError, ShaderLanguage, ShaderWatcher};
+use crate::{texture, Palette, Pixel, ScreenSize};
+
+// -----------------------------------------------------------------------------
+//     - Uniforms -
+// -----------------------------------------------------------------------------
+/// Values available to every pass as the `PassUniforms` block
+/// (`set = 1, binding = 0`).
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct PassUniforms {
+    /// Size of the output in pixels.
+    pub resolution: [f32; 2],
+    /// Seconds since the renderer was created.
+    pub time: f32,
+    /// Number of frames rendered.
+    pub frame: f32,
+    /// Per pass values, see [`PostPass::params`].
+    pub params: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for PassUniforms {}
+unsafe impl bytemuck::Zeroable for PassUniforms {}
+
+// -----------------------------------------------------------------------------
+//     - Post pass -
+// -----------------------------------------------------------------------------
+/// A full screen fragment shader run after the pixels are drawn.
+///
+/// The shader samples the previous pass through `t_diffuse` / `s_diffuse`
+/// (`set = 0`), and has [`PassUniforms`] and an optional lookup texture
+/// `t_lookup` (`set = 1, binding = 1`) available. See `src/post_vignette.frag`
+/// for an example.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostPass {
+    pub name: String,
+    /// Passed to the shader as `params`.
+    pub params: [f32; 4],
+    spirv: Vec<u32>,
+    lookup: Vec<Pixel>,
+}
+
+impl PostPass {
+    /// Create a pass from a SPIR-V fragment shader.
+    /// Panics if the length of `spirv` is not a multiple of four.
+    pub fn from_spirv(name: impl Into<String>, spirv: &[u8]) -> Self {
+        assert_eq!(spirv.len() % 4, 0, "SPIR-V size is not a multiple of 4");
+        let spirv = spirv
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+
+        Self::from_words(name, spirv)
+    }
+
+    /// Compile a GLSL fragment shader into a pass.
+    pub fn from_glsl(name: impl Into<String>, source: &str) -> Result<Self, ShaderError> {
+        let spirv = shader::compile_fragment(source, ShaderLanguage::Glsl)?;
+        Ok(Self::from_words(name, spirv))
+    }
+
+    /// Compile a WGSL fragment shader into a pass.
+    /// The fragment entry point has to be called `main`.
+    pub fn from_wgsl(name: impl Into<String>, source: &str) -> Result<Self, ShaderError> {
+        let spirv = shader::compile_fragment(source, ShaderLanguage::Wgsl)?;
+        Ok(Self::from_words(name, spirv))
+    }
+
+    /// Load and compile a `.frag`, `.glsl` or `.wgsl` file.
+    /// The pass is named after the file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ShaderError> {
+        let path = path.as_ref();
+        let spirv = shader::load_fragment(path)?;
+        Ok(Self::from_words(path.display().to_string(), spirv))
+    }
+
+    // The built-in shaders are covered by the tests, so they always compile
+    fn builtin(name: &str, source: &str) -> Self {
+        Self::from_glsl(name, source).expect("built-in shader failed to compile")
+    }
+
+    fn from_words(name: impl Into<String>, spirv: Vec<u32>) -> Self {
+        Self {
+            name: name.into(),
+            params: [0.0; 4],
+            spirv,
+            lookup: Vec::new(),
+        }
+    }
+
+    /// Set the `params` uniform.
+    pub fn with_params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Set the contents of the lookup texture, as a single row of pixels.
+    pub fn with_lookup(mut self, pixels: Vec<Pixel>) -> Self {
+        self.lookup = pixels;
+        self
+    }
+
+    /// Darken every other line of `line_height` pixels by `intensity` (0.0 - 1.0).
+    pub fn scanlines(intensity: f32, line_height: f32) -> Self {
+        Self::builtin("scanlines", include_str!("post_scanlines.frag"))
+            .with_params([intensity, line_height, 0.0, 0.0])
+    }
+
+    /// Darken the edges of the screen.
+    /// Darkening starts at `radius` from the centre, where 1.0 is the corners.
+    pub fn vignette(strength: f32, radius: f32) -> Self {
+        Self::builtin("vignette", include_str!("post_vignette.frag"))
+            .with_params([strength, radius, 0.0, 0.0])
+    }
+
+    /// Shift the red and blue channels `offset` pixels in opposite directions.
+    pub fn chromatic_aberration(offset: f32) -> Self {
+        Self::builtin("chromatic aberration", include_str!("post_chromatic.frag"))
+            .with_params([offset, 0.0, 0.0, 0.0])
+    }
+
+    /// Make colours brighter than `threshold` (0.0 - 1.0) glow into
+    /// their surroundings.
+    pub fn bloom(threshold: f32, intensity: f32, radius: f32) -> Self {
+        Self::builtin("bloom", include_str!("post_bloom.frag"))
+            .with_params([threshold, intensity, radius, 0.0])
+    }
+
+    /// Snap every colour to the nearest colour in a palette
+    /// (up to 256 colours).
+    pub fn palette_lookup(palette: &Palette) -> Self {
+        let colours = palette
+            .colours()
+            .iter()
+            .take(256)
+            .copied()
+            .collect::<Vec<_>>();
+        Self::builtin("palette lookup", include_str!("post_palette.frag"))
+            .with_params([colours.len() as f32, 0.0, 0.0, 0.0])
+            .with_lookup(colours)
+    }
+
+    pub fn spirv(&self) -> &[u32] {
+        &self.spirv
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Pass -
+//     A post pass uploaded to the gpu
+// -----------------------------------------------------------------------------
+pub(crate) struct Pass {
+    pub(crate) desc: PostPass,
+    /// Set for passes loaded from a file, to reload them when it changes.
+    pub(crate) watcher: Option<ShaderWatcher>,
+    /// The error from the last failed reload, cleared once it compiles again.
+    pub(crate) error: Option<ShaderError>,
+    pipeline: wgpu::RenderPipeline,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    _lookup: texture::Texture,
+}
+
+impl Pass {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        vs_module: &wgpu::ShaderModule,
+        desc: PostPass,
+    ) -> Self {
+        let fs_module = device.create_shader_module(wgpu::ShaderModuleSource::SpirV(
+            desc.spirv.as_slice().into(),
+        ));
+
+        let layout = pass_bind_group_layout(device);
+        let pipeline = create_pipeline(
+            device,
+            format,
+            vs_module,
+            &fs_module,
+            &[&bind_group_layout(device), &layout],
+        );
+
+        let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Post pass uniforms"),
+            contents: bytemuck::bytes_of(&PassUniforms::default()),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let lookup = match desc.lookup.len() {
+            0 => texture::Texture::empty(device, queue, ScreenSize::new(1, 1)),
+            len => {
+                let bytes = desc
+                    .lookup
+                    .iter()
+                    .flat_map(|p| vec![p.r, p.g, p.b, p.a])
+                    .collect::<Vec<_>>();
+                texture::Texture::new(&bytes, device, queue, ScreenSize::new(len as u32, 1))
+            }
+        };
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(uniforms.slice(..)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&lookup.view),
+                },
+            ],
+            label: Some("Post pass bind group"),
+        });
+
+        Self {
+            desc,
+            watcher: None,
+            error: None,
+            pipeline,
+            uniforms,
+            bind_group,
+            _lookup: lookup,
+        }
+    }
+
+    /// Recompile the pass if its source file has changed.
+    /// Returns the rebuilt pass, or the compile error.
+    pub(crate) fn reload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        vs_module: &wgpu::ShaderModule,
+    ) -> Option<Result<Pass, ShaderError>> {
+        let watcher = self.watcher.as_mut()?;
+        if !watcher.changed() {
+            return None;
+        }
+
+        let result = shader::load_fragment(watcher.path()).map(|spirv| {
+            let desc = PostPass {
+                spirv,
+                ..self.desc.clone()
+            };
+            let mut pass = Pass::new(device, queue, format, vs_module, desc);
+            pass.watcher = self.watcher.take();
+            pass
+        });
+
+        Some(result)
+    }
+
+    pub(crate) fn write_uniforms(&self, queue: &wgpu::Queue, mut uniforms: PassUniforms) {
+        uniforms.params = self.desc.params;
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    /// Set the pipeline and bind groups, reading from `input`.
+    pub(crate) fn bind<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        input: &'a wgpu::BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, input, &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+    }
+}
+
+fn pass_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::UniformBuffer {
+                    dynamic: false,
+                    min_binding_size: wgpu::BufferSize::new(size_of::<PassUniforms>() as u64),
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::SampledTexture {
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
+                    component_type: wgpu::TextureComponentType::Float,
+                },
+                count: None,
+            },
+        ],
+        label: Some("Post pass bind group layout"),
+    })
+}
+
+// -----------------------------------------------------------------------------
+//     - Targets -
+//     Two textures the passes take turns reading from and writing to
+// -----------------------------------------------------------------------------
+pub(crate) struct Targets {
+    textures: [texture::Texture; 2],
+    bind_groups: [wgpu::BindGroup; 2],
+}
+
+impl Targets {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        size: ScreenSize,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let textures = [
+            texture::Texture::render_target(device, size, format),
+            texture::Texture::render_target(device, size, format),
+        ];
+        let bind_groups = [
+            bind_group(device, &textures[0]),
+            bind_group(device, &textures[1]),
+        ];
+
+        Self {
+            textures,
+            bind_groups,
+        }
+    }
+
+    pub(crate) fn view(&self, index: usize) -> &wgpu::TextureView {
+        &self.textures[index % 2].view
+    }
+
+    pub(crate) fn bind_group(&self, index: usize) -> &wgpu::BindGroup {
+        &self.bind_groups[index % 2]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+    #[test]
+    fn builtin_passes_are_spirv() {
+        let passes = vec![
+            PostPass::scanlines(0.3, 2.0),
+            PostPass::vignette(0.5, 0.5),
+            PostPass::chromatic_aberration(1.0),
+            PostPass::bloom(0.7, 0.5, 2.0),
+            PostPass::palette_lookup(&Palette::pico8()),
+        ];
+
+        for pass in passes {
+            assert_eq!(pass.spirv()[0], SPIRV_MAGIC, "{}", pass.name);
+        }
+    }
+
+    #[test]
+    fn uniforms_match_std140_block() {
+        // vec2 resolution, float time, float frame, vec4 params
+        assert_eq!(size_of::<PassUniforms>(), 32);
+    }
+
+    #[test]
+    fn palette_lookup_params() {
+        let pass = PostPass::palette_lookup(&Palette::gameboy());
+        assert_eq!(pass.params[0], 4.0);
+        assert_eq!(pass.lookup.len(), 4);
+    }
+}
@@ -0,0 +1,547 @@
+use crate::pixel::{from_linear, to_linear};
+use crate::{Image, IndexedImage, Palette, Pixel};
+
+// -----------------------------------------------------------------------------
+//     - OKLab -
+// -----------------------------------------------------------------------------
+/// A colour in the OKLab colour space.
+/// Distances in OKLab match perceived colour differences far better than
+/// distances in RGB, which makes it a good space for nearest colour matching.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Oklab {
+    pub fn from_pixel(pixel: Pixel) -> Self {
+        Self::from_srgb(pixel.r as f32, pixel.g as f32, pixel.b as f32)
+    }
+
+    /// Convert back to a pixel with the given alpha.
+    pub fn to_pixel(&self, alpha: u8) -> Pixel {
+        let (r, g, b) = self.to_srgb();
+        let channel = |c: f32| c.round().clamp(0.0, 255.0) as u8;
+        Pixel::new(channel(r), channel(g), channel(b), alpha)
+    }
+
+    fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    fn to_srgb(self) -> (f32, f32, f32) {
+        let l = (self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b).powi(3);
+        let m = (self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b).powi(3);
+        let s = (self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b).powi(3);
+
+        let r = 4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s;
+        let g = -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s;
+
+        (from_linear(r), from_linear(g), from_linear(b))
+    }
+
+    fn distance_squared(&self, other: &Oklab) -> f32 {
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        dl * dl + da * da + db * db
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Dithering -
+// -----------------------------------------------------------------------------
+/// How the error between a colour and its closest palette colour is spread out.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dither {
+    /// Plain nearest colour matching.
+    None,
+    /// Ordered dithering with a 2x2 Bayer matrix.
+    Bayer2x2,
+    /// Ordered dithering with a 4x4 Bayer matrix.
+    Bayer4x4,
+    /// Ordered dithering with an 8x8 Bayer matrix.
+    Bayer8x8,
+    /// Floyd-Steinberg error diffusion.
+    FloydSteinberg,
+    /// Atkinson error diffusion. Only diffuses 3/4 of the error,
+    /// which keeps more contrast than Floyd-Steinberg.
+    Atkinson,
+}
+
+const FLOYD_STEINBERG: &[(i64, i64, f32)] = &[
+    (1, 0, 7.0 / 16.0),
+    (-1, 1, 3.0 / 16.0),
+    (0, 1, 5.0 / 16.0),
+    (1, 1, 1.0 / 16.0),
+];
+
+const ATKINSON: &[(i64, i64, f32)] = &[
+    (1, 0, 1.0 / 8.0),
+    (2, 0, 1.0 / 8.0),
+    (-1, 1, 1.0 / 8.0),
+    (0, 1, 1.0 / 8.0),
+    (1, 1, 1.0 / 8.0),
+    (0, 2, 1.0 / 8.0),
+];
+
+// Threshold in the range 0..1 for a position in a Bayer matrix of size `n`.
+fn bayer(n: u32, x: u32, y: u32) -> f32 {
+    // Each level of the recursive matrix contributes one bit of x and y,
+    // with the finest bits carrying the most weight.
+    let mut value = 0;
+    let mut size = 1;
+    while size < n {
+        let (bx, by) = ((x / size) % 2, (y / size) % 2);
+        let quadrant = [[0, 2], [3, 1]][by as usize][bx as usize];
+        value += quadrant * (n / size / 2) * (n / size / 2);
+        size *= 2;
+    }
+
+    (value as f32 + 0.5) / (n * n) as f32
+}
+
+// -----------------------------------------------------------------------------
+//     - Quantize -
+// -----------------------------------------------------------------------------
+/// Convert a true colour image into an indexed image using the given palette.
+/// Fully transparent pixels map to the first transparent colour in the palette,
+/// if there is one. Panics if the palette has more than 256 colours.
+pub fn quantize(image: &Image, palette: &Palette, dither: Dither) -> IndexedImage {
+    assert!(palette.len() <= 256, "palette has more than 256 colours");
+
+    let matcher = Matcher::new(palette);
+    let mut indexed = IndexedImage::new(image.size(), palette.clone());
+    indexed.transparent = matcher.transparent;
+
+    match dither {
+        Dither::None => {
+            for (pos, pixel) in image.enumerate_pixels() {
+                indexed.set(pos.x, pos.y, matcher.pixel(*pixel, 0.0));
+            }
+        }
+        Dither::Bayer2x2 => ordered(image, &matcher, &mut indexed, 2),
+        Dither::Bayer4x4 => ordered(image, &matcher, &mut indexed, 4),
+        Dither::Bayer8x8 => ordered(image, &matcher, &mut indexed, 8),
+        Dither::FloydSteinberg => diffuse(image, &matcher, &mut indexed, FLOYD_STEINBERG),
+        Dither::Atkinson => diffuse(image, &matcher, &mut indexed, ATKINSON),
+    }
+
+    indexed
+}
+
+fn ordered(image: &Image, matcher: &Matcher, indexed: &mut IndexedImage, n: u32) {
+    // Spread the threshold over roughly the distance between two palette colours
+    let spread = 255.0 / (matcher.colours.len().max(2) as f32).cbrt();
+
+    for (pos, pixel) in image.enumerate_pixels() {
+        let offset = (bayer(n, pos.x, pos.y) - 0.5) * spread;
+        indexed.set(pos.x, pos.y, matcher.pixel(*pixel, offset));
+    }
+}
+
+fn diffuse(
+    image: &Image,
+    matcher: &Matcher,
+    indexed: &mut IndexedImage,
+    kernel: &[(i64, i64, f32)],
+) {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let mut colours = image
+        .pixels()
+        .iter()
+        .map(|p| Oklab::from_pixel(*p))
+        .collect::<Vec<_>>();
+
+    for (pos, pixel) in image.enumerate_pixels() {
+        let i = (pos.y as i64 * width + pos.x as i64) as usize;
+
+        if pixel.a == 0 {
+            if let Some(index) = matcher.transparent {
+                indexed.set(pos.x, pos.y, index);
+                continue;
+            }
+        }
+
+        let current = colours[i];
+        let index = matcher.nearest(&current);
+        indexed.set(pos.x, pos.y, index);
+
+        let chosen = matcher.oklab(index);
+        let error = (
+            current.l - chosen.l,
+            current.a - chosen.a,
+            current.b - chosen.b,
+        );
+
+        for (dx, dy, weight) in kernel {
+            let (x, y) = (pos.x as i64 + dx, pos.y as i64 + dy);
+            if x < 0 || x >= width || y >= height {
+                continue;
+            }
+
+            let colour = &mut colours[(y * width + x) as usize];
+            colour.l += error.0 * weight;
+            colour.a += error.1 * weight;
+            colour.b += error.2 * weight;
+        }
+    }
+}
+
+// Nearest colour lookup against the opaque colours of a palette
+struct Matcher {
+    colours: Vec<(u8, Oklab)>,
+    transparent: Option<u8>,
+}
+
+impl Matcher {
+    fn new(palette: &Palette) -> Self {
+        let colours = palette
+            .colours()
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.a > 0)
+            .map(|(i, p)| (i as u8, Oklab::from_pixel(*p)))
+            .collect();
+
+        let transparent = palette
+            .colours()
+            .iter()
+            .position(|p| p.a == 0)
+            .map(|i| i as u8);
+
+        Self {
+            colours,
+            transparent,
+        }
+    }
+
+    // Match a pixel, with `offset` added to every channel first
+    fn pixel(&self, pixel: Pixel, offset: f32) -> u8 {
+        match (pixel.a, self.transparent) {
+            (0, Some(index)) => index,
+            _ => {
+                let colour = Oklab::from_srgb(
+                    pixel.r as f32 + offset,
+                    pixel.g as f32 + offset,
+                    pixel.b as f32 + offset,
+                );
+                self.nearest(&colour)
+            }
+        }
+    }
+
+    fn nearest(&self, colour: &Oklab) -> u8 {
+        self.colours
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(colour)
+                    .partial_cmp(&b.distance_squared(colour))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(index, _)| *index)
+            .unwrap_or(0)
+    }
+
+    fn oklab(&self, index: u8) -> Oklab {
+        self.colours
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, colour)| *colour)
+            .unwrap_or(Oklab {
+                l: 0.0,
+                a: 0.0,
+                b: 0.0,
+            })
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Palette generation -
+// -----------------------------------------------------------------------------
+impl Palette {
+    /// Build a palette of at most `count` colours from the opaque pixels
+    /// of an image, by repeatedly splitting the colour box with the widest
+    /// range at its median.
+    pub fn median_cut(image: &Image, count: usize) -> Palette {
+        let pixels = image
+            .pixels()
+            .iter()
+            .filter(|p| p.a > 0)
+            .copied()
+            .collect::<Vec<_>>();
+        if pixels.is_empty() || count == 0 {
+            return Palette::new(Vec::new());
+        }
+
+        let mut boxes = vec![pixels];
+        while boxes.len() < count.min(256) {
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .map(|(i, pixels)| (i, widest_channel(pixels)))
+                .max_by_key(|(_, (_, range))| *range);
+
+            let (i, channel) = match widest {
+                Some((i, (channel, range))) if range > 0 => (i, channel),
+                _ => break,
+            };
+
+            let mut pixels = boxes.swap_remove(i);
+            pixels.sort_by_key(|p| channel_value(p, channel));
+
+            // Split on the median value rather than the median index,
+            // so identical colours always end up in the same box.
+            let median = channel_value(&pixels[pixels.len() / 2], channel);
+            let mut split = pixels.partition_point(|p| channel_value(p, channel) < median);
+            if split == 0 {
+                split = pixels.partition_point(|p| channel_value(p, channel) <= median);
+            }
+
+            let upper = pixels.split_off(split);
+            boxes.push(pixels);
+            boxes.push(upper);
+        }
+
+        Palette::new(boxes.iter().map(|pixels| average(pixels)).collect())
+    }
+
+    /// Build a palette of at most `count` colours with k-means clustering in
+    /// OKLab space. The clusters start out as the median cut palette, so the
+    /// result is deterministic.
+    pub fn kmeans(image: &Image, count: usize, iterations: usize) -> Palette {
+        let pixels = image
+            .pixels()
+            .iter()
+            .filter(|p| p.a > 0)
+            .map(|p| Oklab::from_pixel(*p))
+            .collect::<Vec<_>>();
+
+        let mut centroids = Palette::median_cut(image, count)
+            .colours()
+            .iter()
+            .map(|p| Oklab::from_pixel(*p))
+            .collect::<Vec<_>>();
+
+        for _ in 0..iterations {
+            let mut sums = vec![(0.0, 0.0, 0.0, 0usize); centroids.len()];
+
+            for pixel in &pixels {
+                let closest = centroids
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        a.distance_squared(pixel)
+                            .partial_cmp(&b.distance_squared(pixel))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+
+                let sum = &mut sums[closest];
+                sum.0 += pixel.l;
+                sum.1 += pixel.a;
+                sum.2 += pixel.b;
+                sum.3 += 1;
+            }
+
+            let mut moved = false;
+            for (centroid, (l, a, b, n)) in centroids.iter_mut().zip(sums) {
+                if n == 0 {
+                    continue;
+                }
+
+                let n = n as f32;
+                let next = Oklab {
+                    l: l / n,
+                    a: a / n,
+                    b: b / n,
+                };
+                moved |= next.distance_squared(centroid) > f32::EPSILON;
+                *centroid = next;
+            }
+
+            if !moved {
+                break;
+            }
+        }
+
+        Palette::new(centroids.iter().map(|c| c.to_pixel(255)).collect())
+    }
+}
+
+fn channel_value(pixel: &Pixel, channel: usize) -> u8 {
+    match channel {
+        0 => pixel.r,
+        1 => pixel.g,
+        _ => pixel.b,
+    }
+}
+
+// The channel with the largest range, along with that range
+fn widest_channel(pixels: &[Pixel]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = pixels.iter().map(|p| channel_value(p, channel));
+            let min = values.clone().min().unwrap_or(0);
+            let max = values.max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn average(pixels: &[Pixel]) -> Pixel {
+    let n = pixels.len().max(1) as u32;
+    let sum = |f: fn(&Pixel) -> u8| {
+        let total = pixels.iter().map(|p| f(p) as u32).sum::<u32>();
+        ((total + n / 2) / n) as u8
+    };
+
+    Pixel::new(sum(|p| p.r), sum(|p| p.g), sum(|p| p.b), 255)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ScreenSize;
+
+    const BLACK: Pixel = Pixel::new(0, 0, 0, 255);
+    const WHITE: Pixel = Pixel::new(255, 255, 255, 255);
+    const GREY: Pixel = Pixel::new(128, 128, 128, 255);
+
+    fn black_and_white() -> Palette {
+        Palette::new(vec![BLACK, WHITE])
+    }
+
+    fn count(image: &IndexedImage, index: u8) -> usize {
+        image.indices().iter().filter(|i| **i == index).count()
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        for pixel in &[
+            BLACK,
+            WHITE,
+            GREY,
+            Pixel::new(255, 0, 77, 255),
+            Pixel::new(12, 200, 99, 255),
+        ] {
+            assert_eq!(Oklab::from_pixel(*pixel).to_pixel(255), *pixel);
+        }
+
+        let white = Oklab::from_pixel(WHITE);
+        assert!((white.l - 1.0).abs() < 1e-3);
+        assert!(white.a.abs() < 1e-3 && white.b.abs() < 1e-3);
+    }
+
+    #[test]
+    fn nearest_colour_without_dithering() {
+        let image = Image::from_pixels(
+            ScreenSize::new(3, 1),
+            vec![WHITE, BLACK, Pixel::new(250, 240, 250, 255)],
+        );
+        let indexed = quantize(&image, &black_and_white(), Dither::None);
+        assert_eq!(indexed.indices(), &[1, 0, 1]);
+    }
+
+    #[test]
+    fn transparent_pixels_use_transparent_colour() {
+        let palette = Palette::new(vec![BLACK, Pixel::zero(), WHITE]);
+        let image = Image::from_pixels(ScreenSize::new(2, 1), vec![Pixel::zero(), WHITE]);
+        let indexed = quantize(&image, &palette, Dither::FloydSteinberg);
+
+        assert_eq!(indexed.indices(), &[1, 2]);
+        assert_eq!(indexed.transparent, Some(1));
+    }
+
+    #[test]
+    fn bayer_thresholds_are_unique() {
+        for n in &[2, 4, 8] {
+            let mut values = (0..n * n)
+                .map(|i| bayer(*n, i % n, i / n))
+                .collect::<Vec<_>>();
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            values.dedup();
+            assert_eq!(values.len() as u32, n * n);
+        }
+        assert_eq!(bayer(2, 1, 1), 1.5 / 4.0);
+    }
+
+    #[test]
+    fn dithering_mixes_grey() {
+        let image = Image::new(ScreenSize::new(8, 8), GREY);
+        let dithers = [
+            Dither::Bayer2x2,
+            Dither::Bayer4x4,
+            Dither::Bayer8x8,
+            Dither::FloydSteinberg,
+            Dither::Atkinson,
+        ];
+
+        assert_eq!(
+            count(&quantize(&image, &black_and_white(), Dither::None), 0),
+            0
+        );
+
+        for dither in &dithers {
+            let indexed = quantize(&image, &black_and_white(), *dither);
+            let (black, white) = (count(&indexed, 0), count(&indexed, 1));
+            assert!(
+                black > 8 && white > 8,
+                "{:?}: {} black, {} white",
+                dither,
+                black,
+                white
+            );
+        }
+    }
+
+    #[test]
+    fn median_cut_finds_colours() {
+        let red = Pixel::new(255, 0, 0, 255);
+        let blue = Pixel::new(0, 0, 255, 255);
+        let mut pixels = vec![red; 10];
+        pixels.extend(vec![blue; 6]);
+        let image = Image::from_pixels(ScreenSize::new(4, 4), pixels);
+
+        let palette = Palette::median_cut(&image, 2);
+        assert_eq!(palette.len(), 2);
+        assert!(palette.colours().contains(&red));
+        assert!(palette.colours().contains(&blue));
+
+        // Never more colours than there are in the image
+        assert_eq!(
+            Palette::median_cut(&Image::new(ScreenSize::new(2, 2), red), 16).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn kmeans_converges_on_clusters() {
+        let pixels = vec![
+            Pixel::new(250, 0, 0, 255),
+            Pixel::new(255, 5, 0, 255),
+            Pixel::new(0, 0, 250, 255),
+            Pixel::new(5, 0, 255, 255),
+        ];
+        let image = Image::from_pixels(ScreenSize::new(2, 2), pixels);
+        let palette = Palette::kmeans(&image, 2, 10);
+
+        assert_eq!(palette.len(), 2);
+        let indexed = quantize(&image, &palette, Dither::None);
+        assert_eq!(indexed.get(0, 0), indexed.get(1, 0));
+        assert_eq!(indexed.get(0, 1), indexed.get(1, 1));
+        assert_ne!(indexed.get(0, 0), indexed.get(0, 1));
+    }
+}
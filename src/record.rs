@@ -0,0 +1,284 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+
+use crate::{Event, EventLoop, Lifecycle, Renderer, ScreenSize};
+
+/// Anything the event loop is told about, apart from updating and drawing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Recorded {
+    Input(Event),
+    Lifecycle(Lifecycle),
+    /// The effective size, as passed to [`EventLoop::resize`].
+    Resize(ScreenSize),
+    /// The window was asked to close, see [`EventLoop::close_requested`].
+    CloseRequested,
+    /// The screenshot key was pressed, see [`EventLoop::screenshot`].
+    Screenshot,
+}
+
+impl Recorded {
+    /// Pass this on to the event loop, resizing the renderer along with it.
+    /// Returns true if the event loop agreed to close.
+    pub(crate) fn send(self, el: &mut impl EventLoop, renderer: &mut Renderer) -> bool {
+        match self {
+            Recorded::Input(event) => el.input(event),
+            Recorded::Lifecycle(event) => el.lifecycle(event),
+            Recorded::Resize(size) => {
+                el.resize(size);
+                renderer.resize(PhysicalSize::new(size.width, size.height));
+            }
+            Recorded::CloseRequested => return el.close_requested(),
+            Recorded::Screenshot => el.screenshot(renderer.screenshot()),
+        }
+        false
+    }
+}
+
+/// Everything that happened in one frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Delivered before the update, in order.
+    pub events: Vec<Recorded>,
+    /// The timestep passed to [`EventLoop::update`].
+    pub dt: Duration,
+    /// [`Renderer::frame_hash`] after drawing.
+    pub hash: u64,
+}
+
+// -----------------------------------------------------------------------------
+//     - Recorder -
+// -----------------------------------------------------------------------------
+/// Writes a recording one frame at a time, so a crash still leaves
+/// everything up to the last frame on disk.
+///
+/// The file has one JSON object per line, one line per frame.
+pub struct Recorder {
+    out: BufWriter<File>,
+    events: Vec<Recorded>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            out: BufWriter::new(File::create(path)?),
+            events: Vec::new(),
+        })
+    }
+
+    /// Add an event to the current frame.
+    pub fn record(&mut self, event: Recorded) {
+        self.events.push(event);
+    }
+
+    /// Finish the current frame and write it out.
+    pub fn end_frame(&mut self, dt: Duration, hash: u64) -> io::Result<()> {
+        let frame = RecordedFrame {
+            events: self.events.drain(..).collect(),
+            dt,
+            hash,
+        };
+        serde_json::to_writer(&mut self.out, &frame)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Recording -
+// -----------------------------------------------------------------------------
+/// A recorded session, to be replayed without a window.
+///
+/// Replays are only exact if the event loop gets all of its time from
+/// `update` and all of its randomness from a seeded generator.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a recording written by a [`Recorder`].
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+        let mut frames = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            frames.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self { frames })
+    }
+
+    /// Feed the recorded events and timesteps into an event loop, drawing
+    /// every frame, usually with [`Renderer::headless`].
+    /// Close requests and screenshots are replayed too, but the replay
+    /// keeps going even if the event loop agrees to close.
+    /// Stops at the first frame that doesn't draw what was recorded,
+    /// otherwise returns the number of frames replayed.
+    pub fn replay(&self, el: &mut impl EventLoop, renderer: &mut Renderer) -> Result<usize, Desync> {
+        for (index, frame) in self.frames.iter().enumerate() {
+            for event in &frame.events {
+                event.clone().send(el, renderer);
+            }
+
+            el.update(frame.dt);
+            el.draw(renderer);
+
+            let hash = renderer.frame_hash();
+            if hash != frame.hash {
+                return Err(Desync {
+                    frame: index,
+                    expected: frame.hash,
+                    actual: hash,
+                });
+            }
+            renderer.render();
+        }
+        Ok(self.frames.len())
+    }
+}
+
+/// A replayed frame was drawn differently from the recorded one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Desync {
+    pub frame: usize,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frame {} was drawn differently (expected hash {:016x}, got {:016x})",
+            self.frame, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for Desync {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ElementState, Image, KeyEvent, ModifiersState, Pixel, ScreenPos, Viewport, VirtualKeyCode,
+    };
+
+    // Moves a pixel right while the right arrow is held
+    struct Walker {
+        viewport: Viewport,
+        x: f32,
+        held: bool,
+        speed: f32,
+        close_requests: usize,
+        screenshots: usize,
+    }
+
+    impl Walker {
+        fn new(speed: f32) -> Self {
+            Self {
+                viewport: Viewport::new(ScreenPos::zero(), ScreenSize::new(8, 2)),
+                x: 0.0,
+                held: false,
+                speed,
+                close_requests: 0,
+                screenshots: 0,
+            }
+        }
+    }
+
+    impl EventLoop for Walker {
+        fn draw(&mut self, renderer: &mut Renderer) {
+            let pos = ScreenPos::new(self.x as u32 % 8, 0);
+            self.viewport.draw_pixel(Pixel::rgb(255, 255, 255), pos);
+            renderer.draw(&mut self.viewport);
+        }
+
+        fn update(&mut self, dt: Duration) {
+            if self.held {
+                self.x += dt.as_secs_f32() * self.speed;
+            }
+        }
+
+        fn resize(&mut self, _new_size: ScreenSize) {}
+
+        fn input(&mut self, event: Event) {
+            if let Event::Key(key) = event {
+                self.held = key.pressed();
+            }
+        }
+
+        fn close_requested(&mut self) -> bool {
+            self.close_requests += 1;
+            false
+        }
+
+        fn screenshot(&mut self, _image: Image) {
+            self.screenshots += 1;
+        }
+    }
+
+    fn key(state: ElementState) -> Recorded {
+        Recorded::Input(Event::Key(KeyEvent {
+            scancode: 0,
+            state,
+            key: Some(VirtualKeyCode::Right),
+            modifiers: ModifiersState::empty(),
+        }))
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!("tinypixel-record-{}.jsonl", std::process::id()));
+        let dt = Duration::from_millis(100);
+        let frames = vec![
+            vec![Recorded::Resize(ScreenSize::new(8, 2))],
+            vec![key(ElementState::Pressed)],
+            vec![Recorded::Screenshot, Recorded::CloseRequested],
+            vec![key(ElementState::Released), Recorded::Lifecycle(Lifecycle::Focused(false))],
+        ];
+
+        // What `start` does, minus the window
+        let mut walker = Walker::new(10.0);
+        let mut renderer = Renderer::headless(ScreenSize::new(4, 4));
+        let mut recorder = Recorder::create(&path).unwrap();
+        for events in frames {
+            for event in events {
+                recorder.record(event.clone());
+                event.send(&mut walker, &mut renderer);
+            }
+            walker.update(dt);
+            walker.draw(&mut renderer);
+            recorder.end_frame(dt, renderer.frame_hash()).unwrap();
+        }
+        drop(recorder);
+
+        let recording = Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recording.frames.len(), 4);
+        assert_eq!(recording.frames[3].events.len(), 2);
+
+        let mut renderer = Renderer::headless(ScreenSize::new(4, 4));
+        let mut walker = Walker::new(10.0);
+        assert_eq!(recording.replay(&mut walker, &mut renderer), Ok(4));
+        assert_eq!((walker.close_requests, walker.screenshots), (1, 1));
+
+        // A change in behaviour shows up as soon as it is drawn
+        let mut renderer = Renderer::headless(ScreenSize::new(4, 4));
+        let desync = recording
+            .replay(&mut Walker::new(20.0), &mut renderer)
+            .unwrap_err();
+        assert_eq!(desync.frame, 1);
+    }
+}
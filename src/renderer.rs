@@ -1,10 +1,19 @@
 use std::mem::size_of;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use futures::executor::block_on;
 use wgpu::util::DeviceExt;
 use winit::{dpi::PhysicalSize, window::Window};
 
-use crate::{texture, Pixel, PixelBuffer, ScreenPos, ScreenSize, Viewport};
+use crate::postprocess::{Pass, PassUniforms, Targets};
+use crate::resize::{effective_size, MAX_TEXTURE_DIMENSION};
+use crate::screenshot;
+use crate::shader::{compile_fragment, compile_vertex, ShaderError, ShaderLanguage, ShaderWatcher};
+use crate::{
+    texture, Compositor, FrameStage, FrameStats, Image, Pixel, PostPass, PresentMode, ScreenPos,
+    ScreenRect, ScreenSize, StatsOverlay, Upscaler, Viewport,
+};
 
 // -----------------------------------------------------------------------------
 //     - Vertex-
@@ -73,66 +82,377 @@ const INDICES: &[u16] = &[0, 2, 3, 0, 3, 1];
 //     - Renderer -
 // -----------------------------------------------------------------------------
 pub struct Renderer {
-    state: State,
-    pixels: PixelBuffer,
+    backend: Backend,
+    // The window size, clamped to what can be rendered
+    size: PhysicalSize<u32>,
+    pixels: Image,
+    upscaler: Option<Upscaler>,
+    // The upscaled pixels, kept until the pixels change
+    upscaled: Image,
+    changed: bool,
+    started: Instant,
+    frames: u32,
+    last_reload: Instant,
+    stats: FrameStats,
+    overlay: Option<StatsOverlay>,
 }
 
 impl Renderer {
-    fn coords_to_index(&self, pos: ScreenPos) -> usize {
-        (pos.x + pos.y * self.state.size.width) as usize
-    }
-
     pub fn draw(&mut self, viewport: &mut Viewport) {
+        let start = Instant::now();
         let pixels = viewport.pixels();
-        if pixels.len() > 0 {
-            eprintln!("{:?}", pixels.len());
-        }
+        self.stats.add(FrameStage::Diff, start.elapsed());
+        self.changed |= !pixels.is_empty();
         pixels.into_iter().for_each(|(pix, pos)| {
-            let index = self.coords_to_index(pos);
-            if index < self.pixels.inner.len() {
-                self.pixels.inner[index] = pix;
-            }
+            self.pixels.set(pos.x, pos.y, pix);
+        });
+    }
+
+    /// Draw all viewports of a compositor, stacked by their z-index.
+    /// Use this instead of `draw` when viewports overlap.
+    pub fn draw_composited(&mut self, compositor: &mut Compositor) {
+        let start = Instant::now();
+        let pixels = compositor.pixels();
+        self.stats.add(FrameStage::Diff, start.elapsed());
+        self.changed |= !pixels.is_empty();
+        pixels.into_iter().for_each(|(pix, pos)| {
+            self.pixels.set(pos.x, pos.y, pix);
         });
     }
 
     pub fn render(&mut self) {
-        self.state.queue.write_texture(
-            wgpu::TextureCopyView {
-                texture: &self.state.texture.inner,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &self.pixels,
-            wgpu::TextureDataLayout {
-                offset: 0,
-                bytes_per_row: size_of::<Pixel>() as u32 * self.state.size.width,
-                rows_per_image: self.state.size.height,
+        if self.last_reload.elapsed() >= RELOAD_INTERVAL {
+            self.last_reload = Instant::now();
+            self.reload_passes();
+        }
+
+        let uniforms = self.uniforms();
+        self.frames = self.frames.wrapping_add(1);
+
+        let state = match &mut self.backend {
+            Backend::Window(state) => state,
+            Backend::Headless(_) => {
+                self.stats.end_frame(Instant::now());
+                return;
+            }
+        };
+
+        let upload_start = Instant::now();
+        let image = match self.upscaler {
+            Some(upscaler) => {
+                if self.changed {
+                    upscaler.apply_into(&self.pixels, &mut self.upscaled);
+                }
+                &self.upscaled
+            }
+            None => &self.pixels,
+        };
+        self.changed = false;
+
+        if state.texture.size.width != image.width() || state.texture.size.height != image.height()
+        {
+            state.resize_texture(image.size());
+        }
+
+        state.write_texture(image, ScreenPos::zero());
+
+        // The overlay is uploaded on top, leaving the pixels underneath intact
+        if let Some(overlay) = &self.overlay {
+            let area = ScreenRect::new(overlay.position, overlay.size).intersection(&image.rect());
+            if let Some(area) = area.filter(|area| !area.is_empty()) {
+                let overlay = overlay.render(&self.stats, image.view(area));
+                state.write_texture(&overlay, area.origin);
+            }
+        }
+        self.stats.add(FrameStage::Upload, upload_start.elapsed());
+
+        let present_start = Instant::now();
+        state.render(uniforms);
+        self.stats.add(FrameStage::Present, present_start.elapsed());
+        self.stats.end_frame(Instant::now());
+    }
+
+    /// Read back the most recent frame as it was presented, after
+    /// upscaling and post-processing. This stalls until the GPU is done.
+    /// A headless renderer returns the upscaled canvas.
+    pub fn screenshot(&self) -> Image {
+        match &self.backend {
+            Backend::Window(state) => state.screenshot(self.uniforms()),
+            Backend::Headless(_) => match self.upscaler {
+                Some(upscaler) => upscaler.apply(&self.pixels),
+                None => self.pixels.clone(),
             },
-            self.state.texture.size,
-        );
-        self.state.render();
+        }
     }
 
+    fn uniforms(&self) -> PassUniforms {
+        PassUniforms {
+            resolution: [self.size.width as f32, self.size.height as f32],
+            time: self.started.elapsed().as_secs_f32(),
+            frame: self.frames as f32,
+            params: [0.0; 4],
+        }
+    }
+
+    /// Resize to the window size. Sizes above [`MAX_TEXTURE_DIMENSION`] are
+    /// clamped, and zero sizes (a minimised window) are ignored.
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
-        let cap = new_size.width * new_size.height;
-        let pixels = PixelBuffer::new(cap as usize, Pixel::zero());
-        self.pixels = pixels;
-        self.state.resize(new_size);
+        let new_size = match render_size(new_size) {
+            Some(size) => size,
+            None => return,
+        };
+        self.size = new_size;
+        self.pixels = Image::empty(canvas_size(new_size, self.upscaler));
+        self.changed = true;
+        if let Backend::Window(state) = &mut self.backend {
+            state.resize(new_size);
+        }
+    }
+
+    /// Run an upscaler as a final pass before the pixels are uploaded.
+    /// Viewports then draw onto a smaller canvas (see [`Renderer::canvas_size`])
+    /// which is scaled up to fill the window.
+    /// The renderer is cleared when the upscaler changes.
+    ///
+    /// If the window size is not a multiple of the upscaler's factor, the
+    /// canvas size is rounded down and the upscaled image is stretched by the
+    /// remaining few pixels to fill the window.
+    pub fn set_upscaler(&mut self, upscaler: Option<Upscaler>) {
+        self.upscaler = upscaler;
+        self.pixels = Image::empty(canvas_size(self.size, upscaler));
+        self.changed = true;
+    }
+
+    /// Add a post-processing pass, run after every pass added before it.
+    /// Returns the index of the pass.
+    pub fn add_pass(&mut self, pass: PostPass) -> usize {
+        match &mut self.backend {
+            Backend::Window(state) => {
+                state.add_pass(pass);
+                state.passes.len() - 1
+            }
+            Backend::Headless(passes) => {
+                passes.push(pass);
+                passes.len() - 1
+            }
+        }
+    }
+
+    /// Frame timings of recent frames.
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Frame timings, for recording time spent outside of the renderer.
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+
+    /// Show (or hide) an FPS counter and frame time graph on top of
+    /// every viewport.
+    pub fn set_stats_overlay(&mut self, overlay: Option<StatsOverlay>) {
+        self.overlay = overlay;
+    }
+
+    /// Change how frames are presented, recreating the swap chain.
+    /// `Fifo` waits for vsync, `Mailbox` waits for vsync but replaces a queued
+    /// frame with a newer one, and `Immediate` does not wait (and may tear).
+    /// If the mode is not supported, wgpu falls back to `Fifo` and logs a warning.
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        if let Backend::Window(state) = &mut self.backend {
+            state.sc_desc.present_mode = mode;
+            state.swap_chain = state
+                .device
+                .create_swap_chain(&state.surface, &state.sc_desc);
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        match &self.backend {
+            Backend::Window(state) => state.sc_desc.present_mode,
+            Backend::Headless(_) => PresentMode::Fifo,
+        }
+    }
+
+    /// Present modes that are known to work.
+    /// wgpu 0.6 does not expose the surface capabilities, so this only
+    /// lists `Fifo`, which every backend supports. Other modes can still be
+    /// requested with [`Renderer::set_present_mode`].
+    pub fn supported_present_modes(&self) -> &'static [PresentMode] {
+        &[PresentMode::Fifo]
+    }
+
+    /// Load a post-processing pass from a GLSL or WGSL file.
+    /// The pass is recompiled whenever the file changes. If the new
+    /// source does not compile, the previous version keeps running and
+    /// the error is available from [`Renderer::shader_error`].
+    pub fn add_pass_from_file(&mut self, path: impl AsRef<Path>) -> Result<usize, ShaderError> {
+        let path = path.as_ref();
+        let pass = PostPass::load(path)?;
+        let index = self.add_pass(pass);
+        if let Backend::Window(state) = &mut self.backend {
+            state.passes[index].watcher = Some(ShaderWatcher::new(path));
+        }
+        Ok(index)
+    }
+
+    /// The error from the first pass whose last reload failed, if that
+    /// shader has not been fixed since.
+    pub fn shader_error(&self) -> Option<&ShaderError> {
+        match &self.backend {
+            Backend::Window(state) => state.passes.iter().find_map(|pass| pass.error.as_ref()),
+            Backend::Headless(_) => None,
+        }
+    }
+
+    fn reload_passes(&mut self) {
+        let state = match &mut self.backend {
+            Backend::Window(state) => state,
+            Backend::Headless(_) => return,
+        };
+        for index in 0..state.passes.len() {
+            let format = state.sc_desc.format;
+            let reloaded =
+                state.passes[index].reload(&state.device, &state.queue, format, &state.vs_module);
+
+            match reloaded {
+                Some(Ok(pass)) => state.passes[index] = pass,
+                Some(Err(e)) => state.passes[index].error = Some(e),
+                None => {}
+            }
+        }
+    }
+
+    /// Remove a post-processing pass.
+    /// Panics if the index is out of bounds.
+    pub fn remove_pass(&mut self, index: usize) -> PostPass {
+        match &mut self.backend {
+            Backend::Window(state) => {
+                let pass = state.passes.remove(index).desc;
+                if state.passes.is_empty() {
+                    state.targets = None;
+                }
+                pass
+            }
+            Backend::Headless(passes) => passes.remove(index),
+        }
+    }
+
+    /// Remove all post-processing passes.
+    pub fn clear_passes(&mut self) {
+        match &mut self.backend {
+            Backend::Window(state) => {
+                state.passes.clear();
+                state.targets = None;
+            }
+            Backend::Headless(passes) => passes.clear(),
+        }
+    }
+
+    /// The post-processing passes, in the order they run.
+    pub fn passes(&self) -> impl Iterator<Item = &PostPass> {
+        let (window, headless): (&[Pass], &[PostPass]) = match &self.backend {
+            Backend::Window(state) => (&state.passes, &[]),
+            Backend::Headless(passes) => (&[], passes),
+        };
+        window.iter().map(|pass| &pass.desc).chain(headless)
+    }
+
+    /// Change the `params` uniform of a pass.
+    /// Indices outside of the pass list are ignored.
+    pub fn set_pass_params(&mut self, index: usize, params: [f32; 4]) {
+        let pass = match &mut self.backend {
+            Backend::Window(state) => state.passes.get_mut(index).map(|pass| &mut pass.desc),
+            Backend::Headless(passes) => passes.get_mut(index),
+        };
+        if let Some(pass) = pass {
+            pass.params = params;
+        }
+    }
+
+    /// The size of the area viewports draw onto.
+    /// This is the window size, unless an upscaler is set.
+    pub fn canvas_size(&self) -> ScreenSize {
+        self.pixels.size()
     }
 
     pub fn new(window: &Window) -> Self {
-        let size = window.inner_size();
-        let cap = size.width * size.height;
-        let pixels = PixelBuffer::new(cap as usize, Pixel::zero());
+        let size = initial_size(window);
+        Self::with_backend(
+            Backend::Window(Box::new(block_on(State::new(window)))),
+            size,
+        )
+    }
+
+    /// A renderer without a window or GPU, for tests and replays.
+    /// Viewports are drawn onto the canvas as usual, but nothing is
+    /// presented and post-processing passes never run.
+    pub fn headless(size: ScreenSize) -> Self {
+        let size = render_size(PhysicalSize::new(size.width, size.height))
+            .unwrap_or_else(|| PhysicalSize::new(1, 1));
+        Self::with_backend(Backend::Headless(Vec::new()), size)
+    }
+
+    /// A hash of the canvas, to check that two runs drew the same thing.
+    /// Stable across platforms and builds.
+    pub fn frame_hash(&self) -> u64 {
+        self.pixels.content_hash()
+    }
 
+    fn with_backend(backend: Backend, size: PhysicalSize<u32>) -> Self {
         Self {
-            state: block_on(State::new(window)),
-            pixels,
+            backend,
+            size,
+            pixels: Image::empty(canvas_size(size, None)),
+            upscaler: None,
+            upscaled: Image::empty(ScreenSize::zero()),
+            changed: true,
+            started: Instant::now(),
+            frames: 0,
+            last_reload: Instant::now(),
+            stats: FrameStats::default(),
+            overlay: None,
         }
     }
 }
 
-fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+enum Backend {
+    Window(Box<State>),
+    // Passes are kept so indices match the windowed renderer
+    Headless(Vec<PostPass>),
+}
+
+/// How often shader files are checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_millis(250);
+
+// Rounded down, see `Renderer::set_upscaler`
+fn canvas_size(window_size: PhysicalSize<u32>, upscaler: Option<Upscaler>) -> ScreenSize {
+    let factor = upscaler.map(|u| u.factor()).unwrap_or(1);
+    ScreenSize::new(window_size.width / factor, window_size.height / factor)
+}
+
+/// The window size clamped to what the device can render,
+/// or `None` for a zero-sized window.
+fn render_size(window_size: PhysicalSize<u32>) -> Option<PhysicalSize<u32>> {
+    let size = ScreenSize::new(window_size.width, window_size.height);
+    effective_size(size, MAX_TEXTURE_DIMENSION)
+        .map(|size| PhysicalSize::new(size.width, size.height))
+}
+
+// A window can start out minimised, but the swap chain needs some size
+fn initial_size(window: &Window) -> PhysicalSize<u32> {
+    render_size(window.inner_size()).unwrap_or_else(|| PhysicalSize::new(1, 1))
+}
+
+type Compile = fn(&str, ShaderLanguage) -> Result<Vec<u32>, ShaderError>;
+
+// The main pipeline shaders are covered by the tests in `shader`, so they always compile
+fn builtin_module(device: &wgpu::Device, source: &str, compile: Compile) -> wgpu::ShaderModule {
+    let spirv = compile(source, ShaderLanguage::Glsl).expect("built-in shader failed to compile");
+    device.create_shader_module(wgpu::ShaderModuleSource::SpirV(spirv.as_slice().into()))
+}
+
+pub(crate) fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         entries: &[
             wgpu::BindGroupLayoutEntry {
@@ -156,9 +476,9 @@ fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     })
 }
 
-fn bind_group(device: &wgpu::Device, texture: &texture::Texture) -> wgpu::BindGroup {
+pub(crate) fn bind_group(device: &wgpu::Device, texture: &texture::Texture) -> wgpu::BindGroup {
     device.create_bind_group(&wgpu::BindGroupDescriptor {
-        layout: &bind_group_layout(&device),
+        layout: &bind_group_layout(device),
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
@@ -191,11 +511,14 @@ struct State {
     num_indices: u32,
     diffuse_bind_group: wgpu::BindGroup,
     texture: texture::Texture,
+    vs_module: wgpu::ShaderModule,
+    passes: Vec<Pass>,
+    targets: Option<Targets>,
 }
 
 impl State {
     async fn new(window: &Window) -> Self {
-        let size = window.inner_size();
+        let size = initial_size(window);
         let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
         let surface = unsafe { instance.create_surface(window) };
         let adapter = instance
@@ -241,8 +564,8 @@ impl State {
         // -----------------------------------------------------------------------------
         //     - Shader bits -
         // -----------------------------------------------------------------------------
-        let vs_module = device.create_shader_module(wgpu::include_spirv!("shader.vert.spv"));
-        let fs_module = device.create_shader_module(wgpu::include_spirv!("shader.frag.spv"));
+        let vs_module = builtin_module(&device, include_str!("shader.vert"), compile_vertex);
+        let fs_module = builtin_module(&device, include_str!("shader.frag"), compile_fragment);
 
         // buffer business
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -262,10 +585,10 @@ impl State {
         // -----------------------------------------------------------------------------
         let render_pipeline = create_pipeline(
             &device,
-            &sc_desc,
-            vs_module,
-            fs_module,
-            texture_bind_group_layout,
+            sc_desc.format,
+            &vs_module,
+            &fs_module,
+            &[&texture_bind_group_layout],
         );
 
         Self {
@@ -281,27 +604,86 @@ impl State {
             num_indices: INDICES.len() as u32,
             diffuse_bind_group,
             texture,
+            vs_module,
+            passes: Vec::new(),
+            targets: None,
         }
     }
 
+    fn add_pass(&mut self, pass: PostPass) {
+        let pass = Pass::new(
+            &self.device,
+            &self.queue,
+            self.sc_desc.format,
+            &self.vs_module,
+            pass,
+        );
+        self.passes.push(pass);
+
+        if self.targets.is_none() {
+            self.targets = Some(Targets::new(
+                &self.device,
+                self.screen_size(),
+                self.sc_desc.format,
+            ));
+        }
+    }
+
+    fn screen_size(&self) -> ScreenSize {
+        ScreenSize::new(self.size.width, self.size.height)
+    }
+
     fn resize(&mut self, new_size: PhysicalSize<u32>) {
         self.size = new_size;
         self.sc_desc.width = new_size.width;
         self.sc_desc.height = new_size.height;
         self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
 
-        let texture = texture::Texture::empty(
-            &self.device,
-            &self.queue,
-            ScreenSize::new(new_size.width, new_size.height),
+        self.resize_texture(ScreenSize::new(new_size.width, new_size.height));
+
+        if self.targets.is_some() {
+            self.targets = Some(Targets::new(
+                &self.device,
+                self.screen_size(),
+                self.sc_desc.format,
+            ));
+        }
+    }
+
+    fn write_texture(&self, image: &Image, origin: ScreenPos) {
+        self.queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &self.texture.inner,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin.x,
+                    y: origin.y,
+                    z: 0,
+                },
+            },
+            image.as_bytes(),
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: size_of::<Pixel>() as u32 * image.width(),
+                rows_per_image: image.height(),
+            },
+            wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth: 1,
+            },
         );
+    }
+
+    fn resize_texture(&mut self, size: ScreenSize) {
+        let texture = texture::Texture::empty(&self.device, &self.queue, size);
 
         self.diffuse_bind_group = bind_group(&self.device, &texture);
 
         self.texture = texture;
     }
 
-    fn render(&mut self) {
+    fn render(&mut self, uniforms: PassUniforms) {
         let frame = self
             .swap_chain
             .get_current_frame()
@@ -311,49 +693,149 @@ impl State {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.encode(&mut encoder, &frame.view, uniforms);
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &frame.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
+    /// Render into an offscreen copy of the frame and read it back.
+    fn screenshot(&self, uniforms: PassUniforms) -> Image {
+        let size = self.screen_size();
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth: 1,
+        };
+
+        // Swap chain images can't be copied from, so the frame is drawn again
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Screenshot"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.sc_desc.format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
         });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bytes_per_row = screenshot::padded_bytes_per_row(size.width);
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot buffer"),
+            size: (bytes_per_row * size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.encode(&mut encoder, &view, uniforms);
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row,
+                    rows_per_image: size.height,
+                },
+            },
+            extent,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapped = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(mapped).expect("Failed to read back the frame");
+
+        let image = screenshot::from_bgra(&slice.get_mapped_range(), size, bytes_per_row);
+        buffer.unmap();
+        image
+    }
+
+    fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: &wgpu::TextureView,
+        uniforms: PassUniforms,
+    ) {
+        // Draw the pixels straight to the frame, or to the first
+        // target if there are post-processing passes.
+        let output = match &self.targets {
+            Some(targets) => targets.view(0),
+            None => frame,
+        };
 
+        let mut render_pass = begin_render_pass(encoder, output);
         render_pass.set_pipeline(&self.render_pipeline);
         render_pass.set_bind_group(0, &self.diffuse_bind_group, &[]);
+        self.draw_quad(&mut render_pass);
+        drop(render_pass);
+
+        if let Some(targets) = &self.targets {
+            let last = self.passes.len() - 1;
+            for (index, pass) in self.passes.iter().enumerate() {
+                pass.write_uniforms(&self.queue, uniforms);
+
+                let output = if index == last {
+                    frame
+                } else {
+                    targets.view(index + 1)
+                };
+                let mut render_pass = begin_render_pass(encoder, output);
+                pass.bind(&mut render_pass, targets.bind_group(index));
+                self.draw_quad(&mut render_pass);
+            }
+        }
+    }
+
+    fn draw_quad<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..));
         render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
-
-        drop(render_pass);
-
-        self.queue.submit(std::iter::once(encoder.finish()));
     }
 }
 
+fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    output: &'a wgpu::TextureView,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: output,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 1.0,
+                }),
+                store: true,
+            },
+        }],
+        depth_stencil_attachment: None,
+    })
+}
+
 // -----------------------------------------------------------------------------
 //     - Create pipeline -
 // -----------------------------------------------------------------------------
-fn create_pipeline(
+pub(crate) fn create_pipeline(
     device: &wgpu::Device,
-    sc_desc: &wgpu::SwapChainDescriptor,
-    vs_module: wgpu::ShaderModule,
-    fs_module: wgpu::ShaderModule,
-    texture_bind_group: wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+    vs_module: &wgpu::ShaderModule,
+    fs_module: &wgpu::ShaderModule,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
 ) -> wgpu::RenderPipeline {
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render pipeline layout what does this even mean"),
-        bind_group_layouts: &[&texture_bind_group],
+        bind_group_layouts,
         push_constant_ranges: &[],
     });
 
@@ -361,11 +843,11 @@ fn create_pipeline(
         label: Some("Pipeline omg pipeline (render okay)"),
         layout: Some(&render_pipeline_layout),
         vertex_stage: wgpu::ProgrammableStageDescriptor {
-            module: &vs_module,
+            module: vs_module,
             entry_point: "main",
         },
         fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-            module: &fs_module,
+            module: fs_module,
             entry_point: "main",
         }),
         rasterization_state: Some(wgpu::RasterizationStateDescriptor {
@@ -377,7 +859,7 @@ fn create_pipeline(
             clamp_depth: false,
         }),
         color_states: &[wgpu::ColorStateDescriptor {
-            format: sc_desc.format,
+            format,
             color_blend: wgpu::BlendDescriptor::REPLACE,
             alpha_blend: wgpu::BlendDescriptor::REPLACE,
             write_mask: wgpu::ColorWrite::ALL,
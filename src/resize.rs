@@ -0,0 +1,132 @@
+use crate::ScreenSize;
+
+/// The largest texture side every backend supports.
+/// wgpu does not report the real limit, so this errs on the safe side.
+pub const MAX_TEXTURE_DIMENSION: u32 = 8192;
+
+/// The size to render at for a window size: `None` for a zero-sized
+/// (minimised) window, and clamped to `max` on each side.
+pub fn effective_size(size: ScreenSize, max: u32) -> Option<ScreenSize> {
+    match size.is_empty() {
+        true => None,
+        false => Some(ScreenSize::new(size.width.min(max), size.height.min(max))),
+    }
+}
+
+/// What happened to the render size since it was last applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SizeChange {
+    /// The window has no area, so rendering should stop.
+    Paused,
+    /// Render at this size from now on, resuming if paused.
+    Resized(ScreenSize),
+}
+
+/// Decides when and at what size the renderer is resized.
+///
+/// Window resizes are collected as they arrive and applied once per frame,
+/// so dragging the window edge recreates the swap chain at most once a frame.
+#[derive(Debug, Clone)]
+pub struct SizePolicy {
+    max: u32,
+    // `None` while paused
+    current: Option<ScreenSize>,
+    pending: Option<ScreenSize>,
+}
+
+impl SizePolicy {
+    pub fn new(size: ScreenSize, max: u32) -> Self {
+        Self {
+            max,
+            current: effective_size(size, max),
+            pending: None,
+        }
+    }
+
+    /// The size rendered at, or `None` while paused.
+    pub fn current(&self) -> Option<ScreenSize> {
+        self.current
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.current.is_none()
+    }
+
+    /// The window was resized. Replaces any resize that wasn't applied yet.
+    pub fn request(&mut self, window_size: ScreenSize) {
+        self.pending = Some(window_size);
+    }
+
+    /// Apply the latest requested size, returning what changed, if anything.
+    pub fn apply(&mut self) -> Option<SizeChange> {
+        let size = effective_size(self.pending.take()?, self.max);
+        if size == self.current {
+            return None;
+        }
+
+        self.current = size;
+        match size {
+            Some(size) => Some(SizeChange::Resized(size)),
+            None => Some(SizeChange::Paused),
+        }
+    }
+}
+
+impl Default for SizePolicy {
+    fn default() -> Self {
+        Self::new(ScreenSize::zero(), MAX_TEXTURE_DIMENSION)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clamps_and_pauses() {
+        assert_eq!(effective_size(ScreenSize::new(0, 10), 64), None);
+        assert_eq!(
+            effective_size(ScreenSize::new(100, 10), 64),
+            Some(ScreenSize::new(64, 10))
+        );
+    }
+
+    #[test]
+    fn resizes_are_coalesced() {
+        let mut policy = SizePolicy::new(ScreenSize::new(10, 10), 64);
+        assert_eq!(policy.apply(), None);
+
+        policy.request(ScreenSize::new(20, 20));
+        policy.request(ScreenSize::new(30, 30));
+        assert_eq!(
+            policy.apply(),
+            Some(SizeChange::Resized(ScreenSize::new(30, 30)))
+        );
+        assert_eq!(policy.apply(), None);
+
+        // Back and forth within a frame is no change at all
+        policy.request(ScreenSize::new(40, 40));
+        policy.request(ScreenSize::new(30, 30));
+        assert_eq!(policy.apply(), None);
+    }
+
+    #[test]
+    fn minimise_and_restore() {
+        let mut policy = SizePolicy::new(ScreenSize::new(10, 10), 64);
+
+        policy.request(ScreenSize::zero());
+        assert_eq!(policy.apply(), Some(SizeChange::Paused));
+        assert!(policy.is_paused());
+
+        policy.request(ScreenSize::new(100, 20));
+        assert_eq!(
+            policy.apply(),
+            Some(SizeChange::Resized(ScreenSize::new(64, 20)))
+        );
+        assert_eq!(policy.current(), Some(ScreenSize::new(64, 20)));
+
+        // Sizes that clamp to the current size change nothing
+        policy.request(ScreenSize::new(200, 20));
+        assert_eq!(policy.apply(), None);
+    }
+}
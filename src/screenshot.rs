@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use crate::{Image, Pixel, ScreenSize};
+
+/// Bytes per row of a texture copied into a buffer,
+/// padded to the alignment wgpu requires.
+pub(crate) fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * std::mem::size_of::<Pixel>() as u32;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Convert padded rows of BGRA bytes, as read back from the swap chain format,
+/// into an image.
+pub(crate) fn from_bgra(bytes: &[u8], size: ScreenSize, bytes_per_row: u32) -> Image {
+    let row_len = (size.width * 4) as usize;
+    let pixels = bytes
+        .chunks(bytes_per_row as usize)
+        .take(size.height as usize)
+        .flat_map(|row| row[..row_len].chunks_exact(4))
+        .map(|bgra| Pixel::new(bgra[2], bgra[1], bgra[0], bgra[3]))
+        .collect();
+
+    Image::from_pixels(size, pixels)
+}
+
+impl Image {
+    /// Save the image as an 8 bit RGBA PNG.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width(), self.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(into_io)?;
+        writer.write_image_data(self.as_bytes()).map_err(into_io)
+    }
+}
+
+fn into_io(error: png::EncodingError) -> io::Error {
+    match error {
+        png::EncodingError::IoError(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+/// A file name for a new screenshot, based on the current time.
+pub(crate) fn file_name() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("screenshot-{}.png", millis)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rows_are_aligned() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+    }
+
+    #[test]
+    fn bgra_to_rgba() {
+        let size = ScreenSize::new(2, 2);
+        let bytes_per_row = padded_bytes_per_row(size.width);
+        let mut bytes = vec![0xff; (bytes_per_row * size.height) as usize];
+        bytes[..8].copy_from_slice(&[3, 2, 1, 255, 6, 5, 4, 255]);
+        let second = bytes_per_row as usize;
+        bytes[second..second + 8].copy_from_slice(&[9, 8, 7, 255, 0, 0, 0, 0]);
+
+        let image = from_bgra(&bytes, size, bytes_per_row);
+        assert_eq!(image.get(0, 0), Some(Pixel::rgb(1, 2, 3)));
+        assert_eq!(image.get(1, 0), Some(Pixel::rgb(4, 5, 6)));
+        assert_eq!(image.get(0, 1), Some(Pixel::rgb(7, 8, 9)));
+        assert_eq!(image.get(1, 1), Some(Pixel::zero()));
+    }
+
+    #[test]
+    fn saves_png() {
+        let path =
+            std::env::temp_dir().join(format!("tinypixel-screenshot-{}.png", std::process::id()));
+        let image = Image::new(ScreenSize::new(3, 2), Pixel::rgb(10, 20, 30));
+        image.save_png(&path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&buf[..info.buffer_size()], image.as_bytes());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
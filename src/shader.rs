@@ -0,0 +1,255 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use naga::back::spv;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+/// The entry point every shader stage uses.
+const ENTRY_POINT: &str = "main";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderLanguage {
+    Glsl,
+    Wgsl,
+}
+
+impl ShaderLanguage {
+    /// Guess the language from a file extension:
+    /// `.frag` and `.glsl` are GLSL, `.wgsl` is WGSL.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "frag" | "glsl" => Some(ShaderLanguage::Glsl),
+            "wgsl" => Some(ShaderLanguage::Wgsl),
+            _ => None,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Error -
+// -----------------------------------------------------------------------------
+/// A shader that failed to load, parse or validate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderError {
+    pub message: String,
+    /// Line in the source, starting at 1.
+    pub line: Option<u32>,
+    /// Column in the source, starting at 1.
+    pub column: Option<u32>,
+}
+
+impl ShaderError {
+    fn new(message: impl Into<String>, location: Option<naga::SourceLocation>) -> Self {
+        Self {
+            message: message.into(),
+            line: location.map(|l| l.line_number),
+            column: location.map(|l| l.line_position),
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            (Some(line), None) => write!(f, "{}: {}", line, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+// -----------------------------------------------------------------------------
+//     - Compile -
+// -----------------------------------------------------------------------------
+/// Compile and validate a fragment shader, returning SPIR-V.
+/// The entry point has to be called `main`.
+pub fn compile_fragment(source: &str, language: ShaderLanguage) -> Result<Vec<u32>, ShaderError> {
+    compile(source, language, naga::ShaderStage::Fragment)
+}
+
+/// Compile and validate a vertex shader, returning SPIR-V.
+/// The entry point has to be called `main`.
+pub fn compile_vertex(source: &str, language: ShaderLanguage) -> Result<Vec<u32>, ShaderError> {
+    compile(source, language, naga::ShaderStage::Vertex)
+}
+
+fn compile(
+    source: &str,
+    language: ShaderLanguage,
+    stage: naga::ShaderStage,
+) -> Result<Vec<u32>, ShaderError> {
+    let module = match language {
+        ShaderLanguage::Glsl => naga::front::glsl::Frontend::default()
+            .parse(&naga::front::glsl::Options::from(stage), source)
+            .map_err(|e| match e.errors.first() {
+                Some(first) => ShaderError::new(first.kind.to_string(), first.location(source)),
+                None => ShaderError::new("failed to parse GLSL", None),
+            })?,
+        ShaderLanguage::Wgsl => naga::front::wgsl::parse_str(source)
+            .map_err(|e| ShaderError::new(e.message(), e.location(source)))?,
+    };
+
+    if !module
+        .entry_points
+        .iter()
+        .any(|ep| ep.name == ENTRY_POINT && ep.stage == stage)
+    {
+        return Err(ShaderError::new(
+            format!("no {:?} entry point called `{}`", stage, ENTRY_POINT),
+            None,
+        ));
+    }
+
+    let info = Validator::new(ValidationFlags::all(), Capabilities::empty())
+        .validate(&module)
+        .map_err(|e| ShaderError::new(e.as_inner().to_string(), e.location(source)))?;
+
+    // SPIR-V 1.0 without debug info, as the wgpu version in use expects
+    let mut options = spv::Options {
+        lang_version: (1, 0),
+        ..Default::default()
+    };
+    options.flags.remove(spv::WriterFlags::DEBUG);
+
+    spv::write_vec(&module, &info, &options, None)
+        .map_err(|e| ShaderError::new(format!("failed to write SPIR-V: {}", e), None))
+}
+
+/// Read and compile a fragment shader, picking the language from the extension.
+pub(crate) fn load_fragment(path: &Path) -> Result<Vec<u32>, ShaderError> {
+    let language = ShaderLanguage::from_path(path).ok_or_else(|| {
+        ShaderError::new(format!("unknown shader language: {}", path.display()), None)
+    })?;
+
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| ShaderError::new(format!("{}: {}", path.display(), e), None))?;
+
+    compile_fragment(&source, language)
+}
+
+// -----------------------------------------------------------------------------
+//     - Watcher -
+// -----------------------------------------------------------------------------
+/// Notices when a shader file has been changed on disk.
+#[derive(Debug, Clone)]
+pub struct ShaderWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ShaderWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True once for every change to the file's modification time.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified != self.modified {
+            self.modified = modified;
+            modified.is_some()
+        } else {
+            false
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    const WGSL: &str = "
+@group(0) @binding(0) var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1) var s_diffuse: sampler;
+
+@fragment
+fn main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, uv);
+}
+";
+
+    #[test]
+    fn compile_glsl() {
+        let spirv = compile_fragment(include_str!("post_vignette.frag"), ShaderLanguage::Glsl);
+        assert_eq!(spirv.unwrap()[0], 0x0723_0203);
+    }
+
+    #[test]
+    fn compile_main_pipeline() {
+        let vertex = compile_vertex(include_str!("shader.vert"), ShaderLanguage::Glsl);
+        let fragment = compile_fragment(include_str!("shader.frag"), ShaderLanguage::Glsl);
+        assert_eq!(vertex.unwrap()[0], 0x0723_0203);
+        assert_eq!(fragment.unwrap()[0], 0x0723_0203);
+    }
+
+    #[test]
+    fn compile_wgsl() {
+        let spirv = compile_fragment(WGSL, ShaderLanguage::Wgsl);
+        assert_eq!(spirv.unwrap()[0], 0x0723_0203);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let glsl = "#version 450\nlayout(location=0) out vec4 f_color;\n\nvoid main() {\n    f_color = oops;\n}\n";
+        let error = compile_fragment(glsl, ShaderLanguage::Glsl).unwrap_err();
+        assert_eq!(error.line, Some(5));
+
+        let wgsl = WGSL.replace("return textureSample", "return textureSampel");
+        let error = compile_fragment(&wgsl, ShaderLanguage::Wgsl).unwrap_err();
+        assert_eq!(error.line, Some(7));
+        assert!(error.to_string().starts_with("7:"));
+    }
+
+    #[test]
+    fn missing_entry_point() {
+        let error = compile_vertex(WGSL, ShaderLanguage::Wgsl).unwrap_err();
+        assert_eq!(error.line, None);
+        assert!(error.message.contains("Vertex"));
+    }
+
+    #[test]
+    fn language_from_extension() {
+        assert_eq!(
+            ShaderLanguage::from_path("crt.frag"),
+            Some(ShaderLanguage::Glsl)
+        );
+        assert_eq!(
+            ShaderLanguage::from_path("crt.wgsl"),
+            Some(ShaderLanguage::Wgsl)
+        );
+        assert_eq!(ShaderLanguage::from_path("crt.spv"), None);
+    }
+
+    #[test]
+    fn watcher_sees_changes() {
+        let path =
+            std::env::temp_dir().join(format!("tinypixel-watch-{}.wgsl", std::process::id()));
+        std::fs::write(&path, WGSL).unwrap();
+
+        let mut watcher = ShaderWatcher::new(&path);
+        assert!(!watcher.changed());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!watcher.changed());
+    }
+}
@@ -0,0 +1,338 @@
+use std::ops::Range;
+
+use crate::{KeyEvent, VirtualKeyCode};
+
+// -----------------------------------------------------------------------------
+//     - Clipboard -
+// -----------------------------------------------------------------------------
+/// Where cut and copied text goes. Implement this to use the system clipboard.
+pub trait Clipboard {
+    fn get(&mut self) -> Option<String>;
+    fn set(&mut self, text: String);
+}
+
+/// A clipboard that only lives as long as the application.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard(Option<String>);
+
+impl Clipboard for MemoryClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.0.clone()
+    }
+
+    fn set(&mut self, text: String) {
+        self.0 = Some(text);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Line editor -
+// -----------------------------------------------------------------------------
+/// A single line of editable text with a cursor and selection.
+///
+/// Feed it `Event::Char` with [`LineEditor::insert_char`] and key presses
+/// with [`LineEditor::handle_key`]. Positions are counted in characters.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+    // The other end of the selection, if there is one
+    anchor: Option<usize>,
+}
+
+impl LineEditor {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = text.chars().count();
+        Self {
+            text,
+            cursor,
+            anchor: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, moving the cursor to the end.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        *self = Self::new(text);
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The selected characters, if any.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        match self.anchor {
+            Some(anchor) if anchor != self.cursor => {
+                Some(anchor.min(self.cursor)..anchor.max(self.cursor))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection()
+            .map(|range| &self.text[self.byte_range(range)])
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Move the cursor. With `select` the selection is extended,
+    /// otherwise it is removed.
+    pub fn set_cursor(&mut self, cursor: usize, select: bool) {
+        match (select, self.anchor) {
+            (true, None) => self.anchor = Some(self.cursor),
+            (true, Some(_)) => {}
+            (false, _) => self.anchor = None,
+        }
+        self.cursor = cursor.min(self.len());
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        match (select, self.selection()) {
+            (false, Some(range)) => self.set_cursor(range.start, false),
+            _ => self.set_cursor(self.cursor.saturating_sub(1), select),
+        }
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        match (select, self.selection()) {
+            (false, Some(range)) => self.set_cursor(range.end, false),
+            _ => self.set_cursor(self.cursor + 1, select),
+        }
+    }
+
+    pub fn home(&mut self, select: bool) {
+        self.set_cursor(0, select);
+    }
+
+    pub fn end(&mut self, select: bool) {
+        self.set_cursor(self.len(), select);
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.len();
+    }
+
+    /// Insert a character at the cursor, replacing the selection.
+    /// Control characters are ignored.
+    pub fn insert_char(&mut self, c: char) -> bool {
+        if c.is_control() {
+            return false;
+        }
+        self.insert_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Insert text at the cursor, replacing the selection.
+    /// Anything from the first line break on is ignored.
+    pub fn insert_str(&mut self, text: &str) -> bool {
+        let text = text.lines().next().unwrap_or("");
+        let deleted = self.delete_selection();
+        if text.is_empty() {
+            return deleted;
+        }
+
+        let at = self.byte_index(self.cursor);
+        self.text.insert_str(at, text);
+        self.cursor += text.chars().count();
+        true
+    }
+
+    /// Delete the selection or the character before the cursor.
+    pub fn backspace(&mut self) -> bool {
+        if self.delete_selection() {
+            return true;
+        }
+        match self.cursor {
+            0 => false,
+            cursor => {
+                self.delete(cursor - 1..cursor);
+                true
+            }
+        }
+    }
+
+    /// Delete the selection or the character after the cursor.
+    pub fn delete_forward(&mut self) -> bool {
+        if self.delete_selection() {
+            return true;
+        }
+        match self.cursor < self.len() {
+            true => {
+                self.delete(self.cursor..self.cursor + 1);
+                true
+            }
+            false => false,
+        }
+    }
+
+    pub fn copy(&self, clipboard: &mut dyn Clipboard) {
+        if let Some(text) = self.selected_text() {
+            clipboard.set(text.to_string());
+        }
+    }
+
+    pub fn cut(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        self.copy(clipboard);
+        self.delete_selection()
+    }
+
+    pub fn paste(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        match clipboard.get() {
+            Some(text) => self.insert_str(&text),
+            None => false,
+        }
+    }
+
+    /// Handle the editing keys: arrows, home and end (hold shift to select),
+    /// backspace, delete, and ctrl + a, c, x and v.
+    /// Returns true if the text changed.
+    pub fn handle_key(&mut self, event: KeyEvent, clipboard: &mut dyn Clipboard) -> bool {
+        let key = match (event.pressed(), event.key) {
+            (true, Some(key)) => key,
+            _ => return false,
+        };
+        let select = event.modifiers.shift();
+
+        match key {
+            VirtualKeyCode::Back => return self.backspace(),
+            VirtualKeyCode::Delete => return self.delete_forward(),
+            VirtualKeyCode::Left => self.move_left(select),
+            VirtualKeyCode::Right => self.move_right(select),
+            VirtualKeyCode::Home => self.home(select),
+            VirtualKeyCode::End => self.end(select),
+            VirtualKeyCode::A if event.modifiers.ctrl() => self.select_all(),
+            VirtualKeyCode::C if event.modifiers.ctrl() => self.copy(clipboard),
+            VirtualKeyCode::X if event.modifiers.ctrl() => return self.cut(clipboard),
+            VirtualKeyCode::V if event.modifiers.ctrl() => return self.paste(clipboard),
+            _ => {}
+        }
+        false
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(range) => {
+                self.delete(range);
+                true
+            }
+            None => {
+                self.anchor = None;
+                false
+            }
+        }
+    }
+
+    fn delete(&mut self, range: Range<usize>) {
+        let bytes = self.byte_range(range.clone());
+        self.text.replace_range(bytes, "");
+        self.cursor = range.start;
+        self.anchor = None;
+    }
+
+    fn byte_index(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+
+    fn byte_range(&self, range: Range<usize>) -> Range<usize> {
+        self.byte_index(range.start)..self.byte_index(range.end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ElementState, ModifiersState};
+
+    fn key(key: VirtualKeyCode, modifiers: ModifiersState) -> KeyEvent {
+        KeyEvent {
+            scancode: 0,
+            state: ElementState::Pressed,
+            key: Some(key),
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn typing_and_deleting() {
+        let mut editor = LineEditor::new("héllo");
+        assert_eq!(editor.cursor(), 5);
+
+        editor.move_left(false);
+        editor.move_left(false);
+        assert!(editor.insert_char('!'));
+        assert!(!editor.insert_char('\u{8}'));
+        assert_eq!(editor.text(), "hél!lo");
+
+        editor.home(false);
+        assert!(!editor.backspace());
+        assert!(editor.delete_forward());
+        editor.move_right(false);
+        assert!(editor.backspace());
+        assert_eq!(editor.text(), "l!lo");
+        assert_eq!(editor.cursor(), 0);
+    }
+
+    #[test]
+    fn selections() {
+        let mut editor = LineEditor::new("one two");
+        editor.move_left(true);
+        editor.move_left(true);
+        editor.move_left(true);
+        assert_eq!(editor.selection(), Some(4..7));
+        assert_eq!(editor.selected_text(), Some("two"));
+
+        // Moving without shift collapses the selection to one end
+        editor.move_left(false);
+        assert_eq!(editor.selection(), None);
+        assert_eq!(editor.cursor(), 4);
+
+        editor.select_all();
+        editor.insert_str("three\nfour");
+        assert_eq!(editor.text(), "three");
+    }
+
+    #[test]
+    fn keys_and_clipboard() {
+        let mut clipboard = MemoryClipboard::default();
+        let mut editor = LineEditor::new("copy me");
+        let ctrl = ModifiersState::CTRL;
+        let shift = ModifiersState::SHIFT;
+
+        editor.handle_key(key(VirtualKeyCode::Home, shift), &mut clipboard);
+        assert_eq!(editor.selection(), Some(0..7));
+        assert!(!editor.handle_key(key(VirtualKeyCode::C, ctrl), &mut clipboard));
+
+        editor.handle_key(
+            key(VirtualKeyCode::End, ModifiersState::empty()),
+            &mut clipboard,
+        );
+        assert!(editor.handle_key(key(VirtualKeyCode::V, ctrl), &mut clipboard));
+        assert_eq!(editor.text(), "copy mecopy me");
+
+        editor.handle_key(key(VirtualKeyCode::A, ctrl), &mut clipboard);
+        assert!(editor.handle_key(key(VirtualKeyCode::X, ctrl), &mut clipboard));
+        assert!(editor.is_empty());
+        assert_eq!(clipboard.get().as_deref(), Some("copy mecopy me"));
+
+        // Released keys do nothing
+        let mut release = key(VirtualKeyCode::Back, ModifiersState::empty());
+        release.state = ElementState::Released;
+        assert!(!editor.handle_key(release, &mut clipboard));
+    }
+}
@@ -41,7 +41,7 @@ impl Texture {
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
             },
-            &pixels,
+            pixels,
             wgpu::TextureDataLayout {
                 offset: 0,
                 bytes_per_row: size_of::<Pixel>() as u32 * size.width,
@@ -52,18 +52,46 @@ impl Texture {
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default()); 
 
-        let sampler = device.create_sampler(
-            &wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter: wgpu::FilterMode::Nearest,
-                min_filter: wgpu::FilterMode::Nearest,
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                ..Default::default()
-            }
-        );
+        let sampler = sampler(device);
+
+        Self { inner: texture, view, sampler, size }
+    }
+
+    /// A texture that can be rendered to and then sampled by the next pass.
+    pub fn render_target(device: &wgpu::Device, size: crate::ScreenSize, format: wgpu::TextureFormat) -> Texture {
+        let size = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = sampler(device);
 
         Self { inner: texture, view, sampler, size }
     }
 }
+
+fn sampler(device: &wgpu::Device) -> wgpu::Sampler {
+    device.create_sampler(
+        &wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }
+    )
+}
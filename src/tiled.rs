@@ -0,0 +1,1273 @@
+//! Import maps made with the [Tiled](https://www.mapeditor.org) map editor.
+//!
+//! Both TMX (XML) and JSON maps are supported, with tile layer data stored as
+//! CSV, XML or base64 (optionally zlib or gzip compressed).
+//! Only orthogonal, finite maps can be loaded.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), tinypixel::tiled::TiledError> {
+//! # let tileset_image = tinypixel::Image::empty(tinypixel::ScreenSize::new(64, 64));
+//! let map = tinypixel::tiled::Map::load("level1.tmx")?;
+//! let tilemap = map.to_tilemap(tileset_image)?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::Engine;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use roxmltree::Node;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Image, Pixel, ScreenSize, Tile, TileAnimation, Tilemap, Vec2D};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+const FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+// Bounds the memory a layer can take, 4096 x 4096 tiles is 64MB of gids
+const MAX_TILES: u32 = 4096 * 4096;
+
+// -----------------------------------------------------------------------------
+//     - Error -
+// -----------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum TiledError {
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    /// The map uses a feature that can not be loaded.
+    Unsupported(String),
+    /// The map is malformed.
+    Invalid(String),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Io(e) => write!(f, "failed to read map: {}", e),
+            TiledError::Xml(e) => write!(f, "invalid TMX: {}", e),
+            TiledError::Json(e) => write!(f, "invalid JSON map: {}", e),
+            TiledError::Unsupported(feature) => write!(f, "unsupported Tiled feature: {}", feature),
+            TiledError::Invalid(reason) => write!(f, "invalid map: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TiledError::Io(e) => Some(e),
+            TiledError::Xml(e) => Some(e),
+            TiledError::Json(e) => Some(e),
+            TiledError::Unsupported(_) | TiledError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TiledError {
+    fn from(e: std::io::Error) -> Self {
+        TiledError::Io(e)
+    }
+}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(e: roxmltree::Error) -> Self {
+        TiledError::Xml(e)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(e: serde_json::Error) -> Self {
+        TiledError::Json(e)
+    }
+}
+
+fn unsupported<T>(feature: impl Into<String>) -> Result<T, TiledError> {
+    Err(TiledError::Unsupported(feature.into()))
+}
+
+fn invalid<T>(reason: impl Into<String>) -> Result<T, TiledError> {
+    Err(TiledError::Invalid(reason.into()))
+}
+
+// -----------------------------------------------------------------------------
+//     - Properties -
+// -----------------------------------------------------------------------------
+/// A custom property set in the editor.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Colour(Pixel),
+    File(String),
+    /// The id of an object.
+    Object(u32),
+}
+
+pub type Properties = HashMap<String, Property>;
+
+fn property(kind: &str, value: &str) -> Result<Property, TiledError> {
+    let number_error = || TiledError::Invalid(format!("invalid {} property: {:?}", kind, value));
+
+    let property = match kind {
+        "" | "string" => Property::String(value.to_string()),
+        "int" => Property::Int(value.parse().map_err(|_| number_error())?),
+        "float" => Property::Float(value.parse().map_err(|_| number_error())?),
+        "bool" => Property::Bool(value == "true"),
+        "color" => Property::Colour(colour(value)?),
+        "file" => Property::File(value.to_string()),
+        "object" => Property::Object(value.parse().map_err(|_| number_error())?),
+        other => return unsupported(format!("{} properties", other)),
+    };
+
+    Ok(property)
+}
+
+// Tiled stores colours as #AARRGGBB or #RRGGBB
+fn colour(value: &str) -> Result<Pixel, TiledError> {
+    if value.is_empty() {
+        return Ok(Pixel::zero());
+    }
+
+    let pixel = Pixel::from_hex(value)
+        .map_err(|e| TiledError::Invalid(format!("invalid colour {:?}: {}", value, e)))?;
+
+    match value.trim_start_matches('#').len() {
+        8 => Ok(Pixel::new(pixel.g, pixel.b, pixel.a, pixel.r)),
+        _ => Ok(pixel),
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Gid -
+// -----------------------------------------------------------------------------
+/// A global tile id, as stored in tile layers.
+/// The upper bits hold flip flags, and zero means there is no tile.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Gid(pub u32);
+
+impl Gid {
+    /// The id without any flags.
+    pub fn id(&self) -> u32 {
+        self.0 & !FLAGS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.id() == 0
+    }
+
+    pub fn flip_x(&self) -> bool {
+        self.0 & FLIPPED_HORIZONTALLY != 0
+    }
+
+    pub fn flip_y(&self) -> bool {
+        self.0 & FLIPPED_VERTICALLY != 0
+    }
+
+    pub fn flip_diagonal(&self) -> bool {
+        self.0 & FLIPPED_DIAGONALLY != 0
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Map -
+// -----------------------------------------------------------------------------
+/// A Tiled map.
+#[derive(Debug, Clone)]
+pub struct Map {
+    /// Size of the map in tiles.
+    pub size: ScreenSize,
+    pub tile_size: ScreenSize,
+    pub tilesets: Vec<Tileset>,
+    /// Layers in drawing order, bottom first.
+    pub layers: Vec<Layer>,
+    pub properties: Properties,
+}
+
+/// A tileset used by a map.
+#[derive(Debug, Clone, Default)]
+pub struct Tileset {
+    /// The gid of the first tile in the tileset.
+    pub first_gid: u32,
+    /// Path of an external tileset, relative to the map.
+    /// External tilesets are only resolved by [`Map::load`].
+    pub source: Option<String>,
+    pub name: String,
+    pub tile_size: ScreenSize,
+    pub tile_count: u32,
+    pub columns: u32,
+    /// Path of the tileset image, relative to the tileset.
+    pub image: Option<String>,
+    /// Animations by local tile id: a list of local tile ids and durations.
+    pub animations: HashMap<u32, Vec<(u32, Duration)>>,
+    /// Custom properties by local tile id.
+    pub tile_properties: HashMap<u32, Properties>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Layer {
+    Tiles(TileLayer),
+    Objects(ObjectLayer),
+}
+
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    pub name: String,
+    /// Size of the layer in tiles.
+    pub size: ScreenSize,
+    /// Offset in pixels.
+    pub offset: Vec2D<i32>,
+    pub visible: bool,
+    /// One gid per tile, row by row.
+    pub gids: Vec<Gid>,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectLayer {
+    pub name: String,
+    /// Offset in pixels.
+    pub offset: Vec2D<i32>,
+    pub visible: bool,
+    pub objects: Vec<Object>,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Object {
+    pub id: u32,
+    pub name: String,
+    /// The object type (called class in newer versions of Tiled).
+    pub kind: String,
+    /// Position and size in pixels.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Rotation in degrees, clockwise.
+    pub rotation: f32,
+    /// Set if the object is a tile object.
+    pub gid: Option<Gid>,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+impl Map {
+    /// Load a `.tmx` or `.json`/`.tmj` map from disk, along with any
+    /// external tilesets it refers to.
+    pub fn load(path: impl AsRef<Path>) -> Result<Map, TiledError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+
+        let mut map = match extension(path).as_str() {
+            "tmx" => Map::from_tmx(&text)?,
+            "json" | "tmj" => Map::from_json(&text)?,
+            other => return unsupported(format!("map files with extension {:?}", other)),
+        };
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for tileset in map.tilesets.iter_mut() {
+            let source = match &tileset.source {
+                Some(source) => dir.join(source),
+                None => continue,
+            };
+
+            let text = std::fs::read_to_string(&source)?;
+            let mut external = match extension(&source).as_str() {
+                "tsx" => Tileset::from_tsx(&text)?,
+                "json" | "tsj" => Tileset::from_json(&text)?,
+                other => return unsupported(format!("tileset files with extension {:?}", other)),
+            };
+
+            external.first_gid = tileset.first_gid;
+            external.source = tileset.source.take();
+            *tileset = external;
+        }
+
+        Ok(map)
+    }
+
+    /// Parse a TMX map.
+    pub fn from_tmx(xml: &str) -> Result<Map, TiledError> {
+        let doc = roxmltree::Document::parse(xml)?;
+        tmx::map(doc.root_element())
+    }
+
+    /// Parse a JSON map.
+    pub fn from_json(json: &str) -> Result<Map, TiledError> {
+        let map: json::Map = serde_json::from_str(json)?;
+        map.convert()
+    }
+
+    /// Find the tileset a gid belongs to.
+    pub fn tileset_for(&self, gid: Gid) -> Option<&Tileset> {
+        self.tilesets
+            .iter()
+            .filter(|tileset| tileset.first_gid <= gid.id())
+            .max_by_key(|tileset| tileset.first_gid)
+    }
+
+    /// Build a [`Tilemap`] from the tile layers of the map.
+    /// The map must use a single tileset, whose image is passed in.
+    /// Object layers are skipped and can be read from [`Map::layers`].
+    pub fn to_tilemap(&self, tileset_image: Image) -> Result<Tilemap, TiledError> {
+        let tileset = match self.tilesets.as_slice() {
+            [tileset] => tileset,
+            [] => return invalid("the map has no tileset"),
+            _ => return unsupported("maps with more than one tileset"),
+        };
+
+        if tileset.tile_size != self.tile_size {
+            return unsupported("tilesets with a different tile size than the map");
+        }
+
+        let mut tilemap = Tilemap::new(crate::Tileset::new(tileset_image, tileset.tile_size));
+
+        for (id, frames) in &tileset.animations {
+            tilemap.set_animation(*id, TileAnimation::new(frames.clone()));
+        }
+
+        for layer in &self.layers {
+            let layer = match layer {
+                Layer::Tiles(layer) => layer,
+                Layer::Objects(_) => continue,
+            };
+
+            let mut tiles = crate::TileLayer::new(layer.size);
+            tiles.offset = layer.offset;
+            tiles.visible = layer.visible;
+
+            for (i, gid) in layer.gids.iter().enumerate() {
+                if gid.is_empty() {
+                    continue;
+                }
+
+                let index = match gid.id().checked_sub(tileset.first_gid) {
+                    Some(index) => index,
+                    None => return invalid("tile gid is below the tileset's firstgid"),
+                };
+
+                let tile = Tile {
+                    index,
+                    flip_x: gid.flip_x(),
+                    flip_y: gid.flip_y(),
+                    flip_diagonal: gid.flip_diagonal(),
+                };
+
+                let i = i as u32;
+                tiles.set(i % layer.size.width, i / layer.size.width, Some(tile));
+            }
+
+            tilemap.add_layer(tiles);
+        }
+
+        Ok(tilemap)
+    }
+}
+
+impl Tileset {
+    /// Parse a TSX tileset.
+    pub fn from_tsx(xml: &str) -> Result<Tileset, TiledError> {
+        let doc = roxmltree::Document::parse(xml)?;
+        tmx::tileset(doc.root_element())
+    }
+
+    /// Parse a JSON tileset.
+    pub fn from_json(json: &str) -> Result<Tileset, TiledError> {
+        let tileset: json::Tileset = serde_json::from_str(json)?;
+        tileset.convert()
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn check_map(orientation: &str, infinite: bool) -> Result<(), TiledError> {
+    if orientation != "orthogonal" {
+        return unsupported(format!("{} maps", orientation));
+    }
+
+    if infinite {
+        return unsupported("infinite maps");
+    }
+
+    Ok(())
+}
+
+// Decode base64 tile data, with optional compression
+fn decode(data: &str, compression: Option<&str>, count: usize) -> Result<Vec<Gid>, TiledError> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| TiledError::Invalid(format!("invalid base64 tile data: {}", e)))?;
+
+    let bytes = match compression {
+        None | Some("") => bytes,
+        Some("zlib") => inflate(ZlibDecoder::new(bytes.as_slice()), count)?,
+        Some("gzip") => inflate(GzDecoder::new(bytes.as_slice()), count)?,
+        Some(other) => return unsupported(format!("{} compressed tile data", other)),
+    };
+
+    let gids = bytes
+        .chunks_exact(4)
+        .map(|b| Gid(u32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+        .collect();
+
+    check_count(gids, count)
+}
+
+// Decompress no more than the layer can hold, so a small file can't inflate without end
+fn inflate(decoder: impl Read, count: usize) -> Result<Vec<u8>, TiledError> {
+    let len = count * 4;
+    let mut out = Vec::new();
+    decoder.take(len as u64 + 1).read_to_end(&mut out)?;
+    if out.len() > len {
+        return invalid(format!("more than {} tiles in layer", count));
+    }
+    Ok(out)
+}
+
+// The number of tiles in a layer of `size`
+fn tile_count(size: ScreenSize) -> Result<usize, TiledError> {
+    match size.width.checked_mul(size.height) {
+        Some(count) if count <= MAX_TILES => Ok(count as usize),
+        _ => invalid(format!(
+            "layer of {}x{} tiles is larger than {} tiles",
+            size.width, size.height, MAX_TILES
+        )),
+    }
+}
+
+fn check_count(gids: Vec<Gid>, count: usize) -> Result<Vec<Gid>, TiledError> {
+    if gids.len() != count {
+        return invalid(format!(
+            "expected {} tiles in layer, found {}",
+            count,
+            gids.len()
+        ));
+    }
+    Ok(gids)
+}
+
+// -----------------------------------------------------------------------------
+//     - TMX -
+// -----------------------------------------------------------------------------
+mod tmx {
+    use super::*;
+
+    fn attr<T: FromStr>(node: Node, name: &str) -> Result<T, TiledError> {
+        match node.attribute(name) {
+            Some(value) => value.parse().map_err(|_| {
+                TiledError::Invalid(format!(
+                    "invalid value {:?} for attribute {:?} on <{}>",
+                    value,
+                    name,
+                    node.tag_name().name()
+                ))
+            }),
+            None => invalid(format!(
+                "missing attribute {:?} on <{}>",
+                name,
+                node.tag_name().name()
+            )),
+        }
+    }
+
+    fn attr_or<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, TiledError> {
+        match node.attribute(name) {
+            Some(_) => attr(node, name),
+            None => Ok(default),
+        }
+    }
+
+    fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+        node.children().filter(|n| n.is_element())
+    }
+
+    fn offset(node: Node) -> Result<Vec2D<i32>, TiledError> {
+        let x: f32 = attr_or(node, "offsetx", 0.0)?;
+        let y: f32 = attr_or(node, "offsety", 0.0)?;
+        Ok(Vec2D::new(x.round() as i32, y.round() as i32))
+    }
+
+    pub(super) fn map(node: Node) -> Result<Map, TiledError> {
+        if node.tag_name().name() != "map" {
+            return invalid("root element is not <map>");
+        }
+
+        let orientation = node.attribute("orientation").unwrap_or("orthogonal");
+        check_map(orientation, node.attribute("infinite") == Some("1"))?;
+
+        let mut map = Map {
+            size: ScreenSize::new(attr(node, "width")?, attr(node, "height")?),
+            tile_size: ScreenSize::new(attr(node, "tilewidth")?, attr(node, "tileheight")?),
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            properties: Properties::new(),
+        };
+
+        for child in elements(node) {
+            match child.tag_name().name() {
+                "properties" => map.properties = properties(child)?,
+                "tileset" => {
+                    let mut tileset = match child.attribute("source") {
+                        Some(source) => Tileset {
+                            source: Some(source.to_string()),
+                            ..Default::default()
+                        },
+                        None => tileset(child)?,
+                    };
+                    tileset.first_gid = attr(child, "firstgid")?;
+                    map.tilesets.push(tileset);
+                }
+                "layer" => map.layers.push(Layer::Tiles(tile_layer(child)?)),
+                "objectgroup" => map.layers.push(Layer::Objects(object_layer(child)?)),
+                "imagelayer" => return unsupported("image layers"),
+                "group" => return unsupported("group layers"),
+                _ => {}
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub(super) fn tileset(node: Node) -> Result<Tileset, TiledError> {
+        if node.tag_name().name() != "tileset" {
+            return invalid("expected a <tileset>");
+        }
+
+        let mut tileset = Tileset {
+            first_gid: attr_or(node, "firstgid", 1)?,
+            source: None,
+            name: attr_or(node, "name", String::new())?,
+            tile_size: ScreenSize::new(attr(node, "tilewidth")?, attr(node, "tileheight")?),
+            tile_count: attr_or(node, "tilecount", 0)?,
+            columns: attr_or(node, "columns", 0)?,
+            ..Default::default()
+        };
+
+        for child in elements(node) {
+            match child.tag_name().name() {
+                "image" => tileset.image = Some(attr(child, "source")?),
+                "tile" => {
+                    let id = attr(child, "id")?;
+                    for tile_child in elements(child) {
+                        match tile_child.tag_name().name() {
+                            "properties" => {
+                                tileset.tile_properties.insert(id, properties(tile_child)?);
+                            }
+                            "animation" => {
+                                let frames = elements(tile_child)
+                                    .map(|frame| {
+                                        let tile = attr(frame, "tileid")?;
+                                        let duration = attr(frame, "duration")?;
+                                        Ok((tile, Duration::from_millis(duration)))
+                                    })
+                                    .collect::<Result<_, TiledError>>()?;
+                                tileset.animations.insert(id, frames);
+                            }
+                            "image" => return unsupported("image collection tilesets"),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(tileset)
+    }
+
+    fn properties(node: Node) -> Result<Properties, TiledError> {
+        elements(node)
+            .filter(|n| n.tag_name().name() == "property")
+            .map(|n| {
+                let name: String = attr(n, "name")?;
+                let kind = n.attribute("type").unwrap_or("string");
+                // Multi line strings are stored as text instead of an attribute
+                let value = n.attribute("value").or_else(|| n.text()).unwrap_or("");
+                Ok((name, property(kind, value)?))
+            })
+            .collect()
+    }
+
+    fn tile_layer(node: Node) -> Result<TileLayer, TiledError> {
+        let size = ScreenSize::new(attr(node, "width")?, attr(node, "height")?);
+        let mut layer = TileLayer {
+            name: attr_or(node, "name", String::new())?,
+            size,
+            offset: offset(node)?,
+            visible: attr_or(node, "visible", 1u8)? != 0,
+            gids: Vec::new(),
+            properties: Properties::new(),
+        };
+
+        for child in elements(node) {
+            match child.tag_name().name() {
+                "properties" => layer.properties = properties(child)?,
+                "data" => layer.gids = data(child, tile_count(size)?)?,
+                _ => {}
+            }
+        }
+
+        Ok(layer)
+    }
+
+    fn data(node: Node, count: usize) -> Result<Vec<Gid>, TiledError> {
+        if elements(node).any(|n| n.tag_name().name() == "chunk") {
+            return unsupported("chunked tile data");
+        }
+
+        let text = node.text().unwrap_or("");
+        match node.attribute("encoding") {
+            None => {
+                let gids = elements(node)
+                    .filter(|n| n.tag_name().name() == "tile")
+                    .map(|n| attr_or(n, "gid", 0).map(Gid))
+                    .collect::<Result<_, _>>()?;
+                check_count(gids, count)
+            }
+            Some("csv") => {
+                let gids = text
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| {
+                        s.parse().map(Gid).map_err(|_| {
+                            TiledError::Invalid(format!("invalid tile in CSV data: {:?}", s))
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                check_count(gids, count)
+            }
+            Some("base64") => decode(text, node.attribute("compression"), count),
+            Some(other) => unsupported(format!("{} encoded tile data", other)),
+        }
+    }
+
+    fn object_layer(node: Node) -> Result<ObjectLayer, TiledError> {
+        let mut layer = ObjectLayer {
+            name: attr_or(node, "name", String::new())?,
+            offset: offset(node)?,
+            visible: attr_or(node, "visible", 1u8)? != 0,
+            objects: Vec::new(),
+            properties: Properties::new(),
+        };
+
+        for child in elements(node) {
+            match child.tag_name().name() {
+                "properties" => layer.properties = properties(child)?,
+                "object" => layer.objects.push(object(child)?),
+                _ => {}
+            }
+        }
+
+        Ok(layer)
+    }
+
+    fn object(node: Node) -> Result<Object, TiledError> {
+        let kind = node.attribute("type").or_else(|| node.attribute("class"));
+
+        let mut object = Object {
+            id: attr(node, "id")?,
+            name: attr_or(node, "name", String::new())?,
+            kind: kind.unwrap_or("").to_string(),
+            x: attr_or(node, "x", 0.0)?,
+            y: attr_or(node, "y", 0.0)?,
+            width: attr_or(node, "width", 0.0)?,
+            height: attr_or(node, "height", 0.0)?,
+            rotation: attr_or(node, "rotation", 0.0)?,
+            gid: node
+                .attribute("gid")
+                .map(|_| attr(node, "gid").map(Gid))
+                .transpose()?,
+            visible: attr_or(node, "visible", 1u8)? != 0,
+            properties: Properties::new(),
+        };
+
+        for child in elements(node) {
+            if child.tag_name().name() == "properties" {
+                object.properties = properties(child)?;
+            }
+        }
+
+        Ok(object)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - JSON -
+// -----------------------------------------------------------------------------
+mod json {
+    use super::*;
+
+    fn visible() -> bool {
+        true
+    }
+
+    fn orthogonal() -> String {
+        "orthogonal".to_string()
+    }
+
+    fn offset(x: f32, y: f32) -> Vec2D<i32> {
+        Vec2D::new(x.round() as i32, y.round() as i32)
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Map {
+        #[serde(default = "orthogonal")]
+        orientation: String,
+        #[serde(default)]
+        infinite: bool,
+        width: u32,
+        height: u32,
+        tilewidth: u32,
+        tileheight: u32,
+        #[serde(default)]
+        layers: Vec<Layer>,
+        #[serde(default)]
+        tilesets: Vec<Tileset>,
+        #[serde(default)]
+        properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    struct Layer {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        #[serde(default)]
+        offsetx: f32,
+        #[serde(default)]
+        offsety: f32,
+        #[serde(default = "visible")]
+        visible: bool,
+        data: Option<Value>,
+        encoding: Option<String>,
+        compression: Option<String>,
+        #[serde(default)]
+        objects: Vec<Object>,
+        #[serde(default)]
+        properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Tileset {
+        #[serde(default)]
+        firstgid: u32,
+        source: Option<String>,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        tilewidth: u32,
+        #[serde(default)]
+        tileheight: u32,
+        #[serde(default)]
+        tilecount: u32,
+        #[serde(default)]
+        columns: u32,
+        image: Option<String>,
+        #[serde(default)]
+        tiles: Vec<TilesetTile>,
+    }
+
+    #[derive(Deserialize)]
+    struct TilesetTile {
+        id: u32,
+        image: Option<String>,
+        #[serde(default)]
+        animation: Vec<Frame>,
+        #[serde(default)]
+        properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    struct Frame {
+        tileid: u32,
+        duration: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct Object {
+        id: u32,
+        #[serde(default)]
+        name: String,
+        #[serde(default, rename = "type", alias = "class")]
+        kind: String,
+        #[serde(default)]
+        x: f32,
+        #[serde(default)]
+        y: f32,
+        #[serde(default)]
+        width: f32,
+        #[serde(default)]
+        height: f32,
+        #[serde(default)]
+        rotation: f32,
+        gid: Option<u32>,
+        #[serde(default = "visible")]
+        visible: bool,
+        #[serde(default)]
+        properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    struct Property {
+        name: String,
+        #[serde(default, rename = "type")]
+        kind: String,
+        value: Value,
+    }
+
+    fn properties(properties: Vec<Property>) -> Result<Properties, TiledError> {
+        properties
+            .into_iter()
+            .map(|p| {
+                let value = match p.value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                Ok((p.name, property(&p.kind, &value)?))
+            })
+            .collect()
+    }
+
+    impl Map {
+        pub(super) fn convert(self) -> Result<super::Map, TiledError> {
+            check_map(&self.orientation, self.infinite)?;
+
+            let layers = self
+                .layers
+                .into_iter()
+                .map(Layer::convert)
+                .collect::<Result<_, _>>()?;
+
+            let tilesets = self
+                .tilesets
+                .into_iter()
+                .map(|tileset| match tileset.source {
+                    Some(source) => Ok(super::Tileset {
+                        first_gid: tileset.firstgid,
+                        source: Some(source),
+                        ..Default::default()
+                    }),
+                    None => tileset.convert(),
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(super::Map {
+                size: ScreenSize::new(self.width, self.height),
+                tile_size: ScreenSize::new(self.tilewidth, self.tileheight),
+                tilesets,
+                layers,
+                properties: properties(self.properties)?,
+            })
+        }
+    }
+
+    impl Layer {
+        fn convert(self) -> Result<super::Layer, TiledError> {
+            let offset = offset(self.offsetx, self.offsety);
+            let layer_properties = properties(self.properties)?;
+
+            match self.kind.as_str() {
+                "tilelayer" => {
+                    let count = tile_count(ScreenSize::new(self.width, self.height))?;
+                    let gids = match self.data {
+                        Some(Value::Array(values)) => {
+                            let gids = values
+                                .iter()
+                                .map(|v| match v.as_u64() {
+                                    Some(gid) => Ok(Gid(gid as u32)),
+                                    None => invalid(format!("invalid tile in layer data: {}", v)),
+                                })
+                                .collect::<Result<_, _>>()?;
+                            check_count(gids, count)?
+                        }
+                        Some(Value::String(data)) => match self.encoding.as_deref() {
+                            Some("base64") => decode(&data, self.compression.as_deref(), count)?,
+                            other => return unsupported(format!("{:?} encoded tile data", other)),
+                        },
+                        None => return unsupported("chunked tile data"),
+                        Some(_) => return invalid("tile layer data is not an array or a string"),
+                    };
+
+                    Ok(super::Layer::Tiles(TileLayer {
+                        name: self.name,
+                        size: ScreenSize::new(self.width, self.height),
+                        offset,
+                        visible: self.visible,
+                        gids,
+                        properties: layer_properties,
+                    }))
+                }
+                "objectgroup" => {
+                    let objects = self
+                        .objects
+                        .into_iter()
+                        .map(|o| {
+                            Ok(super::Object {
+                                id: o.id,
+                                name: o.name,
+                                kind: o.kind,
+                                x: o.x,
+                                y: o.y,
+                                width: o.width,
+                                height: o.height,
+                                rotation: o.rotation,
+                                gid: o.gid.map(Gid),
+                                visible: o.visible,
+                                properties: properties(o.properties)?,
+                            })
+                        })
+                        .collect::<Result<_, TiledError>>()?;
+
+                    Ok(super::Layer::Objects(ObjectLayer {
+                        name: self.name,
+                        offset,
+                        visible: self.visible,
+                        objects,
+                        properties: layer_properties,
+                    }))
+                }
+                "imagelayer" => unsupported("image layers"),
+                "group" => unsupported("group layers"),
+                other => invalid(format!("unknown layer type {:?}", other)),
+            }
+        }
+    }
+
+    impl Tileset {
+        pub(super) fn convert(self) -> Result<super::Tileset, TiledError> {
+            let mut tileset = super::Tileset {
+                first_gid: self.firstgid,
+                source: None,
+                name: self.name,
+                tile_size: ScreenSize::new(self.tilewidth, self.tileheight),
+                tile_count: self.tilecount,
+                columns: self.columns,
+                image: self.image,
+                ..Default::default()
+            };
+
+            for tile in self.tiles {
+                if tile.image.is_some() {
+                    return unsupported("image collection tilesets");
+                }
+
+                if !tile.animation.is_empty() {
+                    let frames = tile
+                        .animation
+                        .iter()
+                        .map(|f| (f.tileid, Duration::from_millis(f.duration)))
+                        .collect();
+                    tileset.animations.insert(tile.id, frames);
+                }
+
+                if !tile.properties.is_empty() {
+                    tileset
+                        .tile_properties
+                        .insert(tile.id, properties(tile.properties)?);
+                }
+            }
+
+            Ok(tileset)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+
+    use super::*;
+
+    const GIDS: [u32; 4] = [
+        1,
+        2 | FLIPPED_HORIZONTALLY,
+        0,
+        4 | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY,
+    ];
+
+    fn tmx(data: &str) -> String {
+        format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8" infinite="0">
+ <properties>
+  <property name="title" value="Level one"/>
+  <property name="gravity" type="float" value="9.5"/>
+ </properties>
+ <tileset firstgid="1" name="tiles" tilewidth="8" tileheight="8" tilecount="4" columns="2">
+  <image source="tiles.png" width="16" height="16"/>
+  <tile id="1">
+   <animation>
+    <frame tileid="1" duration="100"/>
+    <frame tileid="2" duration="200"/>
+   </animation>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="2" height="2" offsetx="4" offsety="-2">
+  {}
+ </layer>
+ <objectgroup id="2" name="spawns" visible="0">
+  <object id="3" name="player" type="spawn" x="16" y="24.5">
+   <properties>
+    <property name="lives" type="int" value="3"/>
+    <property name="tint" type="color" value="#80ff0000"/>
+    <property name="boss" type="bool" value="true"/>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"##,
+            data
+        )
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    fn gid_bytes() -> Vec<u8> {
+        GIDS.iter().flat_map(|g| g.to_le_bytes().to_vec()).collect()
+    }
+
+    fn tile_layer(map: &Map) -> &TileLayer {
+        match &map.layers[0] {
+            Layer::Tiles(layer) => layer,
+            Layer::Objects(_) => panic!("expected a tile layer"),
+        }
+    }
+
+    #[test]
+    fn tmx_encodings() {
+        let csv = GIDS
+            .iter()
+            .map(|g| g.to_string())
+            .collect::<Vec<_>>()
+            .join(",\n");
+        let xml = GIDS
+            .iter()
+            .map(|g| format!(r#"<tile gid="{}"/>"#, g))
+            .collect::<String>();
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&gid_bytes()).unwrap();
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&gid_bytes()).unwrap();
+
+        let data = vec![
+            format!(r#"<data encoding="csv">{}</data>"#, csv),
+            format!("<data>{}</data>", xml),
+            format!(r#"<data encoding="base64">{}</data>"#, base64(&gid_bytes())),
+            format!(
+                r#"<data encoding="base64" compression="zlib">{}</data>"#,
+                base64(&zlib.finish().unwrap())
+            ),
+            format!(
+                r#"<data encoding="base64" compression="gzip">{}</data>"#,
+                base64(&gzip.finish().unwrap())
+            ),
+        ];
+
+        for data in data {
+            let map = Map::from_tmx(&tmx(&data)).unwrap();
+            let gids = tile_layer(&map)
+                .gids
+                .iter()
+                .map(|g| g.0)
+                .collect::<Vec<_>>();
+            assert_eq!(gids, GIDS.to_vec());
+        }
+    }
+
+    #[test]
+    fn tmx_map_contents() {
+        let map = Map::from_tmx(&tmx(r#"<data encoding="csv">1,2,0,4</data>"#)).unwrap();
+
+        assert_eq!(map.size, ScreenSize::new(2, 2));
+        assert_eq!(
+            map.properties["title"],
+            Property::String("Level one".into())
+        );
+        assert_eq!(map.properties["gravity"], Property::Float(9.5));
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.image.as_deref(), Some("tiles.png"));
+        assert_eq!(
+            tileset.animations[&1],
+            vec![
+                (1, Duration::from_millis(100)),
+                (2, Duration::from_millis(200))
+            ]
+        );
+
+        let layer = tile_layer(&map);
+        assert_eq!(layer.name, "ground");
+        assert_eq!(layer.offset, Vec2D::new(4, -2));
+
+        let objects = match &map.layers[1] {
+            Layer::Objects(layer) => layer,
+            Layer::Tiles(_) => panic!("expected an object layer"),
+        };
+        assert!(!objects.visible);
+
+        let player = &objects.objects[0];
+        assert_eq!(
+            (player.name.as_str(), player.kind.as_str()),
+            ("player", "spawn")
+        );
+        assert_eq!((player.x, player.y), (16.0, 24.5));
+        assert_eq!(player.properties["lives"], Property::Int(3));
+        assert_eq!(
+            player.properties["tint"],
+            Property::Colour(Pixel::new(255, 0, 0, 128))
+        );
+        assert_eq!(player.properties["boss"], Property::Bool(true));
+    }
+
+    #[test]
+    fn flip_bits() {
+        let gid = Gid(GIDS[3]);
+        assert_eq!(gid.id(), 4);
+        assert!(!gid.flip_x() && gid.flip_y() && gid.flip_diagonal());
+        assert!(Gid(0).is_empty());
+    }
+
+    #[test]
+    fn json_map() {
+        let json = format!(
+            r#"{{
+                "orientation": "orthogonal", "infinite": false,
+                "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8,
+                "properties": [{{ "name": "title", "type": "string", "value": "Level one" }}],
+                "tilesets": [{{
+                    "firstgid": 1, "name": "tiles", "tilewidth": 8, "tileheight": 8,
+                    "tilecount": 4, "columns": 2, "image": "tiles.png",
+                    "tiles": [{{ "id": 1, "animation": [{{ "tileid": 2, "duration": 50 }}] }}]
+                }}],
+                "layers": [
+                    {{ "type": "tilelayer", "name": "ground", "width": 2, "height": 2,
+                       "data": "{}", "encoding": "base64" }},
+                    {{ "type": "objectgroup", "name": "spawns", "objects": [
+                        {{ "id": 3, "name": "player", "type": "spawn", "x": 16, "y": 24,
+                           "properties": [{{ "name": "lives", "type": "int", "value": 3 }}] }}
+                    ] }}
+                ]
+            }}"#,
+            base64(&gid_bytes())
+        );
+
+        let map = Map::from_json(&json).unwrap();
+        assert_eq!(
+            map.properties["title"],
+            Property::String("Level one".into())
+        );
+        assert_eq!(
+            map.tilesets[0].animations[&1],
+            vec![(2, Duration::from_millis(50))]
+        );
+
+        let gids = tile_layer(&map)
+            .gids
+            .iter()
+            .map(|g| g.0)
+            .collect::<Vec<_>>();
+        assert_eq!(gids, GIDS.to_vec());
+
+        match &map.layers[1] {
+            Layer::Objects(layer) => {
+                assert_eq!(layer.objects[0].properties["lives"], Property::Int(3));
+            }
+            Layer::Tiles(_) => panic!("expected an object layer"),
+        }
+    }
+
+    #[test]
+    fn unsupported_features() {
+        let isometric = tmx("").replace("orthogonal", "isometric");
+        let infinite = tmx("").replace(r#"infinite="0""#, r#"infinite="1""#);
+        let zstd = tmx(r#"<data encoding="base64" compression="zstd">AAAA</data>"#);
+
+        for (xml, expected) in &[
+            (isometric, "isometric maps"),
+            (infinite, "infinite maps"),
+            (zstd, "zstd compressed tile data"),
+        ] {
+            match Map::from_tmx(xml) {
+                Err(TiledError::Unsupported(feature)) => assert_eq!(feature, *expected),
+                other => panic!("expected an unsupported error, got {:?}", other),
+            }
+        }
+
+        let short = tmx(r#"<data encoding="csv">1,2,3</data>"#);
+        assert!(matches!(Map::from_tmx(&short), Err(TiledError::Invalid(_))));
+    }
+
+    #[test]
+    fn convert_to_tilemap() {
+        let map = Map::from_tmx(&tmx(r#"<data encoding="csv">1,2,0,4</data>"#)).unwrap();
+        let tilemap = map
+            .to_tilemap(Image::empty(ScreenSize::new(16, 16)))
+            .unwrap();
+
+        let layer = &tilemap.layers[0];
+        assert_eq!(layer.offset, Vec2D::new(4, -2));
+        assert_eq!(layer.get(0, 0), Some(Tile::new(0)));
+        assert_eq!(layer.get(1, 0), Some(Tile::new(1)));
+        assert_eq!(layer.get(0, 1), None);
+        assert_eq!(tilemap.layers.len(), 1);
+    }
+
+    #[test]
+    fn reject_oversized_layers() {
+        // A megabyte of zeros for a layer of four tiles
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&[0; 1 << 20]).unwrap();
+        let bomb = format!(
+            r#"<data encoding="base64" compression="zlib">{}</data>"#,
+            base64(&zlib.finish().unwrap())
+        );
+        assert!(matches!(
+            Map::from_tmx(&tmx(&bomb)),
+            Err(TiledError::Invalid(_))
+        ));
+
+        let huge = tmx(r#"<data encoding="csv">1,2,3,4</data>"#).replace(
+            r#"width="2" height="2" offsetx"#,
+            r#"width="100000" height="100000" offsetx"#,
+        );
+        assert!(matches!(Map::from_tmx(&huge), Err(TiledError::Invalid(_))));
+    }
+
+    #[test]
+    fn gid_below_first_gid() {
+        let xml = tmx(r#"<data encoding="csv">1,2,0,4</data>"#)
+            .replace(r#"firstgid="1""#, r#"firstgid="2""#);
+        let map = Map::from_tmx(&xml).unwrap();
+        let tilemap = map.to_tilemap(Image::empty(ScreenSize::new(16, 16)));
+        assert!(matches!(tilemap, Err(TiledError::Invalid(_))));
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::{Image, ImageView, ScreenPos, ScreenRect, ScreenSize, Vec2D, Viewport};
+
+// -----------------------------------------------------------------------------
+//     - Tileset -
+// -----------------------------------------------------------------------------
+/// A sprite sheet of equally sized tiles.
+/// Tiles are numbered left to right, top to bottom, starting at zero.
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    image: Image,
+    tile_size: ScreenSize,
+    columns: u32,
+}
+
+impl Tileset {
+    pub fn new(image: Image, tile_size: ScreenSize) -> Self {
+        let columns = image.width() / tile_size.width.max(1);
+        Self {
+            image,
+            tile_size,
+            columns,
+        }
+    }
+
+    pub fn tile_size(&self) -> ScreenSize {
+        self.tile_size
+    }
+
+    pub fn tile_count(&self) -> u32 {
+        self.columns * (self.image.height() / self.tile_size.height.max(1))
+    }
+
+    /// The pixels of a single tile.
+    /// Tiles outside of the tileset are empty views.
+    pub fn tile(&self, index: u32) -> ImageView<'_> {
+        if index >= self.tile_count() {
+            return self.image.view(ScreenRect::zero());
+        }
+
+        let x = index % self.columns * self.tile_size.width;
+        let y = index / self.columns * self.tile_size.height;
+        self.image
+            .view(ScreenRect::new(ScreenPos::new(x, y), self.tile_size))
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Tile -
+// -----------------------------------------------------------------------------
+/// A reference to a tile in a tileset, along with how it is flipped.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Tile {
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Swap x and y before any other flip is applied.
+    /// Combined with the other flips this rotates the tile in 90 degree steps.
+    /// Only square tiles can be flipped diagonally.
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            ..Default::default()
+        }
+    }
+
+    // Position in the tileset tile for a position in the drawn tile
+    fn source(&self, x: u32, y: u32, size: ScreenSize) -> (u32, u32) {
+        debug_assert!(
+            !self.flip_diagonal || size.width == size.height,
+            "diagonally flipped tiles have to be square"
+        );
+        let x = if self.flip_x { size.width - 1 - x } else { x };
+        let y = if self.flip_y { size.height - 1 - y } else { y };
+        if self.flip_diagonal {
+            (y, x)
+        } else {
+            (x, y)
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Tile layer -
+// -----------------------------------------------------------------------------
+/// A grid of tiles.
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    size: ScreenSize,
+    tiles: Vec<Option<Tile>>,
+    /// Offset of the layer in pixels, relative to the map.
+    pub offset: Vec2D<i32>,
+    pub visible: bool,
+}
+
+impl TileLayer {
+    /// Create an empty layer, `size` tiles wide and high.
+    pub fn new(size: ScreenSize) -> Self {
+        Self {
+            size,
+            tiles: vec![None; (size.width * size.height) as usize],
+            offset: Vec2D::zero(),
+            visible: true,
+        }
+    }
+
+    /// The size of the layer in tiles.
+    pub fn size(&self) -> ScreenSize {
+        self.size
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Tile> {
+        if x < self.size.width && y < self.size.height {
+            self.tiles[(y * self.size.width + x) as usize]
+        } else {
+            None
+        }
+    }
+
+    /// Set or clear a tile.
+    /// Positions outside of the layer are ignored.
+    pub fn set(&mut self, x: u32, y: u32, tile: Option<Tile>) {
+        if x < self.size.width && y < self.size.height {
+            self.tiles[(y * self.size.width + x) as usize] = tile;
+        }
+    }
+
+    // The range of tile columns and rows that are at least partly visible
+    // in an area of `view_size` pixels, where the top left tile of the
+    // layer is drawn at `origin`.
+    fn visible_tiles(
+        &self,
+        tile_size: ScreenSize,
+        view_size: ScreenSize,
+        origin: Vec2D<i32>,
+    ) -> (Range<u32>, Range<u32>) {
+        let range = |origin: i32, view: u32, tile: u32, count: u32| {
+            let tile = tile.max(1) as i64;
+            let start = (-(origin as i64)).div_euclid(tile);
+            let end = (view as i64 - origin as i64 + tile - 1).div_euclid(tile);
+            let clamp = |v: i64| v.clamp(0, count as i64) as u32;
+            clamp(start)..clamp(end)
+        };
+
+        (
+            range(origin.x, view_size.width, tile_size.width, self.size.width),
+            range(
+                origin.y,
+                view_size.height,
+                tile_size.height,
+                self.size.height,
+            ),
+        )
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Tile animation -
+// -----------------------------------------------------------------------------
+/// A sequence of tiles shown in place of an animated tile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileAnimation {
+    /// Tile index and how long it is shown for.
+    pub frames: Vec<(u32, Duration)>,
+}
+
+impl TileAnimation {
+    pub fn new(frames: Vec<(u32, Duration)>) -> Self {
+        Self { frames }
+    }
+
+    /// The tile to show after `elapsed` time, looping forever.
+    pub fn frame(&self, elapsed: Duration) -> Option<u32> {
+        let total = self.frames.iter().map(|(_, d)| d.as_nanos()).sum::<u128>();
+        if total == 0 {
+            return self.frames.first().map(|(index, _)| *index);
+        }
+
+        let mut time = elapsed.as_nanos() % total;
+        for (index, duration) in &self.frames {
+            if time < duration.as_nanos() {
+                return Some(*index);
+            }
+            time -= duration.as_nanos();
+        }
+
+        None
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Tilemap -
+// -----------------------------------------------------------------------------
+/// Layers of tiles drawn from a single tileset.
+/// Layers are drawn in order, so the last layer ends up on top.
+#[derive(Debug, Clone)]
+pub struct Tilemap {
+    pub tileset: Tileset,
+    pub layers: Vec<TileLayer>,
+    animations: HashMap<u32, TileAnimation>,
+    elapsed: Duration,
+}
+
+impl Tilemap {
+    pub fn new(tileset: Tileset) -> Self {
+        Self {
+            tileset,
+            layers: Vec::new(),
+            animations: HashMap::new(),
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    /// Add a layer on top of the existing layers.
+    pub fn add_layer(&mut self, layer: TileLayer) {
+        self.layers.push(layer);
+    }
+
+    /// Animate every occurrence of a tile.
+    pub fn set_animation(&mut self, index: u32, animation: TileAnimation) {
+        self.animations.insert(index, animation);
+    }
+
+    pub fn remove_animation(&mut self, index: u32) {
+        self.animations.remove(&index);
+    }
+
+    /// Advance tile animations.
+    pub fn update(&mut self, dt: Duration) {
+        self.elapsed += dt;
+    }
+
+    /// Draw every visible layer into a viewport.
+    /// `scroll` is the position of the map, in pixels, that ends up
+    /// in the top left corner of the viewport.
+    /// Only tiles that are at least partly inside the viewport are drawn.
+    pub fn draw(&self, viewport: &mut Viewport, scroll: Vec2D<i32>) {
+        let tile_size = self.tileset.tile_size();
+        let view_size = viewport.canvas_size();
+
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            let origin = layer.offset - scroll;
+            let (columns, rows) = layer.visible_tiles(tile_size, view_size, origin);
+
+            for y in rows {
+                for x in columns.clone() {
+                    if let Some(tile) = layer.get(x, y) {
+                        let pos = Vec2D::new(
+                            origin.x + (x * tile_size.width) as i32,
+                            origin.y + (y * tile_size.height) as i32,
+                        );
+                        self.draw_tile(viewport, tile, pos);
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_tile(&self, viewport: &mut Viewport, tile: Tile, pos: Vec2D<i32>) {
+        let index = match self.animations.get(&tile.index) {
+            Some(animation) => animation.frame(self.elapsed).unwrap_or(tile.index),
+            None => tile.index,
+        };
+
+        let source = self.tileset.tile(index);
+        let size = source.size();
+
+        for y in 0..size.height {
+            let screen_y = pos.y + y as i32;
+            if screen_y < 0 || screen_y as u32 >= viewport.canvas_size().height {
+                continue;
+            }
+
+            for x in 0..size.width {
+                let screen_x = pos.x + x as i32;
+                if screen_x < 0 || screen_x as u32 >= viewport.canvas_size().width {
+                    continue;
+                }
+
+                let (sx, sy) = tile.source(x, y, size);
+                match source.get(sx, sy) {
+                    Some(pixel) if pixel.a > 0 => {
+                        viewport.draw_pixel(pixel, ScreenPos::new(screen_x as u32, screen_y as u32))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Pixel;
+
+    const TILE: ScreenSize = ScreenSize::new(2, 2);
+
+    // Four tiles in a row, where each tile is a single colour
+    // except tile 3 which has one red pixel in the top left corner.
+    fn tileset() -> Tileset {
+        let mut image = Image::empty(ScreenSize::new(8, 2));
+        for pos in image.coords() {
+            let index = pos.x / 2;
+            if index < 3 {
+                image.set(pos.x, pos.y, Pixel::rgb(index as u8 + 1, 0, 0));
+            }
+        }
+        image.set(6, 0, Pixel::rgb(255, 0, 0));
+        Tileset::new(image, TILE)
+    }
+
+    fn drawn(viewport: &mut Viewport) -> Vec<(Pixel, ScreenPos)> {
+        let mut pixels = viewport.pixels();
+        pixels.sort_by_key(|(_, pos)| (pos.y, pos.x));
+        pixels
+    }
+
+    #[test]
+    fn tileset_lookup() {
+        let tileset = tileset();
+        assert_eq!(tileset.tile_count(), 4);
+        assert_eq!(tileset.tile(1).get(1, 1), Some(Pixel::rgb(2, 0, 0)));
+        assert_eq!(tileset.tile(4).size(), ScreenSize::zero());
+    }
+
+    #[test]
+    fn visible_tile_range() {
+        let layer = TileLayer::new(ScreenSize::new(100, 100));
+        let view = ScreenSize::new(10, 4);
+
+        let (columns, rows) = layer.visible_tiles(TILE, view, Vec2D::zero());
+        assert_eq!((columns, rows), (0..5, 0..2));
+
+        // Scrolled by half a tile, so one more column is partly visible
+        let (columns, rows) = layer.visible_tiles(TILE, view, Vec2D::new(-3, -2));
+        assert_eq!((columns, rows), (1..7, 1..3));
+
+        // The layer is entirely off screen
+        let (columns, _) = layer.visible_tiles(TILE, view, Vec2D::new(-1000, 0));
+        assert!(columns.is_empty());
+    }
+
+    #[test]
+    fn draw_with_scroll() {
+        let mut map = Tilemap::new(tileset());
+        let mut layer = TileLayer::new(ScreenSize::new(2, 1));
+        layer.set(0, 0, Some(Tile::new(0)));
+        layer.set(1, 0, Some(Tile::new(1)));
+        map.add_layer(layer);
+
+        let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(2, 1));
+        map.draw(&mut viewport, Vec2D::new(1, 0));
+
+        let expected = vec![
+            (Pixel::rgb(1, 0, 0), ScreenPos::new(0, 0)),
+            (Pixel::rgb(2, 0, 0), ScreenPos::new(1, 0)),
+        ];
+        assert_eq!(drawn(&mut viewport), expected);
+    }
+
+    #[test]
+    fn flipped_tiles() {
+        let mut map = Tilemap::new(tileset());
+        let mut layer = TileLayer::new(ScreenSize::new(3, 1));
+        layer.set(
+            0,
+            0,
+            Some(Tile {
+                index: 3,
+                flip_x: true,
+                ..Default::default()
+            }),
+        );
+        layer.set(
+            1,
+            0,
+            Some(Tile {
+                index: 3,
+                flip_y: true,
+                ..Default::default()
+            }),
+        );
+        layer.set(
+            2,
+            0,
+            Some(Tile {
+                index: 3,
+                flip_x: true,
+                flip_y: true,
+                ..Default::default()
+            }),
+        );
+        map.add_layer(layer);
+
+        let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(6, 2));
+        map.draw(&mut viewport, Vec2D::zero());
+
+        let red = Pixel::rgb(255, 0, 0);
+        let expected = vec![
+            (red, ScreenPos::new(1, 0)),
+            (red, ScreenPos::new(2, 1)),
+            (red, ScreenPos::new(5, 1)),
+        ];
+        assert_eq!(drawn(&mut viewport), expected);
+    }
+
+    #[test]
+    fn diagonal_flips() {
+        let size = ScreenSize::new(3, 3);
+        let tile = |flip_x, flip_y| Tile {
+            flip_x,
+            flip_y,
+            flip_diagonal: true,
+            ..Default::default()
+        };
+
+        // Transposed
+        assert_eq!(tile(false, false).source(2, 0, size), (0, 2));
+        assert_eq!(tile(false, false).source(1, 1, size), (1, 1));
+
+        // Rotated clockwise, so the top left corner ends up in the top right
+        assert_eq!(tile(true, false).source(2, 0, size), (0, 0));
+        assert_eq!(tile(true, false).source(0, 0, size), (0, 2));
+
+        // Rotated anti-clockwise, so the top left corner ends up in the bottom left
+        assert_eq!(tile(false, true).source(0, 2, size), (0, 0));
+    }
+
+    #[test]
+    fn animated_tiles() {
+        let mut map = Tilemap::new(tileset());
+        let mut layer = TileLayer::new(ScreenSize::new(1, 1));
+        layer.set(0, 0, Some(Tile::new(0)));
+        map.add_layer(layer);
+
+        let frame = Duration::from_millis(100);
+        map.set_animation(0, TileAnimation::new(vec![(0, frame), (2, frame)]));
+
+        let mut viewport = Viewport::new(ScreenPos::zero(), TILE);
+        let colour_at = |map: &Tilemap, viewport: &mut Viewport| {
+            map.draw(viewport, Vec2D::zero());
+            viewport.pixels()[0].0
+        };
+
+        assert_eq!(colour_at(&map, &mut viewport), Pixel::rgb(1, 0, 0));
+        map.update(Duration::from_millis(150));
+        assert_eq!(colour_at(&map, &mut viewport), Pixel::rgb(3, 0, 0));
+        map.update(Duration::from_millis(100));
+        assert_eq!(colour_at(&map, &mut viewport), Pixel::rgb(1, 0, 0));
+    }
+}
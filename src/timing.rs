@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::{font, Image, ImageView, Pixel, ScreenPos, ScreenSize};
+
+// -----------------------------------------------------------------------------
+//     - Frame limiter -
+// -----------------------------------------------------------------------------
+/// Caps the frame rate by telling the event loop when the next frame is due.
+#[derive(Debug, Clone, Default)]
+pub struct FrameLimiter {
+    interval: Option<Duration>,
+    next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// Create a limiter for `max_fps` frames per second.
+    /// `None` (or zero) means no limit.
+    pub fn new(max_fps: Option<u32>) -> Self {
+        let mut limiter = Self::default();
+        limiter.set_max_fps(max_fps);
+        limiter
+    }
+
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        let interval = max_fps
+            .filter(|fps| *fps > 0)
+            .map(|fps| Duration::from_secs(1) / fps);
+
+        if interval != self.interval {
+            self.interval = interval;
+            self.next_frame = None;
+        }
+    }
+
+    pub fn max_fps(&self) -> Option<u32> {
+        self.interval.map(|interval| {
+            (Duration::from_secs(1).as_secs_f64() / interval.as_secs_f64()).round() as u32
+        })
+    }
+
+    /// `None` if a frame should run now, otherwise the time to wait until.
+    /// Running a frame schedules the next one.
+    pub fn wait_until(&mut self, now: Instant) -> Option<Instant> {
+        let interval = self.interval?;
+
+        match self.next_frame {
+            Some(next) if now < next => return Some(next),
+            // Keep a steady pace, unless a frame ran so late
+            // that catching up would mean a burst of frames.
+            Some(next) if now - next < interval => self.next_frame = Some(next + interval),
+            _ => self.next_frame = Some(now + interval),
+        }
+
+        None
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Frame stats -
+// -----------------------------------------------------------------------------
+/// The parts of a frame that are timed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FrameStage {
+    /// `EventLoop::update`.
+    Update,
+    /// `EventLoop::draw`, which includes `Diff`.
+    Draw,
+    /// Working out which pixels changed in `Viewport::pixels`.
+    Diff,
+    /// Upscaling and uploading the pixels to the texture.
+    Upload,
+    /// Encoding the render passes and presenting the frame.
+    Present,
+    /// The time from the end of one frame to the end of the next.
+    Frame,
+}
+
+impl FrameStage {
+    pub const ALL: [FrameStage; 6] = [
+        FrameStage::Update,
+        FrameStage::Draw,
+        FrameStage::Diff,
+        FrameStage::Upload,
+        FrameStage::Present,
+        FrameStage::Frame,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Min, average, max and percentiles of a stage over the recorded history.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Summary {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+/// A rolling history of frame timings.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    capacity: usize,
+    current: [Duration; 6],
+    history: [VecDeque<Duration>; 6],
+    last_frame: Option<Instant>,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(240)
+    }
+}
+
+impl FrameStats {
+    /// Keep the timings of the last `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            current: Default::default(),
+            history: Default::default(),
+            last_frame: None,
+        }
+    }
+
+    /// Add time spent in a stage during the current frame.
+    /// A stage can be added to more than once per frame, e.g. once per viewport.
+    pub fn add(&mut self, stage: FrameStage, time: Duration) {
+        self.current[stage.index()] += time;
+    }
+
+    /// Finish the current frame, moving its timings into the history.
+    /// This is called by the renderer once a frame has been presented.
+    pub fn end_frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame {
+            self.current[FrameStage::Frame.index()] = now - last;
+        }
+
+        for stage in FrameStage::ALL.iter() {
+            let time = std::mem::take(&mut self.current[stage.index()]);
+            // The first frame has nothing to measure its length against
+            if *stage == FrameStage::Frame && self.last_frame.is_none() {
+                continue;
+            }
+
+            let history = &mut self.history[stage.index()];
+            if history.len() == self.capacity {
+                history.pop_front();
+            }
+            history.push_back(time);
+        }
+
+        self.last_frame = Some(now);
+    }
+
+    /// Recorded timings for a stage, oldest first.
+    pub fn history(&self, stage: FrameStage) -> impl Iterator<Item = Duration> + '_ {
+        self.history[stage.index()].iter().copied()
+    }
+
+    /// The timing of a stage in the most recent frame.
+    pub fn last(&self, stage: FrameStage) -> Option<Duration> {
+        self.history[stage.index()].back().copied()
+    }
+
+    /// The `percentile` (0 - 100) of a stage, using the nearest rank.
+    pub fn percentile(&self, stage: FrameStage, percentile: f32) -> Option<Duration> {
+        let mut sorted = self.history(stage).collect::<Vec<_>>();
+        sorted.sort();
+        nearest_rank(&sorted, percentile)
+    }
+
+    pub fn summary(&self, stage: FrameStage) -> Option<Summary> {
+        let mut sorted = self.history(stage).collect::<Vec<_>>();
+        sorted.sort();
+
+        Some(Summary {
+            min: *sorted.first()?,
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            max: *sorted.last()?,
+            p50: nearest_rank(&sorted, 50.0)?,
+            p95: nearest_rank(&sorted, 95.0)?,
+            p99: nearest_rank(&sorted, 99.0)?,
+        })
+    }
+
+    /// Frames per second, averaged over the history.
+    pub fn fps(&self) -> f32 {
+        match self.summary(FrameStage::Frame) {
+            Some(summary) if summary.avg > Duration::from_secs(0) => {
+                1.0 / summary.avg.as_secs_f32()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+fn nearest_rank(sorted: &[Duration], percentile: f32) -> Option<Duration> {
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f32).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+// -----------------------------------------------------------------------------
+//     - Stats overlay -
+// -----------------------------------------------------------------------------
+/// Draws an FPS counter and a frame time graph on top of everything else.
+#[derive(Debug, Clone)]
+pub struct StatsOverlay {
+    pub position: ScreenPos,
+    pub size: ScreenSize,
+    /// The stage shown in the graph.
+    pub stage: FrameStage,
+    /// Frame time drawn as a line across the graph.
+    /// Bars above the line are drawn in red.
+    pub target: Duration,
+}
+
+const OVERLAY_TEXT: Pixel = Pixel::rgb(255, 255, 255);
+const OVERLAY_GOOD: Pixel = Pixel::rgb(0, 228, 54);
+const OVERLAY_BAD: Pixel = Pixel::rgb(255, 0, 77);
+const OVERLAY_LINE: Pixel = Pixel::rgb(255, 236, 39);
+
+impl StatsOverlay {
+    pub fn new(position: ScreenPos) -> Self {
+        Self {
+            position,
+            size: ScreenSize::new(100, 48),
+            stage: FrameStage::Frame,
+            target: Duration::from_micros(16_667),
+        }
+    }
+
+    /// Draw the overlay on top of `background`, which is the
+    /// part of the screen the overlay covers.
+    pub fn render(&self, stats: &FrameStats, background: ImageView) -> Image {
+        let mut image = background.to_image();
+        image.pixels_mut().iter_mut().for_each(|p| {
+            *p = p.lerp(Pixel::rgb(0, 0, 0), 0.7);
+            p.a = 255;
+        });
+
+        let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+        let (avg, max) = match stats.summary(self.stage) {
+            Some(summary) => (ms(summary.avg), ms(summary.max)),
+            None => (0.0, 0.0),
+        };
+        let text = format!("FPS {:.0}\n{:.1}ms max {:.1}", stats.fps(), avg, max);
+        image.draw_text(&text, ScreenPos::new(2, 2), OVERLAY_TEXT);
+
+        // Graph, newest frame on the right
+        let top = 2 + 2 * font::LINE_HEIGHT;
+        if image.height() <= top + 2 || image.width() < 4 {
+            return image;
+        }
+        let height = image.height() - top - 2;
+        let scale = ms(self.target) * 2.0;
+        let bar = |d: Duration| ((ms(d) / scale).min(1.0) * height as f32).round() as u32;
+
+        let columns = (image.width() - 4) as usize;
+        let history = stats.history(self.stage).collect::<Vec<_>>();
+        let bottom = top + height;
+        for (i, time) in history.iter().rev().take(columns).enumerate() {
+            let x = image.width() - 3 - i as u32;
+            let colour = if *time > self.target {
+                OVERLAY_BAD
+            } else {
+                OVERLAY_GOOD
+            };
+            for y in bottom - bar(*time)..bottom {
+                image.set(x, y, colour);
+            }
+        }
+
+        let line = bottom - bar(self.target);
+        for x in 2..image.width() - 2 {
+            image.set(x, line, OVERLAY_LINE);
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn unlimited() {
+        let mut limiter = FrameLimiter::new(None);
+        let now = Instant::now();
+        assert_eq!(limiter.wait_until(now), None);
+        assert_eq!(limiter.wait_until(now), None);
+        assert_eq!(FrameLimiter::new(Some(0)).max_fps(), None);
+    }
+
+    #[test]
+    fn waits_for_next_frame() {
+        let mut limiter = FrameLimiter::new(Some(50));
+        assert_eq!(limiter.max_fps(), Some(50));
+
+        let start = Instant::now();
+        assert_eq!(limiter.wait_until(start), None);
+        assert_eq!(limiter.wait_until(start + 5 * MS), Some(start + 20 * MS));
+
+        // A slightly late frame does not push the schedule back
+        assert_eq!(limiter.wait_until(start + 22 * MS), None);
+        assert_eq!(limiter.wait_until(start + 30 * MS), Some(start + 40 * MS));
+
+        // A very late frame does
+        assert_eq!(limiter.wait_until(start + 100 * MS), None);
+        assert_eq!(limiter.wait_until(start + 110 * MS), Some(start + 120 * MS));
+    }
+
+    fn stats(frames: &[u32]) -> FrameStats {
+        let mut stats = FrameStats::new(4);
+        let start = Instant::now();
+        stats.end_frame(start);
+        let mut now = start;
+        for ms in frames {
+            stats.add(FrameStage::Update, *ms * MS / 2);
+            stats.add(FrameStage::Update, *ms * MS / 2);
+            now += *ms * MS;
+            stats.end_frame(now);
+        }
+        stats
+    }
+
+    #[test]
+    fn rolling_history() {
+        let stats = stats(&[10, 20, 30, 40, 50]);
+
+        let frames = stats.history(FrameStage::Frame).collect::<Vec<_>>();
+        assert_eq!(frames, vec![20 * MS, 30 * MS, 40 * MS, 50 * MS]);
+        assert_eq!(stats.last(FrameStage::Update), Some(50 * MS));
+        assert_eq!(
+            stats.last(FrameStage::Present),
+            Some(Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn summaries() {
+        let stats = stats(&[40, 10, 30, 20]);
+        let summary = stats.summary(FrameStage::Frame).unwrap();
+
+        assert_eq!(summary.min, 10 * MS);
+        assert_eq!(summary.avg, 25 * MS);
+        assert_eq!(summary.max, 40 * MS);
+        assert_eq!(summary.p50, 20 * MS);
+        assert_eq!(summary.p95, 40 * MS);
+        assert!((stats.fps() - 40.0).abs() < 0.01);
+        assert!(FrameStats::new(4).summary(FrameStage::Frame).is_none());
+    }
+
+    #[test]
+    fn overlay_draws_graph() {
+        let stats = stats(&[10, 40]);
+        let overlay = StatsOverlay::new(ScreenPos::zero());
+        let background = Image::new(overlay.size, Pixel::rgb(255, 255, 255));
+        let image = overlay.render(&stats, background.as_view());
+
+        assert_eq!(image.size(), overlay.size);
+        let bottom = overlay.size.height - 3;
+        assert_eq!(image.get(overlay.size.width - 3, bottom), Some(OVERLAY_BAD));
+        assert_eq!(
+            image.get(overlay.size.width - 4, bottom),
+            Some(OVERLAY_GOOD)
+        );
+        assert_eq!(image.get(0, 0), Some(Pixel::rgb(77, 77, 77)));
+    }
+}
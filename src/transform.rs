@@ -0,0 +1,303 @@
+use crate::{Image, Pixel, ScreenPos, ScreenRect, ScreenSize};
+
+/// How pixels are sampled when an image is resized.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ResizeFilter {
+    /// Pick the closest source pixel. Keeps hard pixel edges.
+    Nearest,
+    /// Blend the four closest source pixels.
+    Bilinear,
+    /// Average every source pixel covered by the destination pixel.
+    /// Best suited for shrinking images.
+    Box,
+}
+
+// -----------------------------------------------------------------------------
+//     - Transforms -
+// -----------------------------------------------------------------------------
+impl Image {
+    /// Rotate the image 90 degrees clockwise.
+    pub fn rotate90(&self) -> Image {
+        let (width, height) = (self.width(), self.height());
+        self.remap(ScreenSize::new(height, width), |x, y| (y, height - 1 - x))
+    }
+
+    /// Rotate the image 180 degrees.
+    pub fn rotate180(&self) -> Image {
+        let (width, height) = (self.width(), self.height());
+        self.remap(self.size(), |x, y| (width - 1 - x, height - 1 - y))
+    }
+
+    /// Rotate the image 270 degrees clockwise (90 degrees counter clockwise).
+    pub fn rotate270(&self) -> Image {
+        let (width, height) = (self.width(), self.height());
+        self.remap(ScreenSize::new(height, width), |x, y| (width - 1 - y, x))
+    }
+
+    /// Rotate the image clockwise by an arbitrary angle (in degrees) around its centre,
+    /// using nearest sampling.
+    /// The new image is large enough to hold the entire rotated image, and any
+    /// area not covered by the source is filled with `background`.
+    pub fn rotate(&self, degrees: f32, background: Pixel) -> Image {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (width, height) = (self.width() as f32, self.height() as f32);
+
+        // Shave a little off to avoid growing by one pixel due to rounding errors
+        let new_width = (width * cos.abs() + height * sin.abs() - 1e-3).ceil().max(0.0);
+        let new_height = (width * sin.abs() + height * cos.abs() - 1e-3).ceil().max(0.0);
+        let mut image = Image::new(ScreenSize::new(new_width as u32, new_height as u32), background);
+
+        for pos in image.coords() {
+            let dx = pos.x as f32 + 0.5 - new_width / 2.0;
+            let dy = pos.y as f32 + 0.5 - new_height / 2.0;
+            let sx = (dx * cos + dy * sin + width / 2.0).floor();
+            let sy = (-dx * sin + dy * cos + height / 2.0).floor();
+
+            if sx >= 0.0 && sy >= 0.0 {
+                if let Some(pixel) = self.get(sx as u32, sy as u32) {
+                    image.set(pos.x, pos.y, pixel);
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Mirror the image left to right.
+    pub fn flip_horizontal(&self) -> Image {
+        let width = self.width();
+        self.remap(self.size(), |x, y| (width - 1 - x, y))
+    }
+
+    /// Mirror the image top to bottom.
+    pub fn flip_vertical(&self) -> Image {
+        let height = self.height();
+        self.remap(self.size(), |x, y| (x, height - 1 - y))
+    }
+
+    /// Copy a part of the image into a new image.
+    /// The rect is clipped to the image.
+    pub fn crop(&self, rect: ScreenRect) -> Image {
+        self.view(rect).to_image()
+    }
+
+    /// Add a border around the image, filled with `pixel`.
+    pub fn pad(&self, left: u32, top: u32, right: u32, bottom: u32, pixel: Pixel) -> Image {
+        let size = ScreenSize::new(
+            self.width() + left + right,
+            self.height() + top + bottom,
+        );
+        let mut image = Image::new(size, pixel);
+        image.copy_from(self, self.rect(), ScreenPos::new(left, top));
+        image
+    }
+
+    /// Scale the image to a new size.
+    pub fn resize(&self, size: ScreenSize, filter: ResizeFilter) -> Image {
+        if self.size().is_empty() {
+            return Image::empty(size);
+        }
+
+        match filter {
+            ResizeFilter::Nearest => self.resize_nearest(size),
+            ResizeFilter::Bilinear => self.resize_bilinear(size),
+            ResizeFilter::Box => self.resize_box(size),
+        }
+    }
+
+    fn resize_nearest(&self, size: ScreenSize) -> Image {
+        let (width, height) = (self.width() as u64, self.height() as u64);
+        let (new_width, new_height) = (size.width as u64, size.height as u64);
+
+        self.remap(size, |x, y| {
+            // Sample at the centre of the destination pixel
+            let sx = (2 * x as u64 * width + width) / (2 * new_width);
+            let sy = (2 * y as u64 * height + height) / (2 * new_height);
+            (sx.min(width - 1) as u32, sy.min(height - 1) as u32)
+        })
+    }
+
+    fn resize_bilinear(&self, size: ScreenSize) -> Image {
+        let scale_x = self.width() as f32 / size.width as f32;
+        let scale_y = self.height() as f32 / size.height as f32;
+        let max_x = (self.width() - 1) as f32;
+        let max_y = (self.height() - 1) as f32;
+
+        let mut image = Image::empty(size);
+        for pos in image.coords() {
+            let sx = ((pos.x as f32 + 0.5) * scale_x - 0.5).max(0.0).min(max_x);
+            let sy = ((pos.y as f32 + 0.5) * scale_y - 0.5).max(0.0).min(max_y);
+
+            let (x0, y0) = (sx.floor() as u32, sy.floor() as u32);
+            let (x1, y1) = (sx.ceil() as u32, sy.ceil() as u32);
+            let (tx, ty) = (sx.fract(), sy.fract());
+
+            let samples = [
+                (self.pixel(x0, y0), (1.0 - tx) * (1.0 - ty)),
+                (self.pixel(x1, y0), tx * (1.0 - ty)),
+                (self.pixel(x0, y1), (1.0 - tx) * ty),
+                (self.pixel(x1, y1), tx * ty),
+            ];
+
+            image.set(pos.x, pos.y, weighted_average(&samples));
+        }
+
+        image
+    }
+
+    fn resize_box(&self, size: ScreenSize) -> Image {
+        let scale_x = self.width() as f32 / size.width as f32;
+        let scale_y = self.height() as f32 / size.height as f32;
+
+        let mut image = Image::empty(size);
+        for pos in image.coords() {
+            let x0 = (pos.x as f32 * scale_x).floor() as u32;
+            let y0 = (pos.y as f32 * scale_y).floor() as u32;
+            let x1 = (((pos.x + 1) as f32 * scale_x).ceil() as u32).clamp(x0 + 1, self.width());
+            let y1 = (((pos.y + 1) as f32 * scale_y).ceil() as u32).clamp(y0 + 1, self.height());
+
+            let samples = (y0..y1)
+                .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+                .map(|(x, y)| (self.pixel(x, y), 1.0))
+                .collect::<Vec<_>>();
+
+            image.set(pos.x, pos.y, weighted_average(&samples));
+        }
+
+        image
+    }
+
+    // Build a new image where every pixel is looked up in this image
+    // through `f`, which maps destination coordinates to source coordinates.
+    fn remap(&self, size: ScreenSize, f: impl Fn(u32, u32) -> (u32, u32)) -> Image {
+        let mut image = Image::empty(size);
+        for pos in image.coords() {
+            let (x, y) = f(pos.x, pos.y);
+            image.set(pos.x, pos.y, self.pixel(x, y));
+        }
+        image
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        self.get(x, y).unwrap_or_else(Pixel::zero)
+    }
+}
+
+fn weighted_average(samples: &[(Pixel, f32)]) -> Pixel {
+    let total = samples.iter().map(|(_, w)| w).sum::<f32>();
+    if total <= 0.0 {
+        return Pixel::zero();
+    }
+
+    let channel = |f: fn(&Pixel) -> u8| {
+        let sum = samples.iter().map(|(p, w)| f(p) as f32 * w).sum::<f32>();
+        (sum / total).round().clamp(0.0, 255.0) as u8
+    };
+
+    Pixel::new(channel(|p| p.r), channel(|p| p.g), channel(|p| p.b), channel(|p| p.a))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> Image {
+        let pixels = (0..width * height)
+            .map(|i| Pixel::new(i as u8, 0, 0, 255))
+            .collect();
+        Image::from_pixels(ScreenSize::new(width, height), pixels)
+    }
+
+    #[test]
+    fn rotate_quarter_turns() {
+        let image = gradient(3, 2);
+        let rotated = image.rotate90();
+
+        assert_eq!(rotated.size(), ScreenSize::new(2, 3));
+        // The bottom left corner ends up in the top left
+        assert_eq!(rotated.get(0, 0), image.get(0, 1));
+        assert_eq!(rotated.get(1, 0), image.get(0, 0));
+
+        assert_eq!(rotated.rotate90(), image.rotate180());
+        assert_eq!(rotated.rotate180(), image.rotate270());
+        assert_eq!(image.rotate270().rotate90(), image);
+    }
+
+    #[test]
+    fn arbitrary_rotation_matches_quarter_turns() {
+        let image = gradient(4, 3);
+        assert_eq!(image.rotate(90.0, Pixel::zero()), image.rotate90());
+        assert_eq!(image.rotate(180.0, Pixel::zero()), image.rotate180());
+        assert_eq!(image.rotate(0.0, Pixel::zero()), image);
+    }
+
+    #[test]
+    fn arbitrary_rotation_grows_and_fills() {
+        let image = Image::new(ScreenSize::new(10, 10), Pixel::new(1, 2, 3, 255));
+        let background = Pixel::new(9, 9, 9, 255);
+        let rotated = image.rotate(45.0, background);
+
+        assert_eq!(rotated.size(), ScreenSize::new(15, 15));
+        assert_eq!(rotated.get(0, 0), Some(background));
+        assert_eq!(rotated.get(7, 7), Some(Pixel::new(1, 2, 3, 255)));
+    }
+
+    #[test]
+    fn flips() {
+        let image = gradient(3, 2);
+        assert_eq!(image.flip_horizontal().get(0, 0), image.get(2, 0));
+        assert_eq!(image.flip_vertical().get(0, 0), image.get(0, 1));
+        assert_eq!(image.flip_horizontal().flip_vertical(), image.rotate180());
+    }
+
+    #[test]
+    fn crop_and_pad() {
+        let image = gradient(4, 4);
+        let cropped = image.crop(ScreenRect::new(ScreenPos::new(1, 1), ScreenSize::new(2, 2)));
+        assert_eq!(cropped.size(), ScreenSize::new(2, 2));
+        assert_eq!(cropped.get(0, 0), image.get(1, 1));
+
+        let border = Pixel::new(0, 0, 0, 255);
+        let padded = cropped.pad(1, 2, 3, 4, border);
+        assert_eq!(padded.size(), ScreenSize::new(6, 8));
+        assert_eq!(padded.get(0, 0), Some(border));
+        assert_eq!(padded.get(1, 2), image.get(1, 1));
+        assert_eq!(padded.crop(ScreenRect::new(ScreenPos::new(1, 2), cropped.size())), cropped);
+    }
+
+    #[test]
+    fn resize_nearest_duplicates_pixels() {
+        let image = gradient(2, 2);
+        let resized = image.resize(ScreenSize::new(4, 4), ResizeFilter::Nearest);
+
+        assert_eq!(resized.get(0, 0), image.get(0, 0));
+        assert_eq!(resized.get(1, 1), image.get(0, 0));
+        assert_eq!(resized.get(3, 0), image.get(1, 0));
+        assert_eq!(resized.get(2, 3), image.get(1, 1));
+        assert_eq!(resized.resize(image.size(), ResizeFilter::Nearest), image);
+    }
+
+    #[test]
+    fn resize_bilinear_blends() {
+        let pixels = vec![Pixel::new(0, 0, 0, 255), Pixel::new(200, 0, 0, 255)];
+        let image = Image::from_pixels(ScreenSize::new(2, 1), pixels);
+        let resized = image.resize(ScreenSize::new(4, 1), ResizeFilter::Bilinear);
+
+        let reds = resized.pixels().iter().map(|p| p.r).collect::<Vec<_>>();
+        assert_eq!(reds, vec![0, 50, 150, 200]);
+    }
+
+    #[test]
+    fn resize_box_averages() {
+        let pixels = vec![
+            Pixel::new(0, 0, 0, 255),
+            Pixel::new(100, 0, 0, 255),
+            Pixel::new(200, 0, 0, 255),
+            Pixel::new(100, 0, 0, 255),
+        ];
+        let image = Image::from_pixels(ScreenSize::new(2, 2), pixels);
+        let resized = image.resize(ScreenSize::new(1, 1), ResizeFilter::Box);
+
+        assert_eq!(resized.get(0, 0), Some(Pixel::new(100, 0, 0, 255)));
+    }
+}
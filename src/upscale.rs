@@ -0,0 +1,348 @@
+use crate::{Image, Pixel, ScreenSize};
+
+/// Pixel-art upscaling filters.
+/// These smooth out diagonal edges while keeping flat areas sharp,
+/// which looks better than plain nearest scaling at large window sizes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Upscaler {
+    /// Scale2x, also known as EPX. Doubles the size.
+    Scale2x,
+    /// Scale3x. Triples the size.
+    Scale3x,
+    /// Eagle. Doubles the size.
+    Eagle,
+    /// A basic 2xBR, which blends along detected edges. Doubles the size.
+    Xbr,
+}
+
+impl Upscaler {
+    /// How many times larger the output is than the input, on each axis.
+    pub fn factor(&self) -> u32 {
+        match self {
+            Upscaler::Scale3x => 3,
+            Upscaler::Scale2x | Upscaler::Eagle | Upscaler::Xbr => 2,
+        }
+    }
+
+    /// Upscale an image.
+    pub fn apply(&self, image: &Image) -> Image {
+        let mut output = Image::empty(ScreenSize::zero());
+        self.apply_into(image, &mut output);
+        output
+    }
+
+    /// Upscale an image into `output`, reusing its memory if it already has
+    /// the right size.
+    pub fn apply_into(&self, image: &Image, output: &mut Image) {
+        match self {
+            Upscaler::Scale2x => upscale_into(image, 2, output, scale2x_pixels),
+            Upscaler::Scale3x => upscale_into(image, 3, output, scale3x_pixels),
+            Upscaler::Eagle => upscale_into(image, 2, output, eagle_pixels),
+            Upscaler::Xbr => upscale_into(image, 2, output, xbr_pixels),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Neighbourhood -
+// -----------------------------------------------------------------------------
+// Read pixels relative to a centre pixel.
+// Anything outside of the image is clamped to the closest edge pixel.
+struct Neighbours<'a> {
+    image: &'a Image,
+    x: i64,
+    y: i64,
+}
+
+impl<'a> Neighbours<'a> {
+    fn at(&self, dx: i64, dy: i64) -> Pixel {
+        let x = (self.x + dx).clamp(0, self.image.width() as i64 - 1);
+        let y = (self.y + dy).clamp(0, self.image.height() as i64 - 1);
+        self.image.get(x as u32, y as u32).unwrap_or_else(Pixel::zero)
+    }
+}
+
+// Run `f` for every source pixel and write the block of pixels
+// it returns into the upscaled image.
+fn upscale<const N: usize>(
+    image: &Image,
+    factor: u32,
+    f: impl Fn(&Neighbours) -> [Pixel; N],
+) -> Image {
+    let mut output = Image::empty(ScreenSize::zero());
+    upscale_into(image, factor, &mut output, f);
+    output
+}
+
+fn upscale_into<const N: usize>(
+    image: &Image,
+    factor: u32,
+    output: &mut Image,
+    f: impl Fn(&Neighbours) -> [Pixel; N],
+) {
+    let size = ScreenSize::new(image.width() * factor, image.height() * factor);
+    if output.size() != size {
+        *output = Image::empty(size);
+    }
+
+    for pos in image.coords() {
+        let neighbours = Neighbours {
+            image,
+            x: pos.x as i64,
+            y: pos.y as i64,
+        };
+
+        for (i, pixel) in f(&neighbours).iter().enumerate() {
+            let x = pos.x * factor + i as u32 % factor;
+            let y = pos.y * factor + i as u32 / factor;
+            output.set(x, y, *pixel);
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Scale2x / EPX -
+// -----------------------------------------------------------------------------
+/// Scale2x (EPX).
+pub fn scale2x(image: &Image) -> Image {
+    upscale(image, 2, scale2x_pixels)
+}
+
+fn scale2x_pixels(n: &Neighbours) -> [Pixel; 4] {
+    let e = n.at(0, 0);
+    let b = n.at(0, -1);
+    let d = n.at(-1, 0);
+    let f = n.at(1, 0);
+    let h = n.at(0, 1);
+
+    if b == h || d == f {
+        return [e; 4];
+    }
+
+    [
+        if d == b { d } else { e },
+        if b == f { f } else { e },
+        if d == h { d } else { e },
+        if h == f { f } else { e },
+    ]
+}
+
+// -----------------------------------------------------------------------------
+//     - Scale3x -
+// -----------------------------------------------------------------------------
+/// Scale3x.
+pub fn scale3x(image: &Image) -> Image {
+    upscale(image, 3, scale3x_pixels)
+}
+
+fn scale3x_pixels(n: &Neighbours) -> [Pixel; 9] {
+    let a = n.at(-1, -1);
+    let b = n.at(0, -1);
+    let c = n.at(1, -1);
+    let d = n.at(-1, 0);
+    let e = n.at(0, 0);
+    let f = n.at(1, 0);
+    let g = n.at(-1, 1);
+    let h = n.at(0, 1);
+    let i = n.at(1, 1);
+
+    if b == h || d == f {
+        return [e; 9];
+    }
+
+    let pick = |edge: bool, pixel: Pixel| if edge { pixel } else { e };
+
+    [
+        pick(d == b, d),
+        pick((d == b && e != c) || (b == f && e != a), b),
+        pick(b == f, f),
+        pick((d == b && e != g) || (d == h && e != a), d),
+        e,
+        pick((b == f && e != i) || (h == f && e != c), f),
+        pick(d == h, d),
+        pick((d == h && e != i) || (h == f && e != g), h),
+        pick(h == f, f),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+//     - Eagle -
+// -----------------------------------------------------------------------------
+/// Eagle.
+pub fn eagle(image: &Image) -> Image {
+    upscale(image, 2, eagle_pixels)
+}
+
+fn eagle_pixels(n: &Neighbours) -> [Pixel; 4] {
+    let c = n.at(0, 0);
+    let corner = |dx: i64, dy: i64| {
+        let diagonal = n.at(dx, dy);
+        if n.at(dx, 0) == diagonal && n.at(0, dy) == diagonal {
+            diagonal
+        } else {
+            c
+        }
+    };
+
+    [corner(-1, -1), corner(1, -1), corner(-1, 1), corner(1, 1)]
+}
+
+// -----------------------------------------------------------------------------
+//     - xBR -
+// -----------------------------------------------------------------------------
+/// A basic 2xBR.
+/// Each output corner is blended with a neighbour if an edge runs across it.
+pub fn xbr(image: &Image) -> Image {
+    upscale(image, 2, xbr_pixels)
+}
+
+fn xbr_pixels(n: &Neighbours) -> [Pixel; 4] {
+    [
+        xbr_corner(n, -1, -1),
+        xbr_corner(n, 1, -1),
+        xbr_corner(n, -1, 1),
+        xbr_corner(n, 1, 1),
+    ]
+}
+
+// The rule is written for the bottom right corner.
+// Other corners are handled by mirroring the neighbourhood with `sx` and `sy`.
+fn xbr_corner(n: &Neighbours, sx: i64, sy: i64) -> Pixel {
+    let at = |dx: i64, dy: i64| n.at(dx * sx, dy * sy);
+
+    let e = at(0, 0);
+    let f = at(1, 0);
+    let h = at(0, 1);
+
+    if e == f || e == h {
+        return e;
+    }
+
+    let b = at(0, -1);
+    let c = at(1, -1);
+    let d = at(-1, 0);
+    let g = at(-1, 1);
+    let i = at(1, 1);
+    let f4 = at(2, 0);
+    let i4 = at(2, 1);
+    let h5 = at(0, 2);
+    let i5 = at(1, 2);
+
+    let across = distance(e, c)
+        + distance(e, g)
+        + distance(i, f4)
+        + distance(i, h5)
+        + 4 * distance(h, f);
+    let along = distance(h, d)
+        + distance(h, i5)
+        + distance(f, i4)
+        + distance(f, b)
+        + 4 * distance(e, i);
+
+    if across >= along {
+        return e;
+    }
+
+    let other = if distance(e, f) <= distance(e, h) { f } else { h };
+    blend(e, other)
+}
+
+// Weighted distance between two colours in YUV space
+fn distance(a: Pixel, b: Pixel) -> u32 {
+    let (ay, au, av) = yuv(a);
+    let (by, bu, bv) = yuv(b);
+    (48.0 * (ay - by).abs() + 7.0 * (au - bu).abs() + 6.0 * (av - bv).abs()) as u32
+}
+
+fn yuv(p: Pixel) -> (f32, f32, f32) {
+    let (r, g, b) = (p.r as f32, p.g as f32, p.b as f32);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    (y, 0.492 * (b - y), 0.877 * (r - y))
+}
+
+fn blend(a: Pixel, b: Pixel) -> Pixel {
+    let mix = |a: u8, b: u8| (a as u16 + b as u16).div_ceil(2) as u8;
+    Pixel::new(mix(a.r, b.r), mix(a.g, b.g), mix(a.b, b.b), mix(a.a, b.a))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const W: Pixel = Pixel::new(255, 255, 255, 255);
+    const K: Pixel = Pixel::new(0, 0, 0, 255);
+
+    fn image(rows: &[&str]) -> Image {
+        let pixels = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| if c == '#' { K } else { W }))
+            .collect();
+        Image::from_pixels(ScreenSize::new(rows[0].len() as u32, rows.len() as u32), pixels)
+    }
+
+    #[test]
+    fn flat_images_stay_flat() {
+        let flat = Image::new(ScreenSize::new(3, 2), K);
+
+        for upscaler in &[Upscaler::Scale2x, Upscaler::Scale3x, Upscaler::Eagle, Upscaler::Xbr] {
+            let factor = upscaler.factor();
+            let expected = Image::new(ScreenSize::new(3 * factor, 2 * factor), K);
+            assert_eq!(upscaler.apply(&flat), expected);
+        }
+    }
+
+    #[test]
+    fn apply_into_matches_apply() {
+        let src = image(&["#.", ".#"]);
+        let mut output = Image::empty(ScreenSize::new(1, 1));
+
+        for upscaler in &[Upscaler::Scale2x, Upscaler::Scale3x, Upscaler::Eagle, Upscaler::Xbr] {
+            upscaler.apply_into(&src, &mut output);
+            assert_eq!(output, upscaler.apply(&src));
+        }
+    }
+
+    #[test]
+    fn scale2x_rounds_diagonals() {
+        let src = image(&["#.", ".#"]);
+        let scaled = scale2x(&src);
+
+        assert_eq!(scaled.size(), ScreenSize::new(4, 4));
+        // The white top right pixel gets its bottom left quarter filled in
+        // towards the black diagonal, while the corners stay white.
+        assert_eq!(scaled.get(2, 1), Some(K));
+        assert_eq!(scaled.get(3, 0), Some(W));
+    }
+
+    #[test]
+    fn scale3x_keeps_centre_pixel() {
+        let src = image(&["#..", ".#.", "..#"]);
+        let scaled = scale3x(&src);
+
+        assert_eq!(scaled.size(), ScreenSize::new(9, 9));
+        for pos in src.coords() {
+            assert_eq!(scaled.get(pos.x * 3 + 1, pos.y * 3 + 1), src.get(pos.x, pos.y));
+        }
+    }
+
+    #[test]
+    fn eagle_fills_corners() {
+        let src = image(&["##", "#."]);
+        let scaled = eagle(&src);
+
+        assert_eq!(scaled.get(2, 2), Some(K));
+        assert_eq!(scaled.get(3, 3), Some(W));
+    }
+
+    #[test]
+    fn xbr_blends_edges() {
+        let src = image(&["#...", "##..", "###.", "####"]);
+        let scaled = xbr(&src);
+        let grey = blend(W, K);
+
+        assert_eq!(scaled.size(), ScreenSize::new(8, 8));
+        assert!(scaled.pixels().contains(&grey));
+        assert_eq!(scaled.get(0, 7), Some(K));
+        assert_eq!(scaled.get(7, 0), Some(W));
+    }
+}
@@ -1,6 +1,6 @@
-use std::mem::swap;
-
-use crate::{Pixel, PixelBuffer, ScreenPos, ScreenSize};
+use crate::{
+    ImageView, IndexedImage, Pixel, PixelBuffer, ScreenPos, ScreenRect, ScreenSize, Vec2D,
+};
 
 /// Represents a drawable area on screen.
 pub struct Viewport {
@@ -11,30 +11,102 @@ pub struct Viewport {
     /// The size of the viewport. Should probably match the size of the camera
     /// that is used with this viewport.
     pub size: ScreenSize,
+    /// What is drawn this frame, the size of the canvas.
     pub new_buf: PixelBuffer,
+    // What was drawn on screen last frame, the size of `presented`.
     old_buf: PixelBuffer,
+    // Where the viewport was on screen last frame
+    presented: ScreenRect,
     scale_factor: u32,
+    canvas: ScreenSize,
+    scroll: ScreenPos,
+    offscreen: bool,
 }
 
 impl Viewport {
     /// Create a new viewport with a given screen position.
     pub fn new(position: ScreenPos, size: ScreenSize) -> Self {
+        Self::with_canvas(position, size, size)
+    }
+
+    /// Create a viewport that draws onto a canvas larger than itself.
+    /// Only the part of the canvas at the scroll offset is shown (see [`Viewport::scroll_to`]).
+    /// The canvas is never smaller than the viewport.
+    pub fn with_canvas(position: ScreenPos, size: ScreenSize, canvas: ScreenSize) -> Self {
+        let canvas = canvas.max(size);
         Self {
             position,
             size,
-            new_buf: PixelBuffer::empty((size.width * size.height) as usize),
+            new_buf: PixelBuffer::empty((canvas.width * canvas.height) as usize),
             old_buf: PixelBuffer::empty((size.width * size.height) as usize),
+            presented: ScreenRect::new(position, size),
             scale_factor: 1,
+            canvas,
+            scroll: ScreenPos::zero(),
+            offscreen: false,
         }
     }
 
+    /// Create a viewport that is never shown on screen.
+    /// What is drawn to it is kept until it is cleared, so it can be drawn
+    /// once and then copied into other viewports with [`Viewport::blit`].
+    pub fn offscreen(size: ScreenSize) -> Self {
+        let mut viewport = Self::new(ScreenPos::zero(), size);
+        viewport.offscreen = true;
+        viewport
+    }
+
     /// Resize the viewport.
-    /// Remember to clear the renderer or residual
-    /// characters might remain.
+    /// A canvas larger than the new size is kept, otherwise the canvas
+    /// is resized along with the viewport.
+    /// Whatever the viewport no longer covers is cleared on the next frame,
+    /// as it is when the position changes.
     pub fn resize(&mut self, new_size: ScreenSize) {
+        let canvas = match self.canvas == self.size {
+            true => new_size,
+            false => self.canvas.max(new_size),
+        };
         self.size = ScreenSize::new(new_size.width, new_size.height);
-        self.new_buf = PixelBuffer::empty((new_size.width * new_size.height) as usize);
-        self.old_buf = PixelBuffer::empty((new_size.width * new_size.height) as usize);
+        self.resize_canvas(canvas);
+    }
+
+    /// Resize the canvas, clearing it. The canvas is never smaller than the viewport.
+    pub fn resize_canvas(&mut self, canvas: ScreenSize) {
+        self.canvas = canvas.max(self.size);
+        self.new_buf = PixelBuffer::empty((self.canvas.width * self.canvas.height) as usize);
+        self.scroll_to(self.scroll);
+    }
+
+    pub fn canvas_size(&self) -> ScreenSize {
+        self.canvas
+    }
+
+    pub fn is_offscreen(&self) -> bool {
+        self.offscreen
+    }
+
+    /// The top left corner of the part of the canvas that is shown.
+    pub fn scroll(&self) -> ScreenPos {
+        self.scroll
+    }
+
+    /// Scroll the canvas so `pos` is in the top left corner.
+    /// The viewport never scrolls past the edges of the canvas.
+    pub fn scroll_to(&mut self, pos: ScreenPos) {
+        let max = self.canvas - self.size;
+        self.scroll = ScreenPos::new(pos.x.min(max.width), pos.y.min(max.height));
+    }
+
+    /// Scroll the canvas by `offset` pixels.
+    pub fn scroll_by(&mut self, offset: Vec2D<i32>) {
+        let x = (self.scroll.x as i32 + offset.x).max(0);
+        let y = (self.scroll.y as i32 + offset.y).max(0);
+        self.scroll_to(ScreenPos::new(x as u32, y as u32));
+    }
+
+    /// The part of the canvas that is shown, in canvas coordinates.
+    pub fn visible(&self) -> ScreenRect {
+        ScreenRect::new(self.scroll, self.size)
     }
 
     /// Draw the pixels onto the renderable surface layers.
@@ -48,82 +120,212 @@ impl Viewport {
     /// Draw a single pixel onto the rendereable surface layers.
     /// This is called from `draw_pixels` for each pixel.
     pub fn draw_pixel(&mut self, pixel: Pixel, pos: ScreenPos) {
-        if self.in_view(pos) {
-            for x in 0..self.scale_factor {
-                for y in 0..self.scale_factor {
-                    let index = self.size.width * (pos.y + y) + pos.x + x;
+        for x in 0..self.scale_factor {
+            for y in 0..self.scale_factor {
+                // Scaled pixels are clipped too, rather than wrapping onto the next row
+                let pos = ScreenPos::new(pos.x + x, pos.y + y);
+                if self.in_view(pos) {
+                    let index = self.canvas.width * pos.y + pos.x;
                     self.new_buf.set_pixel(index as usize, pixel);
                 }
             }
         }
     }
 
-    /// Fill the entire viewport with one colour
+    /// Draw an image (or a part of one) with its top left corner at `pos`.
+    /// Transparent pixels are skipped.
+    pub fn draw_image<'a>(&mut self, image: impl Into<ImageView<'a>>, pos: ScreenPos) {
+        let image = image.into();
+        for (y, row) in image.rows().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                if pixel.a > 0 {
+                    self.draw_pixel(*pixel, ScreenPos::new(pos.x + x as u32, pos.y + y as u32));
+                }
+            }
+        }
+    }
+
+    /// Draw an indexed image with its top left corner at `pos`.
+    /// The palette is applied as the image is drawn.
+    pub fn draw_indexed(&mut self, image: &IndexedImage, pos: ScreenPos) {
+        for (offset, pixel) in image.pixels() {
+            if pixel.a > 0 {
+                self.draw_pixel(pixel, ScreenPos::new(pos.x + offset.x, pos.y + offset.y));
+            }
+        }
+    }
+
+    /// Copy part of another viewport's canvas, with its top left corner at `pos`.
+    /// Transparent pixels are skipped.
+    pub fn blit(&mut self, source: &Viewport, src: ScreenRect, pos: ScreenPos) {
+        let src = match src.intersection(&ScreenRect::from_size(source.canvas)) {
+            Some(src) => src,
+            None => return,
+        };
+
+        for y in 0..src.size.height {
+            for x in 0..src.size.width {
+                let pixel = source.get(ScreenPos::new(src.origin.x + x, src.origin.y + y));
+                match pixel {
+                    Some(pixel) if pixel.a > 0 => {
+                        self.draw_pixel(pixel, ScreenPos::new(pos.x + x, pos.y + y))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// The pixel drawn at a canvas position this frame.
+    pub fn get(&self, pos: ScreenPos) -> Option<Pixel> {
+        if !self.in_view(pos) {
+            return None;
+        }
+        let index = self.canvas.width * pos.y + pos.x;
+        self.new_buf.inner.get(index as usize).copied()
+    }
+
+    /// Fill the entire canvas with one colour
     pub fn fill(&mut self, pixel: Pixel) {
         self.new_buf.inner.iter_mut().for_each(|p| *p = pixel);
     }
 
+    /// Clear the canvas. Only needed for offscreen viewports,
+    /// as the others are cleared every frame.
+    pub fn clear(&mut self) {
+        self.new_buf.zero();
+    }
+
     /// Set the scale factor
     pub fn scale(&mut self, scale_factor: u32) {
         self.scale_factor = scale_factor;
     }
 
-    fn in_view(&self, pos: ScreenPos) -> bool {
-        pos.x < self.size.width && pos.y < self.size.height
+    /// The area the viewport covers on screen.
+    pub fn rect(&self) -> ScreenRect {
+        ScreenRect::new(self.position, self.size)
     }
 
-    fn offset(&self, pos: ScreenPos) -> ScreenPos {
-        ScreenPos::new(pos.x + self.position.x, pos.y + self.position.y)
+    /// Where the viewport was on screen as of the last call to `pixels`.
+    pub(crate) fn presented_rect(&self) -> ScreenRect {
+        self.presented
     }
 
-    fn index_to_coords(&self, index: usize) -> ScreenPos {
-        let x = index as u32 % self.size.width;
-        let y = index as u32 / self.size.width;
+    /// The pixel at a screen position as of the last call to `pixels`.
+    pub(crate) fn presented(&self, pos: ScreenPos) -> Option<Pixel> {
+        if !self.presented.contains(pos) {
+            return None;
+        }
+        let local = pos - self.presented.origin.to_vector();
+        let index = self.presented.size.width * local.y + local.x;
+        self.old_buf.inner.get(index as usize).copied()
+    }
 
-        ScreenPos::new(x, y)
+    fn in_view(&self, pos: ScreenPos) -> bool {
+        pos.x < self.canvas.width && pos.y < self.canvas.height
     }
 
+    /// The pixels that changed on screen since the last call, including
+    /// clears for the area the viewport has moved away from.
     pub(crate) fn pixels(&mut self) -> Vec<(Pixel, ScreenPos)> {
+        if self.offscreen {
+            return Vec::new();
+        }
+
+        let pixels = match self.rect() == self.presented {
+            true => self.diff(),
+            false => self.diff_moved(),
+        };
+        self.new_buf.zero();
+
+        pixels
+    }
+
+    // Diff against the last frame, in place, when the viewport hasn't moved.
+    fn diff(&mut self) -> Vec<(Pixel, ScreenPos)> {
         let mut pixels = Vec::new();
+        if self.size.is_empty() {
+            return pixels;
+        }
 
-        for (new, old) in self
-            .new_buf
-            .inner
-            .iter()
-            .enumerate()
-            .zip(&self.old_buf.inner)
-        {
-            match (new, old) {
-                ((_, new), old) if new == old => {}
-                ((index, Pixel { a: 0, .. }), old_pixel) => {
-                    if old_pixel.a > 0 {
-                        let pos = self.offset(self.index_to_coords(index));
-                        pixels.push((Pixel::zero(), pos));
-                    }
+        let (width, canvas_width) = (self.size.width as usize, self.canvas.width as usize);
+        let (scroll, position) = (self.scroll, self.position);
+        let visible = self.new_buf.inner[scroll.y as usize * canvas_width..]
+            .chunks(canvas_width)
+            .map(|row| &row[scroll.x as usize..][..width]);
+        let presented = self.old_buf.inner.chunks_mut(width);
+
+        for (y, (new_row, old_row)) in visible.zip(presented).enumerate() {
+            for (x, (new, old)) in new_row.iter().zip(old_row.iter_mut()).enumerate() {
+                if let Some(pixel) = changed(*new, *old) {
+                    let pos = ScreenPos::new(position.x + x as u32, position.y + y as u32);
+                    pixels.push((pixel, pos));
                 }
-                ((index, pixel), _) => {
-                    let pos = self.offset(self.index_to_coords(index));
-                    pixels.push((*pixel, pos));
+                *old = *new;
+            }
+        }
+
+        pixels
+    }
+
+    // Diff in screen space against wherever the viewport was last frame.
+    fn diff_moved(&mut self) -> Vec<(Pixel, ScreenPos)> {
+        let mut pixels = Vec::new();
+        let rect = self.rect();
+        let presented = self.presented;
+        let old_buf = std::mem::replace(
+            &mut self.old_buf,
+            PixelBuffer::empty((rect.size.width * rect.size.height) as usize),
+        );
+        let old = |pos: ScreenPos| match presented.contains(pos) {
+            true => {
+                let local = pos - presented.origin.to_vector();
+                old_buf.inner[(presented.size.width * local.y + local.x) as usize]
+            }
+            false => Pixel::zero(),
+        };
+
+        for y in 0..rect.size.height {
+            for x in 0..rect.size.width {
+                let index = self.canvas.width * (self.scroll.y + y) + self.scroll.x + x;
+                let new = self.new_buf.inner[index as usize];
+                let pos = ScreenPos::new(rect.origin.x + x, rect.origin.y + y);
+                if let Some(pixel) = changed(new, old(pos)) {
+                    pixels.push((pixel, pos));
                 }
+                self.old_buf.inner[(rect.size.width * y + x) as usize] = new;
             }
         }
 
-        swap(&mut self.new_buf, &mut self.old_buf);
-        self.new_buf.zero();
+        // Clear what was left behind
+        for y in presented.min_y()..presented.max_y() {
+            for x in presented.min_x()..presented.max_x() {
+                let pos = ScreenPos::new(x, y);
+                if !rect.contains(pos) && old(pos).a > 0 {
+                    pixels.push((Pixel::zero(), pos));
+                }
+            }
+        }
 
+        self.presented = rect;
         pixels
     }
 }
 
+/// What to draw where a pixel changed from `old` to `new`, if anything.
+/// A transparent pixel clears what was there, but only if something was.
+fn changed(new: Pixel, old: Pixel) -> Option<Pixel> {
+    match (new, old) {
+        (new, old) if new == old => None,
+        (Pixel { a: 0, .. }, old) if old.a > 0 => Some(Pixel::zero()),
+        (Pixel { a: 0, .. }, _) => None,
+        (new, _) => Some(new),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::*;
-
-    fn camera(viewport: &Viewport) -> Camera<camera::NoLimit> {
-        let pos = WorldPos::new(30.0, 30.0);
-        Camera::from_viewport(pos, viewport)
-    }
 
     fn viewport() -> Viewport {
         let pos = ScreenPos::new(2, 2);
@@ -134,35 +336,147 @@ mod test {
     #[test]
     fn draw_corners() {
         let mut view = viewport();
-        let cam = camera(&view);
-
-        let min_x = cam.bounding_box.min_x();
-        let max_x = cam.bounding_box.max_x();
-        let min_y = cam.bounding_box.min_y();
-        let max_y = cam.bounding_box.max_y();
 
-        let a = WorldPos::new(min_x, min_y);
-        let b = WorldPos::new(max_x - 1.0, min_y);
-        let c = WorldPos::new(min_x, max_y - 1.0);
-        let d = WorldPos::new(max_x - 1.0, max_y - 1.0);
+        let a = Pixel::new(255, 0, 0, 255);
+        let b = Pixel::new(0, 255, 0, 255);
+        let c = Pixel::new(0, 0, 255, 255);
+        let d = Pixel::new(255, 255, 255, 255);
 
-        let positions = vec![a, b, c, d];
-        let glyphs = vec!['A', 'B', 'C', 'D'];
-        let pixels = positions
+        let positions = vec![
+            ScreenPos::new(0, 0),
+            ScreenPos::new(5, 0),
+            ScreenPos::new(0, 5),
+            ScreenPos::new(5, 5),
+        ];
+        let pixels = vec![a, b, c, d]
             .into_iter()
-            .zip(glyphs)
-            .map(|(p, g)| Pixel::new(g, cam.to_screen(p), None, None))
+            .zip(positions)
             .collect::<Vec<_>>();
 
         view.draw_pixels(pixels);
 
-        let a = Pixel::new('A', ScreenPos::new(2, 2), None, None);
-        let b = Pixel::new('B', ScreenPos::new(7, 2), None, None);
-        let c = Pixel::new('C', ScreenPos::new(2, 7), None, None);
-        let d = Pixel::new('D', ScreenPos::new(7, 7), None, None);
+        let drawn_pixels = view.pixels();
+
+        let expected = vec![
+            (a, ScreenPos::new(2, 2)),
+            (b, ScreenPos::new(7, 2)),
+            (c, ScreenPos::new(2, 7)),
+            (d, ScreenPos::new(7, 7)),
+        ];
+
+        assert_eq!(&drawn_pixels, &expected);
+    }
+
+    #[test]
+    fn draw_indexed_skips_transparent() {
+        let mut view = viewport();
+        let mut image = IndexedImage::from_indices(
+            ScreenSize::new(2, 1),
+            vec![0, 1],
+            crate::Palette::gameboy(),
+        );
+        image.transparent = Some(0);
+
+        view.draw_indexed(&image, ScreenPos::new(1, 1));
 
         let drawn_pixels = view.pixels();
+        assert_eq!(&drawn_pixels, &[(image.palette.get(1), ScreenPos::new(4, 3))]);
+    }
+
+    #[test]
+    fn scrolled_canvas() {
+        let red = Pixel::rgb(255, 0, 0);
+        let mut view = Viewport::with_canvas(
+            ScreenPos::new(1, 1),
+            ScreenSize::new(2, 2),
+            ScreenSize::new(4, 4),
+        );
+
+        view.draw_pixel(red, ScreenPos::new(3, 3));
+        assert!(view.pixels().is_empty());
+
+        // Scrolling past the edge stops at the edge
+        view.scroll_to(ScreenPos::new(10, 10));
+        assert_eq!(view.scroll(), ScreenPos::new(2, 2));
+        view.draw_pixel(red, ScreenPos::new(3, 3));
+        assert_eq!(view.pixels(), vec![(red, ScreenPos::new(2, 2))]);
+
+        view.scroll_by(Vec2D::new(-1, -5));
+        assert_eq!(view.scroll(), ScreenPos::new(1, 0));
+        view.draw_pixel(red, ScreenPos::new(3, 3));
+        assert_eq!(view.pixels(), vec![(Pixel::zero(), ScreenPos::new(2, 2))]);
+    }
+
+    #[test]
+    fn offscreen_blit() {
+        let red = Pixel::rgb(255, 0, 0);
+        let mut portrait = Viewport::offscreen(ScreenSize::new(2, 2));
+        portrait.draw_pixel(red, ScreenPos::new(1, 1));
+
+        // Offscreen viewports are not shown and keep what was drawn
+        assert!(portrait.pixels().is_empty());
+        assert_eq!(portrait.get(ScreenPos::new(1, 1)), Some(red));
+
+        let mut view = viewport();
+        let src = ScreenRect::new(ScreenPos::new(1, 0), ScreenSize::new(4, 4));
+        view.blit(&portrait, src, ScreenPos::new(3, 0));
+        assert_eq!(view.pixels(), vec![(red, ScreenPos::new(5, 3))]);
+    }
 
-        assert_eq!(&drawn_pixels, &[a, b, c, d]);
+    #[test]
+    fn moving_clears_vacated_pixels() {
+        let red = Pixel::rgb(255, 0, 0);
+        let mut view = Viewport::new(ScreenPos::new(0, 0), ScreenSize::new(2, 1));
+        view.fill(red);
+        view.pixels();
+
+        view.position = ScreenPos::new(1, 0);
+        view.fill(red);
+        // (1, 0) was red and still is, (0, 0) is left behind
+        assert_eq!(
+            view.pixels(),
+            vec![
+                (red, ScreenPos::new(2, 0)),
+                (Pixel::zero(), ScreenPos::new(0, 0))
+            ]
+        );
+
+        view.fill(red);
+        assert!(view.pixels().is_empty());
+    }
+
+    #[test]
+    fn resizing_clears_vacated_pixels() {
+        let red = Pixel::rgb(255, 0, 0);
+        let mut view = Viewport::new(ScreenPos::new(1, 1), ScreenSize::new(2, 2));
+        view.fill(red);
+        view.pixels();
+
+        view.resize(ScreenSize::new(1, 1));
+        view.fill(red);
+        let cleared = view.pixels();
+        assert_eq!(cleared.len(), 3);
+        assert!(cleared.iter().all(|(pixel, _)| *pixel == Pixel::zero()));
+        assert!(!cleared.contains(&(Pixel::zero(), ScreenPos::new(1, 1))));
+
+        // Growing again draws the new area
+        view.resize(ScreenSize::new(2, 1));
+        view.fill(red);
+        assert_eq!(view.pixels(), vec![(red, ScreenPos::new(2, 1))]);
+    }
+
+    #[test]
+    fn resize_keeps_larger_canvas() {
+        let mut view = Viewport::with_canvas(
+            ScreenPos::zero(),
+            ScreenSize::new(2, 2),
+            ScreenSize::new(8, 8),
+        );
+        view.resize(ScreenSize::new(4, 10));
+        assert_eq!(view.canvas_size(), ScreenSize::new(8, 10));
+
+        let mut view = viewport();
+        view.resize(ScreenSize::new(3, 3));
+        assert_eq!(view.canvas_size(), ScreenSize::new(3, 3));
     }
 }